@@ -0,0 +1,201 @@
+use super::button::{button_xml_path, load_button};
+use super::encoding::read_text_file;
+use super::models::{AssetIssue, AssetIssueKind, VcpDocument};
+use super::parser::parse_vcp;
+use super::paths::button_folders;
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Scans every skin in `skins/` and every folder in `Buttons/` below
+/// `vcp_root`, reporting each referenced file that is missing, unreadable or
+/// empty. Button folders no skin uses are checked too, since export copies
+/// them to the CNC as well.
+pub fn check_project_assets(vcp_root: &Path) -> Result<Vec<AssetIssue>, String> {
+    let mut scan = AssetScan::new(vcp_root);
+    scan.project(&[])?;
+    Ok(scan.issues)
+}
+
+/// Checks the images and buttons one skin refers to. `skin` names the skin in
+/// the returned issues; the document need not be saved yet.
+pub fn check_skin_assets(vcp_root: &Path, skin: &str, doc: &VcpDocument) -> Vec<AssetIssue> {
+    let mut scan = AssetScan::new(vcp_root);
+    scan.skin(skin, doc);
+    scan.issues
+}
+
+/// Every asset problem in the project and in the `skins` being exported,
+/// given by name and document, in one scan. The documents need not be saved
+/// yet; saved copies of them are not checked.
+pub fn check_export_assets(
+    vcp_root: &Path,
+    skins: &[(String, &VcpDocument)],
+) -> Result<Vec<AssetIssue>, String> {
+    let exported: Vec<String> = skins
+        .iter()
+        .map(|(skin, _)| skin.to_ascii_lowercase())
+        .collect();
+    let mut scan = AssetScan::new(vcp_root);
+    scan.project(&exported)?;
+    for (skin, doc) in skins {
+        scan.skin(skin, doc);
+    }
+    Ok(scan.issues)
+}
+
+/// The error an export that refuses to ship unusable assets returns.
+pub fn assets_error(issues: &[AssetIssue]) -> String {
+    let messages: Vec<&str> = issues.iter().map(|issue| issue.message.as_str()).collect();
+    format!(
+        "Project has {} missing or unusable asset(s): {}",
+        messages.len(),
+        messages.join("; ")
+    )
+}
+
+struct AssetScan<'a> {
+    vcp_root: &'a Path,
+    /// Button folders whose definition has already been checked
+    checked_buttons: HashSet<String>,
+    issues: Vec<AssetIssue>,
+}
+
+impl<'a> AssetScan<'a> {
+    fn new(vcp_root: &'a Path) -> Self {
+        AssetScan {
+            vcp_root,
+            checked_buttons: HashSet::new(),
+            issues: Vec::new(),
+        }
+    }
+
+    /// Checks the skins in `skins/`, other than those in `skip`, and every
+    /// folder in `Buttons/`.
+    fn project(&mut self, skip: &[String]) -> Result<(), String> {
+        for skin in files_with_extension(&self.vcp_root.join("skins"), "vcp")? {
+            let label = self.relative(&skin);
+            if skip.contains(&label.to_ascii_lowercase()) {
+                continue;
+            }
+            match read_text_file(&skin).and_then(|(content, _)| parse_vcp(&content)) {
+                Ok(doc) => self.skin(&label, &doc),
+                Err(e) => self.push(AssetIssueKind::Unreadable, &label, &label, e),
+            }
+        }
+
+        for name in button_folders(self.vcp_root)? {
+            let folder = format!("Buttons/{}", name);
+            self.button(&name, &folder);
+        }
+        Ok(())
+    }
+
+    fn skin(&mut self, skin: &str, doc: &VcpDocument) {
+        for image in &doc.images {
+            let path = normalize(&image.path);
+            self.file(&path, skin);
+        }
+        for button in &doc.buttons {
+            self.button(&button.name, skin);
+        }
+    }
+
+    /// Checks `Buttons/<name>/<name>.xml` and every image it names. The
+    /// definition's contents are only checked once, however many skins use it.
+    fn button(&mut self, name: &str, referenced_by: &str) {
+        let xml_path = match button_xml_path(self.vcp_root, name) {
+            Ok(path) => self.relative(&path),
+            Err(e) => {
+                let folder = format!("Buttons/{}", name);
+                return self.push(AssetIssueKind::Missing, &folder, referenced_by, e);
+            }
+        };
+        if !self.file(&xml_path, referenced_by) || !self.checked_buttons.insert(name.to_string()) {
+            return;
+        }
+
+        match load_button(self.vcp_root, name) {
+            Ok(definition) => {
+                for image in definition.referenced_images() {
+                    let path = format!("Buttons/{}/{}", name, normalize(image));
+                    self.file(&path, &xml_path);
+                }
+            }
+            Err(e) => self.push(AssetIssueKind::Unreadable, &xml_path, referenced_by, e),
+        }
+    }
+
+    /// Checks that `path` is a non-empty, readable file, recording an issue
+    /// otherwise.
+    fn file(&mut self, path: &str, referenced_by: &str) -> bool {
+        let full_path = self.vcp_root.join(path);
+        let problem = match fs::metadata(&full_path) {
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Some((AssetIssueKind::Missing, "File not found".to_string()))
+            }
+            Err(e) => Some((AssetIssueKind::Unreadable, e.to_string())),
+            Ok(metadata) if metadata.is_dir() => Some((
+                AssetIssueKind::Unreadable,
+                "Expected a file, found a folder".to_string(),
+            )),
+            Ok(metadata) if metadata.len() == 0 => {
+                Some((AssetIssueKind::Empty, "File is empty".to_string()))
+            }
+            Ok(_) => fs::File::open(&full_path)
+                .err()
+                .map(|e| (AssetIssueKind::Unreadable, e.to_string())),
+        };
+        match problem {
+            Some((kind, detail)) => {
+                self.push(kind, path, referenced_by, detail);
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn push(&mut self, kind: AssetIssueKind, path: &str, referenced_by: &str, detail: String) {
+        let issue = AssetIssue {
+            kind,
+            path: path.to_string(),
+            referenced_by: referenced_by.to_string(),
+            message: format!("{} (used by {}): {}", path, referenced_by, detail),
+        };
+        if !self.issues.contains(&issue) {
+            self.issues.push(issue);
+        }
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        let path = path.strip_prefix(self.vcp_root).unwrap_or(path);
+        normalize(&path.to_string_lossy())
+    }
+}
+
+/// Forward slashes only, so Windows-style references resolve on every platform.
+fn normalize(path: &str) -> String {
+    let path = path.trim().replace('\\', "/");
+    path.strip_prefix("./").unwrap_or(&path).to_string()
+}
+
+fn files_with_extension(folder: &Path, extension: &str) -> Result<Vec<PathBuf>, String> {
+    if !folder.exists() {
+        return Ok(Vec::new());
+    }
+    let entries =
+        fs::read_dir(folder).map_err(|e| format!("Failed to read {}: {}", folder.display(), e))?;
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
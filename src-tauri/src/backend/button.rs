@@ -0,0 +1,262 @@
+use super::encoding::{decode_text, encode_text};
+use super::models::{ButtonExtra, ButtonRun, PlcInput, PlcOutput, VcpButton};
+use super::paths::check_file_name;
+use super::xml::{escape_text, line_column, parse_document, XmlElement, XmlNodeKind};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Button files use two-space indentation, matching the button editor preview
+const INDENT: &str = "  ";
+
+/// The elements this editor understands, in the order it writes them.
+const FIELDS: [&str; 7] = [
+    "skin_event_num",
+    "default_image",
+    "on_click_swap",
+    "run",
+    "app",
+    "plc_output",
+    "plc_input",
+];
+
+/// Folder of button `name` below a VCP root folder. The name must not reach
+/// outside `Buttons/`.
+pub fn button_folder(vcp_root: &Path, name: &str) -> Result<PathBuf, String> {
+    check_file_name(name, "button")?;
+    Ok(vcp_root.join("Buttons").join(name))
+}
+
+/// Location of the definition for button `name` below a VCP root folder.
+pub fn button_xml_path(vcp_root: &Path, name: &str) -> Result<PathBuf, String> {
+    Ok(button_folder(vcp_root, name)?.join(format!("{}.xml", name)))
+}
+
+pub fn load_button(vcp_root: &Path, name: &str) -> Result<VcpButton, String> {
+    let bytes = fs::read(button_xml_path(vcp_root, name)?)
+        .map_err(|e| format!("Failed to read button XML: {}", e))?;
+    let (content, format) = decode_text(&bytes);
+    let mut button = parse_button_xml(&content)?;
+    button.name = name.to_string();
+    button.format = format;
+    Ok(button)
+}
+
+/// Writes `button` to `<button_folder>/<name>.xml`.
+pub fn save_button(button_folder: &Path, button: &VcpButton) -> Result<(), String> {
+    if button.name.is_empty() {
+        return Err("Button has no name".to_string());
+    }
+    check_file_name(&button.name, "button")?;
+    let xml_path = button_folder.join(format!("{}.xml", button.name));
+    fs::write(
+        &xml_path,
+        encode_text(&serialize_button_xml(button), &button.format),
+    )
+    .map_err(|e| format!("Failed to write button XML: {}", e))
+}
+
+/// Parses the contents of a button definition file. The returned button has
+/// no name, since that comes from the folder the file lives in.
+pub fn parse_button_xml(content: &str) -> Result<VcpButton, String> {
+    let tree = parse_document(content)?;
+    let root = tree.root().ok_or("No root element found in button XML")?;
+    if root.name != "vcp_button" {
+        return Err(error_at(
+            content,
+            root,
+            &format!("Expected root element <vcp_button>, found <{}>", root.name),
+        ));
+    }
+
+    let mut button = VcpButton::default();
+    // The last known element, which the comments and elements that follow
+    // it are written after
+    let mut after: Option<String> = None;
+    for node in &root.children {
+        let element = match &node.kind {
+            XmlNodeKind::Element(element) => element,
+            XmlNodeKind::Comment(_) => {
+                button.extra.push(ButtonExtra {
+                    after: after.clone(),
+                    markup: content[node.span.clone()].to_string(),
+                });
+                continue;
+            }
+            _ => continue,
+        };
+        if FIELDS.contains(&element.name.as_str()) {
+            after = Some(element.name.clone());
+        }
+
+        match element.name.as_str() {
+            "skin_event_num" => button.skin_event_num = optional_number(content, element)?,
+            "default_image" => button.default_image = non_empty(element),
+            "on_click_swap" => button.on_click_swap = non_empty(element),
+            "run" => button.run = parse_run(element),
+            "app" => button.app = non_empty(element),
+            "plc_output" => button.plc_output = Some(parse_plc_output(content, element)?),
+            "plc_input" => button.plc_input = Some(parse_plc_input(content, element)?),
+            _ => button.extra.push(ButtonExtra {
+                after: after.clone(),
+                markup: content[element.span.clone()].to_string(),
+            }),
+        }
+    }
+
+    Ok(button)
+}
+
+fn parse_run(element: &XmlElement) -> Option<ButtonRun> {
+    element.child_elements().find_map(|child| {
+        let value = non_empty(child)?;
+        match child.name.as_str() {
+            "line" => Some(ButtonRun::Line(value)),
+            "macro" => Some(ButtonRun::Macro(value)),
+            _ => None,
+        }
+    })
+}
+
+fn parse_plc_output(content: &str, element: &XmlElement) -> Result<PlcOutput, String> {
+    Ok(PlcOutput {
+        number: required_number(content, element, "number")?,
+        color_on: element
+            .child("color_on")
+            .and_then(non_empty)
+            .unwrap_or_else(|| "#EC1C24".to_string()),
+        color_off: element
+            .child("color_off")
+            .and_then(non_empty)
+            .unwrap_or_else(|| "#81151C".to_string()),
+        image_on: element.child("image_on").and_then(non_empty),
+        image_off: element.child("image_off").and_then(non_empty),
+    })
+}
+
+fn parse_plc_input(content: &str, element: &XmlElement) -> Result<PlcInput, String> {
+    Ok(PlcInput {
+        number: required_number(content, element, "number")?,
+        image_active: element.child("image_active").and_then(non_empty),
+        image_inactive: element.child("image_inactive").and_then(non_empty),
+    })
+}
+
+fn non_empty(element: &XmlElement) -> Option<String> {
+    Some(element.text()).filter(|text| !text.is_empty())
+}
+
+fn optional_number(content: &str, element: &XmlElement) -> Result<Option<i32>, String> {
+    match non_empty(element) {
+        Some(text) => text.parse().map(Some).map_err(|_| {
+            error_at(
+                content,
+                element,
+                &format!("Invalid number '{}' in <{}>", text, element.name),
+            )
+        }),
+        None => Ok(None),
+    }
+}
+
+fn required_number(content: &str, parent: &XmlElement, name: &str) -> Result<i32, String> {
+    let child = parent.child(name).ok_or_else(|| {
+        error_at(
+            content,
+            parent,
+            &format!("Missing <{}> in <{}>", name, parent.name),
+        )
+    })?;
+    optional_number(content, child)?.ok_or_else(|| {
+        error_at(
+            content,
+            child,
+            &format!("Empty <{}> in <{}>", name, parent.name),
+        )
+    })
+}
+
+fn error_at(content: &str, element: &XmlElement, message: &str) -> String {
+    let (line, column) = line_column(content, element.span.start);
+    format!(
+        "Button XML error at line {}, column {}: {}",
+        line, column, message
+    )
+}
+
+pub fn serialize_button_xml(button: &VcpButton) -> String {
+    let mut xml = String::from("<vcp_button>\n");
+    write_extras(&mut xml, button, None);
+
+    if let Some(skin_event_num) = button.skin_event_num {
+        write_field(&mut xml, 1, "skin_event_num", &skin_event_num.to_string());
+    }
+    write_extras(&mut xml, button, Some("skin_event_num"));
+    write_optional(&mut xml, 1, "default_image", &button.default_image);
+    write_extras(&mut xml, button, Some("default_image"));
+    write_optional(&mut xml, 1, "on_click_swap", &button.on_click_swap);
+    write_extras(&mut xml, button, Some("on_click_swap"));
+
+    if let Some(run) = &button.run {
+        let (kind, value) = match run {
+            ButtonRun::Line(value) => ("line", value),
+            ButtonRun::Macro(value) => ("macro", value),
+        };
+        xml.push_str(&format!("{}<run>\n", INDENT));
+        write_field(&mut xml, 2, kind, value);
+        xml.push_str(&format!("{}</run>\n", INDENT));
+    }
+    write_extras(&mut xml, button, Some("run"));
+
+    write_optional(&mut xml, 1, "app", &button.app);
+    write_extras(&mut xml, button, Some("app"));
+
+    if let Some(output) = &button.plc_output {
+        xml.push_str(&format!("{}<plc_output>\n", INDENT));
+        write_field(&mut xml, 2, "number", &output.number.to_string());
+        write_field(&mut xml, 2, "color_on", &output.color_on);
+        write_field(&mut xml, 2, "color_off", &output.color_off);
+        write_optional(&mut xml, 2, "image_on", &output.image_on);
+        write_optional(&mut xml, 2, "image_off", &output.image_off);
+        xml.push_str(&format!("{}</plc_output>\n", INDENT));
+    }
+    write_extras(&mut xml, button, Some("plc_output"));
+
+    if let Some(input) = &button.plc_input {
+        xml.push_str(&format!("{}<plc_input>\n", INDENT));
+        write_field(&mut xml, 2, "number", &input.number.to_string());
+        write_optional(&mut xml, 2, "image_active", &input.image_active);
+        write_optional(&mut xml, 2, "image_inactive", &input.image_inactive);
+        xml.push_str(&format!("{}</plc_input>\n", INDENT));
+    }
+    write_extras(&mut xml, button, Some("plc_input"));
+
+    xml.push_str("</vcp_button>");
+    xml
+}
+
+/// Writes the comments and unrecognised elements that follow `after`, even
+/// when that element itself is no longer written.
+fn write_extras(xml: &mut String, button: &VcpButton, after: Option<&str>) {
+    for extra in &button.extra {
+        if extra.after.as_deref() == after {
+            xml.push_str(&format!("{}{}\n", INDENT, extra.markup));
+        }
+    }
+}
+
+fn write_field(xml: &mut String, depth: usize, name: &str, value: &str) {
+    xml.push_str(&format!(
+        "{}<{}>{}</{}>\n",
+        INDENT.repeat(depth),
+        name,
+        escape_text(value),
+        name
+    ));
+}
+
+/// Writes `value` unless it is absent or empty; CNC12 treats both the same.
+fn write_optional(xml: &mut String, depth: usize, name: &str, value: &Option<String>) {
+    if let Some(value) = value.as_deref().filter(|value| !value.is_empty()) {
+        write_field(xml, depth, name, value);
+    }
+}
@@ -0,0 +1,271 @@
+use super::encoding::read_text_file;
+use super::events::BUILTIN_SKIN_EVENTS;
+use super::models::{
+    CatalogDiff, CatalogEntry, CatalogListDiff, PlcDefinitions, PlcSymbolKind, SymbolCatalog,
+};
+use super::plc::parse_plc_source;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Catalog file in the VCP root.
+pub const CATALOG_FILE: &str = ".vcp-catalog.json";
+
+/// Inputs of the stock Acorn VCP keys. Each key has an LED output with the
+/// same number, e.g. `SpinOverPlusLED IS OUT1057`.
+pub const BUILTIN_INPUTS: &[(i32, &str)] = &[
+    (1057, "SpinOverPlusKey"),
+    (1058, "SpinAutoManKey"),
+    (1059, "Aux1Key"),
+    (1060, "Aux2Key"),
+    (1061, "Aux3Key"),
+    (1062, "SpinOver100Key"),
+    (1063, "SpinCWKey"),
+    (1064, "Aux4Key"),
+    (1065, "Aux5Key"),
+    (1066, "Aux6Key"),
+    (1067, "SpinOverMinusKey"),
+    (1068, "SpinCCWKey"),
+    (1069, "Aux7Key"),
+    (1070, "Aux8Key"),
+    (1071, "Aux9Key"),
+    (1072, "SpinStopKey"),
+    (1073, "SpinStartKey"),
+    (1074, "Aux10Key"),
+    (1075, "Aux11Key"),
+    (1076, "Aux12Key"),
+    (1077, "CoolAutoManKey"),
+    (1078, "CoolFloodKey"),
+    (1079, "CoolMistKey"),
+    (1080, "VacOnKey"),
+    (1081, "LimitDefeatKey"),
+    (1082, "IncrContKey"),
+    (1083, "x1JogKey"),
+    (1084, "x10JogKey"),
+    (1085, "x100JogKey"),
+    (1086, "MPGKey"),
+    (1087, "Ax4PlusJogKey"),
+    (1088, "UnusedR7C2Key"),
+    (1089, "Ax2PlusJogKey"),
+    (1090, "UnusedR7C4Key"),
+    (1091, "Ax3PlusJogKey"),
+    (1092, "UnusedR8C1Key"),
+    (1093, "Ax1MinusJogKey"),
+    (1094, "FastSlowKey"),
+    (1095, "Ax1PlusJogKey"),
+    (1096, "UnusedR8C5Key"),
+    (1097, "Ax4MinusJogKey"),
+    (1098, "UnusedR9C2Key"),
+    (1099, "Ax2MinusJogKey"),
+    (1100, "UnusedR9C4Key"),
+    (1101, "Ax3MinusJogKey"),
+    (1102, "CycleCancelKey"),
+    (1103, "SingleBlockKey"),
+    (1104, "ToolCheckKey"),
+    (1105, "FeedHoldKey"),
+    (1106, "CycleStartKey"),
+];
+
+pub fn catalog_path(vcp_root: &Path) -> PathBuf {
+    vcp_root.join(CATALOG_FILE)
+}
+
+/// Reads the project's own catalog entries, empty when it has none yet.
+pub fn load_project_catalog(vcp_root: &Path) -> Result<SymbolCatalog, String> {
+    let path = catalog_path(vcp_root);
+    if !path.exists() {
+        return Ok(SymbolCatalog::default());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read catalog: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse catalog: {}", e))
+}
+
+pub fn save_project_catalog(vcp_root: &Path, catalog: &SymbolCatalog) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(catalog)
+        .map_err(|e| format!("Failed to serialize catalog: {}", e))?;
+    fs::write(catalog_path(vcp_root), content)
+        .map_err(|e| format!("Failed to write catalog: {}", e))
+}
+
+/// The symbols of the stock Acorn PLC program.
+pub fn builtin_catalog() -> SymbolCatalog {
+    let entry = |&(number, name): &(i32, &str)| CatalogEntry {
+        number,
+        name: name.to_string(),
+        label: label_for(name),
+    };
+    SymbolCatalog {
+        replaces_defaults: false,
+        skin_events: BUILTIN_SKIN_EVENTS.iter().map(entry).collect(),
+        outputs: BUILTIN_INPUTS
+            .iter()
+            .map(|&(number, name)| {
+                let name = format!("{}LED", name.strip_suffix("Key").unwrap_or(name));
+                CatalogEntry {
+                    number,
+                    label: label_for(&name),
+                    name,
+                }
+            })
+            .collect(),
+        inputs: BUILTIN_INPUTS.iter().map(entry).collect(),
+    }
+}
+
+/// The catalog the button editor offers for the project in `vcp_root`.
+pub fn project_catalog(vcp_root: &Path) -> Result<SymbolCatalog, String> {
+    Ok(merge_catalogs(
+        &builtin_catalog(),
+        &load_project_catalog(vcp_root)?,
+    ))
+}
+
+/// `project` laid over `defaults`: project entries replace defaults with the
+/// same number, and all lists come back sorted by number.
+pub fn merge_catalogs(defaults: &SymbolCatalog, project: &SymbolCatalog) -> SymbolCatalog {
+    let merge = |builtin: &[CatalogEntry], own: &[CatalogEntry]| {
+        let mut merged = BTreeMap::new();
+        if !project.replaces_defaults {
+            merged.extend(builtin.iter().map(|entry| (entry.number, entry.clone())));
+        }
+        for entry in own {
+            let mut entry = entry.clone();
+            if entry.label.is_empty() {
+                entry.label = label_for(&entry.name);
+            }
+            merged.insert(entry.number, entry);
+        }
+        merged.into_values().collect::<Vec<_>>()
+    };
+    SymbolCatalog {
+        replaces_defaults: project.replaces_defaults,
+        skin_events: merge(&defaults.skin_events, &project.skin_events),
+        outputs: merge(&defaults.outputs, &project.outputs),
+        inputs: merge(&defaults.inputs, &project.inputs),
+    }
+}
+
+/// Builds a catalog listing what a PLC program defines. Commented-out
+/// definitions are skipped, and where several symbols share a number the
+/// first one wins. Labels are kept from `previous` for unchanged symbols.
+pub fn catalog_from_plc(definitions: &PlcDefinitions, previous: &SymbolCatalog) -> SymbolCatalog {
+    let collect = |symbols: Vec<(i32, &str)>, previous: &[CatalogEntry]| {
+        let mut entries: BTreeMap<i32, CatalogEntry> = BTreeMap::new();
+        for (number, name) in symbols {
+            entries.entry(number).or_insert_with(|| CatalogEntry {
+                number,
+                name: name.to_string(),
+                label: previous
+                    .iter()
+                    .find(|entry| entry.number == number && entry.name == name)
+                    .map(|entry| entry.label.clone())
+                    .unwrap_or_else(|| label_for(name)),
+            });
+        }
+        entries.into_values().collect::<Vec<_>>()
+    };
+    let symbols = |kind: PlcSymbolKind| {
+        definitions
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.kind == kind && !symbol.commented)
+            .map(|symbol| (symbol.number, symbol.name.as_str()))
+            .collect::<Vec<_>>()
+    };
+    let skin_events = definitions
+        .skin_events
+        .iter()
+        .filter(|event| !event.commented)
+        .map(|event| (event.number, event.name.as_str()))
+        .collect();
+
+    SymbolCatalog {
+        replaces_defaults: true,
+        skin_events: collect(skin_events, &previous.skin_events),
+        outputs: collect(symbols(PlcSymbolKind::Out), &previous.outputs),
+        inputs: collect(symbols(PlcSymbolKind::Inp), &previous.inputs),
+    }
+}
+
+pub fn diff_catalogs(before: &SymbolCatalog, after: &SymbolCatalog) -> CatalogDiff {
+    CatalogDiff {
+        skin_events: diff_lists(&before.skin_events, &after.skin_events),
+        outputs: diff_lists(&before.outputs, &after.outputs),
+        inputs: diff_lists(&before.inputs, &after.inputs),
+    }
+}
+
+fn diff_lists(before: &[CatalogEntry], after: &[CatalogEntry]) -> CatalogListDiff {
+    let find = |list: &[CatalogEntry], number| list.iter().find(|e| e.number == number).cloned();
+    let mut diff = CatalogListDiff::default();
+    for entry in after {
+        match find(before, entry.number) {
+            None => diff.added.push(entry.clone()),
+            Some(old) if old.name != entry.name => diff.renamed.push((old, entry.clone())),
+            Some(_) => {}
+        }
+    }
+    for entry in before {
+        if find(after, entry.number).is_none() {
+            diff.removed.push(entry.clone());
+        }
+    }
+    diff
+}
+
+/// Replaces the project catalog with the symbols defined in `plc_source`,
+/// returning how the catalog the editor offers changes. With `apply` unset
+/// nothing is written, so the diff can be previewed first.
+pub fn refresh_catalog(
+    vcp_root: &Path,
+    plc_source: &Path,
+    apply: bool,
+) -> Result<CatalogDiff, String> {
+    let (content, _) = read_text_file(plc_source)?;
+    let definitions = parse_plc_source(&content);
+    if definitions.skin_events.is_empty() && definitions.symbols.is_empty() {
+        return Err("No skin event or I/O definitions found in PLC source".to_string());
+    }
+
+    let before = project_catalog(vcp_root)?;
+    let refreshed = catalog_from_plc(&definitions, &before);
+    let diff = diff_catalogs(&before, &merge_catalogs(&builtin_catalog(), &refreshed));
+    if apply {
+        save_project_catalog(vcp_root, &refreshed)?;
+    }
+    Ok(diff)
+}
+
+/// Readable label for a PLC symbol: `SkinCoolFlood_M` becomes `Cool Flood`.
+pub fn label_for(name: &str) -> String {
+    let core = name.strip_suffix("_M").unwrap_or(name);
+    let core = match core.strip_prefix("Skin") {
+        Some(rest) if !rest.is_empty() => rest,
+        _ => core,
+    };
+
+    let chars: Vec<char> = core.chars().collect();
+    let mut label = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            label.push(' ');
+            continue;
+        }
+        let previous = i.checked_sub(1).map(|p| chars[p]);
+        let next_is_lowercase = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+        let boundary = match previous {
+            // camelCase, a word after a number, or the last capital of an
+            // acronym as in `ATCIndex`
+            Some(p) if c.is_uppercase() => p.is_lowercase() || next_is_lowercase,
+            // Numbers after a word, but not in codes like `X10` or `R7C2`
+            Some(p) if c.is_ascii_digit() => p.is_lowercase(),
+            _ => false,
+        };
+        if boundary && !label.ends_with(' ') {
+            label.push(' ');
+        }
+        label.push(c);
+    }
+    label.trim().to_string()
+}
@@ -0,0 +1,240 @@
+use super::export::{export_project_into, ExportEntry};
+use super::import::check_entry_path;
+use super::manifest::verify_export;
+use super::models::{DeployReport, SerializeOptions};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Exports the project at `vcp_root` and deploys it to `target`, the `vcp`
+/// folder of a CNC as reached from here, such as a mounted share or a USB
+/// stick. See [`deploy_export`].
+pub fn deploy_project(
+    vcp_root: &Path,
+    cnc_base_path: &str,
+    target: &Path,
+    skins: &[ExportEntry],
+    options: Option<SerializeOptions>,
+) -> Result<DeployReport, String> {
+    if same_folder(vcp_root, target) {
+        return Err("Cannot deploy a project over itself".to_string());
+    }
+    // The zip is only needed until it is unpacked
+    let folder = staging_folder(target)?;
+    let export = export_project_into(folder.path(), vcp_root, cnc_base_path, skins, options)?;
+    let report = deploy_export(Path::new(&export.zip), target)?;
+    Ok(DeployReport {
+        asset_issues: export.asset_issues,
+        ..report
+    })
+}
+
+/// Replaces the `vcp` folder at `target` with the contents of an export zip.
+///
+/// The export is unpacked next to `target` and checked against its manifest
+/// first. Then the current folder is renamed to `<name>_backup_<timestamp>`
+/// and the new one renamed into place. CNC12 may find no folder at all for
+/// the moment between the two renames, but never a half-written one. Should
+/// the second rename fail, the backup is put back.
+pub fn deploy_export(zip_path: &Path, target: &Path) -> Result<DeployReport, String> {
+    if target.exists() && !target.is_dir() {
+        return Err(format!("{} is not a folder", target.display()));
+    }
+    let folder = staging_folder(target)?;
+    unpack(zip_path, folder.path())?;
+    let report = verify_export(folder.path())?;
+    if let Some(issue) = report.issues.first() {
+        return Err(format!(
+            "{} does not match the export's manifest, not deploying",
+            issue.path
+        ));
+    }
+
+    match_permissions(folder.path(), target)?;
+    let backup = switch(folder.path(), target)?;
+    Ok(DeployReport {
+        target: target.to_string_lossy().to_string(),
+        backup: backup.map(|path| path.to_string_lossy().to_string()),
+        restored: None,
+        asset_issues: Vec::new(),
+    })
+}
+
+/// Backups of `target` made by deploys and rollbacks, newest first.
+pub fn list_backups(target: &Path) -> Result<Vec<PathBuf>, String> {
+    let prefix = backup_prefix(target)?;
+    let parent = parent_of(target);
+    let entries =
+        fs::read_dir(parent).map_err(|e| format!("Failed to read {}: {}", parent.display(), e))?;
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read backups: {}", e))?;
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with(&prefix) && path.is_dir() {
+            backups.push(path);
+        }
+    }
+    // Timestamps in the names sort oldest first
+    backups.sort();
+    backups.reverse();
+    Ok(backups)
+}
+
+/// Puts a backup back in place of `target`: the one named `backup`, or the
+/// newest when `None`. The folder it replaces is backed up in turn, so a
+/// rollback can itself be rolled back.
+pub fn rollback_deploy(target: &Path, backup: Option<&str>) -> Result<DeployReport, String> {
+    let backups = list_backups(target)?;
+    let restored = match backup {
+        Some(backup) => {
+            // Either the backup's name or its path will do
+            let name = Path::new(backup).file_name().unwrap_or_default();
+            backups
+                .into_iter()
+                .find(|path| path.file_name() == Some(name))
+                .ok_or_else(|| format!("{} is not a backup of {}", backup, target.display()))?
+        }
+        None => backups
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("No backups of {} to roll back to", target.display()))?,
+    };
+
+    let backup = switch(&restored, target)?;
+    Ok(DeployReport {
+        target: target.to_string_lossy().to_string(),
+        backup: backup.map(|path| path.to_string_lossy().to_string()),
+        restored: Some(restored.to_string_lossy().to_string()),
+        asset_issues: Vec::new(),
+    })
+}
+
+/// Moves `target` aside to a new backup, if it exists, and `folder` into its
+/// place, returning the backup.
+pub(crate) fn switch(folder: &Path, target: &Path) -> Result<Option<PathBuf>, String> {
+    let backup = if target.exists() {
+        let backup = backup_path(target)?;
+        fs::rename(target, &backup)
+            .map_err(|e| format!("Failed to back up {}: {}", target.display(), e))?;
+        Some(backup)
+    } else {
+        None
+    };
+    if let Err(e) = fs::rename(folder, target) {
+        let Some(backup) = &backup else {
+            return Err(format!("Failed to move the new folder into place: {}", e));
+        };
+        return Err(match fs::rename(backup, target) {
+            Ok(()) => format!(
+                "Failed to move the new folder into place, {} is unchanged: {}",
+                target.display(),
+                e
+            ),
+            Err(restore) => format!(
+                "Failed to move the new folder into place: {}. Putting the old folder back \
+                 failed too ({}), it is still at {}",
+                e,
+                restore,
+                backup.display()
+            ),
+        });
+    }
+    Ok(backup)
+}
+
+/// A free `<name>_backup_<timestamp>` path next to `target`.
+fn backup_path(target: &Path) -> Result<PathBuf, String> {
+    let prefix = backup_prefix(target)?;
+    // Two backups in the same second get different names
+    let stamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let mut attempt = 1;
+    loop {
+        let name = match attempt {
+            1 => format!("{}{}", prefix, stamp),
+            _ => format!("{}{}_{}", prefix, stamp, attempt),
+        };
+        let path = target.with_file_name(name);
+        if !path.exists() {
+            return Ok(path);
+        }
+        attempt += 1;
+    }
+}
+
+fn backup_prefix(target: &Path) -> Result<String, String> {
+    let name = target
+        .file_name()
+        .ok_or_else(|| format!("{} is not a folder name", target.display()))?;
+    Ok(format!("{}_backup_", name.to_string_lossy()))
+}
+
+/// Gives `folder`, a staging folder that will take the place of `target`,
+/// the permissions of `target`, or those of a new folder when there is no
+/// `target` yet. Staging folders start out private to this user.
+pub(crate) fn match_permissions(folder: &Path, target: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let permissions = match fs::metadata(target) {
+            Ok(metadata) => metadata.permissions(),
+            Err(_) => fs::Permissions::from_mode(0o755),
+        };
+        fs::set_permissions(folder, permissions)
+            .map_err(|e| format!("Failed to set permissions of {}: {}", folder.display(), e))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (folder, target);
+    Ok(())
+}
+
+/// The folder holding `target`, where backups and staging folders go so
+/// they can be renamed into place.
+pub(crate) fn parent_of(target: &Path) -> &Path {
+    match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+fn staging_folder(target: &Path) -> Result<tempfile::TempDir, String> {
+    tempfile::Builder::new()
+        .prefix(".vcp_deploy_")
+        .tempdir_in(parent_of(target))
+        .map_err(|e| format!("Failed to create staging folder: {}", e))
+}
+
+/// Writes the files in the `vcp/` folder of an export zip into `folder`.
+fn unpack(zip_path: &Path, folder: &Path) -> Result<(), String> {
+    let file = fs::File::open(zip_path).map_err(|e| format!("Failed to open zip file: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip file: {}", e))?;
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read zip file: {}", e))?;
+        let name = entry.name().replace('\\', "/");
+        let Some(path) = name.strip_prefix("vcp/") else {
+            continue;
+        };
+        if entry.is_dir() || path.is_empty() {
+            continue;
+        }
+        check_entry_path(path)?;
+        let destination = folder.join(path);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let mut file = fs::File::create(&destination)
+            .map_err(|e| format!("Failed to create {}: {}", path, e))?;
+        io::copy(&mut entry, &mut file).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+fn same_folder(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
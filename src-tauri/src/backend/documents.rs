@@ -0,0 +1,115 @@
+use super::history::EditHistory;
+use super::models::{DocumentId, DocumentInfo, VcpDocument};
+use std::collections::BTreeMap;
+
+/// A skin open in the editor: its contents, where it is saved and how it
+/// got there.
+#[derive(Debug, Clone)]
+pub struct OpenDocument {
+    pub document: VcpDocument,
+    pub path: Option<String>,
+    pub history: EditHistory,
+    /// Label of the window editing the document
+    pub window: Option<String>,
+}
+
+impl OpenDocument {
+    pub fn is_dirty(&self) -> bool {
+        self.history.is_dirty()
+    }
+}
+
+/// Every document open in the editor, keyed by id. Ids are never reused, so
+/// a window holding the id of a closed document gets an error rather than
+/// someone else's skin.
+#[derive(Debug, Default)]
+pub struct DocumentRegistry {
+    last_id: DocumentId,
+    documents: BTreeMap<DocumentId, OpenDocument>,
+}
+
+impl DocumentRegistry {
+    /// Adds a document and returns its id. `path` is the file it was read
+    /// from, if any.
+    pub fn insert(
+        &mut self,
+        document: VcpDocument,
+        path: Option<String>,
+        window: Option<String>,
+    ) -> DocumentId {
+        self.last_id += 1;
+        self.documents.insert(
+            self.last_id,
+            OpenDocument {
+                document,
+                path,
+                history: EditHistory::default(),
+                window,
+            },
+        );
+        self.last_id
+    }
+
+    /// Puts a newly created or opened skin in place of document `id`, for a
+    /// window that moves on to another file.
+    pub fn replace(
+        &mut self,
+        id: DocumentId,
+        document: VcpDocument,
+        path: Option<String>,
+    ) -> Result<(), String> {
+        let entry = self.get_mut(id)?;
+        entry.document = document;
+        entry.path = path;
+        entry.history.reset(true);
+        Ok(())
+    }
+
+    pub fn get(&self, id: DocumentId) -> Result<&OpenDocument, String> {
+        self.documents
+            .get(&id)
+            .ok_or_else(|| format!("No open document with id {}", id))
+    }
+
+    pub fn get_mut(&mut self, id: DocumentId) -> Result<&mut OpenDocument, String> {
+        self.documents
+            .get_mut(&id)
+            .ok_or_else(|| format!("No open document with id {}", id))
+    }
+
+    pub fn close(&mut self, id: DocumentId) -> Option<OpenDocument> {
+        self.documents.remove(&id)
+    }
+
+    /// Closes the documents of a window that went away, returning their ids.
+    pub fn close_window(&mut self, window: &str) -> Vec<DocumentId> {
+        let ids: Vec<DocumentId> = self
+            .documents
+            .iter()
+            .filter(|(_, entry)| entry.window.as_deref() == Some(window))
+            .map(|(&id, _)| id)
+            .collect();
+        for id in &ids {
+            self.documents.remove(id);
+        }
+        ids
+    }
+
+    pub fn info(&self, id: DocumentId) -> Result<DocumentInfo, String> {
+        let entry = self.get(id)?;
+        Ok(DocumentInfo {
+            id,
+            path: entry.path.clone(),
+            dirty: entry.is_dirty(),
+            window: entry.window.clone(),
+        })
+    }
+
+    /// Open documents, oldest first.
+    pub fn list(&self) -> Vec<DocumentInfo> {
+        self.documents
+            .keys()
+            .filter_map(|&id| self.info(id).ok())
+            .collect()
+    }
+}
@@ -0,0 +1,129 @@
+use super::models::{FileFormat, LineEnding, TextEncoding};
+use encoding_rs::{UTF_16BE, UTF_16LE, WINDOWS_1252};
+use std::fs;
+use std::path::Path;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// Reads a skin or button file, detecting its encoding and line endings.
+pub fn read_text_file(path: &Path) -> Result<(String, FileFormat), String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(decode_text(&bytes))
+}
+
+/// Writes `text` back in `format`, converting line endings as needed.
+pub fn write_text_file(path: &Path, text: &str, format: &FileFormat) -> Result<(), String> {
+    fs::write(path, encode_text(text, format)).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Decodes file contents into text, returning the format they were stored in.
+///
+/// A byte order mark decides the encoding when present. Without one, UTF-16 is
+/// recognised by the NUL bytes around the leading `<`, valid UTF-8 is taken
+/// as-is unless the XML declaration names a Windows codepage, and anything
+/// else is read as Windows-1252, the "ANSI" codepage of Western Windows PCs.
+pub fn decode_text(bytes: &[u8]) -> (String, FileFormat) {
+    let (encoding, bom, body) = if let Some(body) = bytes.strip_prefix(UTF8_BOM) {
+        (TextEncoding::Utf8, true, body)
+    } else if let Some(body) = bytes.strip_prefix(UTF16LE_BOM) {
+        (TextEncoding::Utf16le, true, body)
+    } else if let Some(body) = bytes.strip_prefix(UTF16BE_BOM) {
+        (TextEncoding::Utf16be, true, body)
+    } else if bytes.starts_with(&[b'<', 0]) {
+        (TextEncoding::Utf16le, false, bytes)
+    } else if bytes.starts_with(&[0, b'<']) {
+        (TextEncoding::Utf16be, false, bytes)
+    } else if std::str::from_utf8(bytes).is_ok() && !declares_windows_codepage(bytes) {
+        (TextEncoding::Utf8, false, bytes)
+    } else {
+        (TextEncoding::Windows1252, false, bytes)
+    };
+
+    let text = match encoding {
+        TextEncoding::Utf8 => String::from_utf8_lossy(body).into_owned(),
+        TextEncoding::Utf16le => UTF_16LE.decode_without_bom_handling(body).0.into_owned(),
+        TextEncoding::Utf16be => UTF_16BE.decode_without_bom_handling(body).0.into_owned(),
+        TextEncoding::Windows1252 => WINDOWS_1252
+            .decode_without_bom_handling(body)
+            .0
+            .into_owned(),
+    };
+
+    let format = FileFormat {
+        encoding,
+        bom,
+        line_ending: detect_line_ending(&text),
+    };
+    (text, format)
+}
+
+/// Encodes `text` for writing in `format`. Characters Windows-1252 cannot
+/// represent are written as numeric character references.
+pub fn encode_text(text: &str, format: &FileFormat) -> Vec<u8> {
+    let text = match format.line_ending {
+        LineEnding::Crlf => to_crlf(text),
+        LineEnding::Lf => text.to_string(),
+    };
+
+    let mut bytes = Vec::new();
+    match format.encoding {
+        TextEncoding::Utf8 => {
+            if format.bom {
+                bytes.extend_from_slice(UTF8_BOM);
+            }
+            bytes.extend_from_slice(text.as_bytes());
+        }
+        TextEncoding::Utf16le => {
+            if format.bom {
+                bytes.extend_from_slice(UTF16LE_BOM);
+            }
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        }
+        TextEncoding::Utf16be => {
+            if format.bom {
+                bytes.extend_from_slice(UTF16BE_BOM);
+            }
+            bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        }
+        TextEncoding::Windows1252 => bytes.extend_from_slice(&WINDOWS_1252.encode(&text).0),
+    }
+    bytes
+}
+
+/// CRLF only when every line break in the file is CRLF, so files with mixed
+/// endings are left exactly as they are.
+fn detect_line_ending(text: &str) -> LineEnding {
+    let breaks = text.matches('\n').count();
+    if breaks > 0 && text.matches("\r\n").count() == breaks {
+        LineEnding::Crlf
+    } else {
+        LineEnding::Lf
+    }
+}
+
+/// Turns every lone `\n` into `\r\n`, leaving existing CRLF pairs alone.
+pub fn to_crlf(text: &str) -> String {
+    let mut converted = String::with_capacity(text.len() + text.len() / 32);
+    let mut previous = '\0';
+    for c in text.chars() {
+        if c == '\n' && previous != '\r' {
+            converted.push('\r');
+        }
+        converted.push(c);
+        previous = c;
+    }
+    converted
+}
+
+fn declares_windows_codepage(bytes: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(100)]).to_lowercase();
+    if !head.starts_with("<?xml") {
+        return false;
+    }
+    let declaration = head.split("?>").next().unwrap_or_default();
+    ["windows-1252", "iso-8859-1", "latin1", "cp1252"]
+        .iter()
+        .any(|name| declaration.contains(name))
+}
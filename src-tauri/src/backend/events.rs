@@ -0,0 +1,261 @@
+use super::button::load_button;
+use super::models::{EventIssue, Severity, VcpButton};
+use super::paths::button_folders;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Highest skin event CNC12 supports, per `SkinEventsList.txt`.
+pub const MAX_SKIN_EVENT: i32 = 255;
+
+/// Skin events defined by the stock Acorn PLC program.
+pub const BUILTIN_SKIN_EVENTS: &[(i32, &str)] = &[
+    (1, "SkinSpinOverPlus_M"),
+    (2, "SkinSpinAutoMan_M"),
+    (3, "SkinAux1_M"),
+    (4, "SkinAux2_M"),
+    (5, "SkinAux3_M"),
+    (6, "SkinSpin100_M"),
+    (7, "SkinSpinCW_M"),
+    (8, "SkinAux4_M"),
+    (9, "SkinAux5_M"),
+    (10, "SkinAux6_M"),
+    (11, "SkinSpinOverMinus_M"),
+    (12, "SkinSpinCCW_M"),
+    (13, "SkinAux7_M"),
+    (14, "SkinAux8_M"),
+    (15, "SkinAux9_M"),
+    (16, "SkinSpinStop_M"),
+    (17, "SkinSpinStart_M"),
+    (18, "SkinAux10_M"),
+    (19, "SkinAux11_M"),
+    (20, "SkinAux12_M"),
+    (21, "SkinCoolAutoMan_M"),
+    (22, "SkinCoolFlood_M"),
+    (23, "SkinCoolMist_M"),
+    (24, "SkinVacOn_M"),
+    (25, "SkinLimitDefeat_M"),
+    (26, "SkinIncCont_M"),
+    (27, "SkinX1_M"),
+    (28, "SkinX10_M"),
+    (29, "SkinX100_M"),
+    (30, "SkinMPG_M"),
+    (31, "SkinJogAx4Plus_M"),
+    (32, "SkinR7C2_M"),
+    (33, "SkinJogAx2Plus_M"),
+    (34, "SkinR7C4_M"),
+    (35, "SkinJogAx3Plus_M"),
+    (36, "SkinR8C1_M"),
+    (37, "SkinJogAx1Minus_M"),
+    (38, "SkinFastSlowJog_M"),
+    (39, "SkinJogAx1Plus_M"),
+    (40, "SkinR8C5_M"),
+    (41, "SkinJogAx4Minus_M"),
+    (42, "SkinR9C2_M"),
+    (43, "SkinJogAx2Minus_M"),
+    (44, "SkinR9C4_M"),
+    (45, "SkinJogAx3Minus_M"),
+    (46, "SkinCycleCancel_M"),
+    (47, "SkinSingleBlock_M"),
+    (48, "SkinToolCheck_M"),
+    (49, "SkinFeedHold_M"),
+    (50, "SkinCycleStart_M"),
+    (51, "SkinResetOk_M"),
+    (52, "SkinFeedOverMinus_M"),
+    (53, "SkinFeedOver100"),
+    (54, "SkinFeedOverPlus_M"),
+    (55, "KeepAlive_M"),
+    (56, "SkinResetButtonPressed_M"),
+    (58, "OverrideRapidsEnabled_M"),
+    (59, "OverrideRapidsDisabled_M"),
+    (60, "VirtualJpRunning_M"),
+    (61, "UsingVirtualOverride_M"),
+    (62, "SkinResetSet_M"),
+    (63, "FeedOverLT100"),
+    (64, "FeedOverEQ100"),
+    (65, "FeedOverGT100"),
+    (66, "SkinAux13_M"),
+    (67, "SkinAux14_M"),
+    (68, "SkinAux15_M"),
+    (69, "SkinAux16_M"),
+    (70, "SkinTailStock_M"),
+    (71, "SkinOpenChuck_M"),
+    (72, "SkinCloseChuck_M"),
+    (73, "SkinWorklight_M"),
+    (74, "SkinColletOpenClose_M"),
+    (75, "SkinRouterAirBlow_M"),
+    (76, "SkinSpindleHigh_M"),
+    (77, "SkinSpindleMed_M"),
+    (78, "SkinSpindleLow_M"),
+    (79, "SkinSpindleBrake_M"),
+    (80, "CncAndPlcActive_M"),
+    (81, "DryRun_M"),
+    (82, "RapidOver_M"),
+    (83, "SkinATCIndexPlus_M"),
+    (84, "SkinATCIndexMinus_M"),
+    (85, "SkinTurretIndex_M"),
+    (93, "EStopPressed_M"),
+    (94, "UsingKbOverride_M"),
+    (99, "SpinBrakeMode_M"),
+    (200, "SkinManualLube_M"),
+    (201, "SkinClampOn_M"),
+    (202, "SkinDustCollection_M"),
+    (203, "SkinCutoff_M"),
+    (204, "SkinPartChute_M"),
+    (205, "SkinToolUnclamp_M"),
+    (206, "SkinDustFoot_M"),
+    (207, "SkinLaserAlign_M"),
+    (208, "SkinPopUpPins_M"),
+    (209, "SkinSpindleCooling_M"),
+];
+
+/// Events the PLC raises itself to report machine state. A button bound to
+/// one of these fakes that state instead of triggering an action.
+pub const RESERVED_SKIN_EVENTS: &[&str] = &[
+    "KeepAlive_M",
+    "OverrideRapidsEnabled_M",
+    "OverrideRapidsDisabled_M",
+    "VirtualJpRunning_M",
+    "UsingVirtualOverride_M",
+    "SkinResetSet_M",
+    "FeedOverLT100",
+    "FeedOverEQ100",
+    "FeedOverGT100",
+    "CncAndPlcActive_M",
+    "EStopPressed_M",
+    "UsingKbOverride_M",
+];
+
+/// Name of built-in skin event `number`, if the stock PLC program defines it.
+pub fn builtin_event_name(number: i32) -> Option<&'static str> {
+    BUILTIN_SKIN_EVENTS
+        .iter()
+        .find(|(event, _)| *event == number)
+        .map(|(_, name)| *name)
+}
+
+/// Reads every button definition in `Buttons/` below `vcp_root` and checks
+/// the skin events they are bound to.
+pub fn analyze_skin_events(vcp_root: &Path) -> Result<Vec<EventIssue>, String> {
+    let mut buttons = Vec::new();
+    let mut issues = Vec::new();
+    for name in button_folders(vcp_root)? {
+        match load_button(vcp_root, &name) {
+            Ok(button) => buttons.push(button),
+            Err(e) => issues.push(EventIssue {
+                rule: "unreadable-button".to_string(),
+                severity: Severity::Warning,
+                event: None,
+                buttons: vec![name.clone()],
+                message: format!("Button '{}' was not checked: {}", name, e),
+            }),
+        }
+    }
+
+    issues.extend(analyze_buttons(&buttons));
+    Ok(issues)
+}
+
+/// Checks the skin events `buttons` are bound to against each other and the
+/// built-in event list:
+///
+/// - `duplicate-event`: several buttons raise the same event and fight over it
+/// - `out-of-range`: the event is outside 1..=255
+/// - `reserved-event`: the event is one the PLC raises itself
+/// - `unbound-event`: a built-in user event no button raises
+pub fn analyze_buttons(buttons: &[VcpButton]) -> Vec<EventIssue> {
+    let mut bound: BTreeMap<i32, Vec<String>> = BTreeMap::new();
+    for button in buttons {
+        if let Some(event) = button.skin_event_num {
+            bound.entry(event).or_default().push(button.name.clone());
+        }
+    }
+
+    let mut issues = Vec::new();
+    for (&event, names) in &mut bound {
+        names.sort();
+        let label = match builtin_event_name(event) {
+            Some(name) => format!("Skin event {} ({})", event, name),
+            None => format!("Skin event {}", event),
+        };
+
+        if !(1..=MAX_SKIN_EVENT).contains(&event) {
+            issues.push(event_issue(
+                "out-of-range",
+                Severity::Error,
+                event,
+                names,
+                format!(
+                    "{} is outside 1-{}, used by {}",
+                    label,
+                    MAX_SKIN_EVENT,
+                    quoted(names)
+                ),
+            ));
+        }
+        if names.len() > 1 {
+            issues.push(event_issue(
+                "duplicate-event",
+                Severity::Error,
+                event,
+                names,
+                format!(
+                    "{} is bound to {} buttons: {}",
+                    label,
+                    names.len(),
+                    quoted(names)
+                ),
+            ));
+        }
+        if builtin_event_name(event).is_some_and(|name| RESERVED_SKIN_EVENTS.contains(&name)) {
+            issues.push(event_issue(
+                "reserved-event",
+                Severity::Error,
+                event,
+                names,
+                format!(
+                    "{} is raised by the PLC itself and should not be bound to {}",
+                    label,
+                    quoted(names)
+                ),
+            ));
+        }
+    }
+
+    for &(event, name) in BUILTIN_SKIN_EVENTS {
+        if !bound.contains_key(&event) && !RESERVED_SKIN_EVENTS.contains(&name) {
+            issues.push(event_issue(
+                "unbound-event",
+                Severity::Info,
+                event,
+                &[],
+                format!("Skin event {} ({}) is not bound to any button", event, name),
+            ));
+        }
+    }
+
+    issues
+}
+
+fn event_issue(
+    rule: &str,
+    severity: Severity,
+    event: i32,
+    buttons: &[String],
+    message: String,
+) -> EventIssue {
+    EventIssue {
+        rule: rule.to_string(),
+        severity,
+        event: Some(event),
+        buttons: buttons.to_vec(),
+        message,
+    }
+}
+
+fn quoted(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|name| format!("'{}'", name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
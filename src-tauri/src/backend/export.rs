@@ -0,0 +1,330 @@
+use super::assets::{assets_error, check_export_assets};
+use super::ignore::IgnoreRules;
+use super::manifest::{relative_path, HashingWriter, MANIFEST_FILE, MANIFEST_VERSION};
+use super::models::{
+    Controller, ExportManifest, ExportReport, ExportSkin, MachineProfile, MachineType,
+    ManifestSkin, SerializeOptions, SkinTarget, VcpDocument,
+};
+use super::parser::{load_file, render_file};
+use super::settings::load_project_settings;
+use super::validate::check_document;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+/// Where CNC12 keeps the `vcp` folder unless configured otherwise.
+pub const DEFAULT_CNC_BASE_PATH: &str = r"C:\cncm\resources\vcp";
+
+impl Controller {
+    pub const ALL: [Controller; 4] = [
+        Controller::Acorn,
+        Controller::AcornSix,
+        Controller::Oak,
+        Controller::Hickory,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Controller::Acorn => "acorn",
+            Controller::AcornSix => "acornsix",
+            Controller::Oak => "oak",
+            Controller::Hickory => "hickory",
+        }
+    }
+}
+
+impl MachineType {
+    pub const ALL: [MachineType; 4] = [
+        MachineType::Mill,
+        MachineType::Lathe,
+        MachineType::Router,
+        MachineType::Plasma,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MachineType::Mill => "mill",
+            MachineType::Lathe => "lathe",
+            MachineType::Router => "router",
+            MachineType::Plasma => "plasma",
+        }
+    }
+}
+
+impl MachineProfile {
+    /// The skin file CNC12 loads for this machine, e.g.
+    /// `oak_lathe_vcp_skin.vcp`.
+    pub fn skin_file(&self) -> String {
+        format!(
+            "{}_{}_vcp_skin.vcp",
+            self.controller.name(),
+            self.machine.name()
+        )
+    }
+
+    /// Reads `<controller>-<type>`, e.g. `oak-lathe`, in any case.
+    pub fn parse(text: &str) -> Result<MachineProfile, String> {
+        let lower = text.to_ascii_lowercase();
+        let parsed = lower.split_once('-').and_then(|(controller, machine)| {
+            Some(MachineProfile {
+                controller: Controller::ALL
+                    .into_iter()
+                    .find(|c| c.name() == controller)?,
+                machine: MachineType::ALL.into_iter().find(|m| m.name() == machine)?,
+            })
+        });
+        parsed.ok_or_else(|| {
+            format!(
+                "Unknown machine '{}', expected <controller>-<type> such as acorn-mill or oak-lathe",
+                text
+            )
+        })
+    }
+}
+
+impl SkinTarget {
+    /// The file name in `skins/`. Names must end in `.vcp` and stay inside
+    /// the folder.
+    pub fn file_name(&self) -> Result<String, String> {
+        match self {
+            SkinTarget::Machine(profile) => Ok(profile.skin_file()),
+            SkinTarget::File(name) => {
+                if name.contains(['/', '\\', ':']) || name.starts_with('.') {
+                    return Err(format!("Invalid skin name '{}'", name));
+                }
+                if !name.to_ascii_lowercase().ends_with(".vcp") {
+                    return Err(format!("Skin name '{}' must end in .vcp", name));
+                }
+                Ok(name.clone())
+            }
+        }
+    }
+}
+
+/// A skin to pack. Without a `target` it is named for the project's machine.
+#[derive(Debug, Clone)]
+pub struct ExportEntry {
+    pub document: VcpDocument,
+    pub target: Option<SkinTarget>,
+    /// File the skin was read from, for the manifest
+    pub source: Option<String>,
+}
+
+/// Reads the skins listed for an export from disk. Relative sources are
+/// taken from `vcp_root`.
+pub fn read_export_skins(
+    vcp_root: &Path,
+    skins: &[ExportSkin],
+) -> Result<Vec<ExportEntry>, String> {
+    skins
+        .iter()
+        .map(|skin| {
+            let path = vcp_root.join(&skin.source);
+            let document = load_file(&path.to_string_lossy())
+                .map_err(|e| format!("{}: {}", skin.source, e))?
+                .document;
+            Ok(ExportEntry {
+                document,
+                target: skin.target.clone(),
+                source: Some(path.to_string_lossy().to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Packs the project at `vcp_root` with `skins` in `skins/` into
+/// `vcp_export_<timestamp>.zip` in `cnc_base_path`. A manifest listing every
+/// file with its checksum goes in as well.
+///
+/// Image paths in the skins are made absolute under `cnc_base_path`. The
+/// project's settings decide whether validation errors or missing assets
+/// stop the export, and supply the output options when `options` is `None`.
+/// Missing assets that do not stop it are listed in the report.
+pub fn export_project(
+    vcp_root: &Path,
+    cnc_base_path: &str,
+    skins: &[ExportEntry],
+    options: Option<SerializeOptions>,
+) -> Result<ExportReport, String> {
+    export_project_into(
+        Path::new(cnc_base_path),
+        vcp_root,
+        cnc_base_path,
+        skins,
+        options,
+    )
+}
+
+/// Like [`export_project`], but writes the zip into `folder` rather than
+/// `cnc_base_path`, for when the CNC's folder is not reachable under that
+/// path here.
+pub fn export_project_into(
+    folder: &Path,
+    vcp_root: &Path,
+    cnc_base_path: &str,
+    skins: &[ExportEntry],
+    options: Option<SerializeOptions>,
+) -> Result<ExportReport, String> {
+    if skins.is_empty() {
+        return Err("Nothing to export, no skins were given".to_string());
+    }
+    let settings = load_project_settings(vcp_root)?;
+
+    // CNC12 runs on Windows, where names differ only in case clash
+    let mut names = HashSet::new();
+    let mut files = Vec::with_capacity(skins.len());
+    for skin in skins {
+        let target = skin
+            .target
+            .clone()
+            .unwrap_or(SkinTarget::Machine(settings.machine));
+        let file = target.file_name()?;
+        if !names.insert(file.to_ascii_lowercase()) {
+            return Err(format!("More than one skin would be exported as {}", file));
+        }
+        if settings.validation.block_export {
+            check_document(&skin.document).map_err(|e| format!("{}: {}", file, e))?;
+        }
+        files.push(file);
+    }
+    let exported_skins: Vec<(String, &VcpDocument)> = files
+        .iter()
+        .zip(skins)
+        .map(|(file, skin)| (format!("skins/{}", file), &skin.document))
+        .collect();
+    let asset_issues = check_export_assets(vcp_root, &exported_skins)?;
+    if settings.validation.block_export && !asset_issues.is_empty() {
+        return Err(assets_error(&asset_issues));
+    }
+    let options = options.unwrap_or(settings.output);
+    let rules = IgnoreRules::load(vcp_root)?;
+    let now = chrono::Utc::now();
+    let mut manifest_files = Vec::new();
+
+    // The zip is written under a hidden name and renamed once complete, so
+    // concurrent or failed exports never leave a broken zip behind
+    let mut temp = tempfile::Builder::new()
+        .prefix(".vcp_export_")
+        .suffix(".zip.part")
+        .tempfile_in(folder)
+        .map_err(|e| format!("Failed to create zip file: {}", e))?;
+    let mut zip = ZipWriter::new(temp.as_file_mut());
+    let zip_options =
+        FileOptions::<()>::default().compression_method(zip::CompressionMethod::Deflated);
+
+    // Stream the project straight from disk, leaving out the skins being
+    // exported, which are written from the documents below
+    let exported: HashSet<String> = names.iter().map(|name| format!("skins/{}", name)).collect();
+    let walker = walkdir::WalkDir::new(vcp_root)
+        .follow_links(true)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !rules.is_ignored(
+                    &relative_path(vcp_root, entry.path()),
+                    entry.file_type().is_dir(),
+                )
+        });
+    for entry in walker {
+        let entry = entry.map_err(|e| format!("Failed to read project folder: {}", e))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let name = relative_path(vcp_root, entry.path());
+        if exported.contains(&name.to_ascii_lowercase()) {
+            continue;
+        }
+        zip.start_file(format!("vcp/{}", name), zip_options)
+            .map_err(|e| format!("Failed to add file to zip: {}", e))?;
+        let mut file =
+            fs::File::open(entry.path()).map_err(|e| format!("Failed to open {}: {}", name, e))?;
+        let mut writer = HashingWriter::new(&mut zip);
+        io::copy(&mut file, &mut writer)
+            .map_err(|e| format!("Failed to write {} to zip: {}", name, e))?;
+        manifest_files.push(writer.finish(name));
+    }
+
+    for (skin, file) in skins.iter().zip(&files) {
+        // Unlike saving, exporting applies the output options to skins read
+        // from disk too
+        let document = with_cnc_image_paths(&skin.document, cnc_base_path);
+        let content = render_file(&document, &options);
+
+        zip.start_file(format!("vcp/skins/{}", file), zip_options)
+            .map_err(|e| format!("Failed to add file to zip: {}", e))?;
+        let mut writer = HashingWriter::new(&mut zip);
+        writer
+            .write_all(&content)
+            .map_err(|e| format!("Failed to write VCP file: {}", e))?;
+        manifest_files.push(writer.finish(format!("skins/{}", file)));
+    }
+
+    manifest_files.sort_by(|a, b| a.path.cmp(&b.path));
+    let manifest = ExportManifest {
+        version: MANIFEST_VERSION,
+        editor_version: env!("CARGO_PKG_VERSION").to_string(),
+        created: now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        source: vcp_root.to_string_lossy().to_string(),
+        cnc_base_path: cnc_base_path.to_string(),
+        skins: skins
+            .iter()
+            .zip(&files)
+            .map(|(skin, file)| ManifestSkin {
+                file: file.clone(),
+                source: skin.source.clone(),
+            })
+            .collect(),
+        options,
+        files: manifest_files,
+    };
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    zip.start_file(format!("vcp/{}", MANIFEST_FILE), zip_options)
+        .map_err(|e| format!("Failed to add file to zip: {}", e))?;
+    zip.write_all(manifest_json.as_bytes())
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finalize zip: {}", e))?;
+
+    // Two exports in the same second get different names
+    let stamp = now.format("%Y%m%d_%H%M%S");
+    let mut attempt = 1;
+    loop {
+        let zip_filename = match attempt {
+            1 => format!("vcp_export_{}.zip", stamp),
+            _ => format!("vcp_export_{}_{}.zip", stamp, attempt),
+        };
+        let zip_path = folder.join(zip_filename);
+        match temp.persist_noclobber(&zip_path) {
+            Ok(_) => {
+                return Ok(ExportReport {
+                    zip: zip_path.to_string_lossy().to_string(),
+                    asset_issues,
+                })
+            }
+            Err(e) if e.error.kind() == io::ErrorKind::AlreadyExists => temp = e.file,
+            Err(e) => return Err(format!("Failed to save zip file: {}", e.error)),
+        }
+        attempt += 1;
+    }
+}
+
+/// `doc` with its image paths made absolute under `cnc_base_path`, where
+/// CNC12 looks for them. Paths are changed on the document so they are
+/// escaped like any other text when written.
+fn with_cnc_image_paths(doc: &VcpDocument, cnc_base_path: &str) -> VcpDocument {
+    let base = cnc_base_path.replace('\\', "/");
+    let base = base.trim_end_matches('/');
+    let mut doc = doc.clone();
+    for image in &mut doc.images {
+        if let Some(rest) = image.path.strip_prefix("images/") {
+            image.path = format!("{}/images/{}", base, rest);
+        }
+    }
+    doc
+}
@@ -0,0 +1,377 @@
+use super::models::{EditCommand, Element, ElementKind, HistoryList, VcpDocument};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+impl EditCommand {
+    /// Applies the edit to `doc` and returns the edit that reverts it. On
+    /// error `doc` is left unchanged.
+    pub fn apply(&self, doc: &mut VcpDocument) -> Result<EditCommand, String> {
+        match self {
+            EditCommand::AddElement { element, index } => {
+                let kind = element.kind();
+                let len = element_count(doc, kind);
+                let index = index.unwrap_or(len);
+                if index > len {
+                    return Err(format!(
+                        "Cannot insert {} at {}, there are only {}",
+                        kind_name(kind),
+                        index,
+                        len
+                    ));
+                }
+                match (**element).clone() {
+                    Element::Border(border) => doc.borders.insert(index, border),
+                    Element::Image(image) => doc.images.insert(index, image),
+                    Element::Button(button) => doc.buttons.insert(index, *button),
+                }
+                Ok(EditCommand::DeleteElement { kind, index })
+            }
+            EditCommand::DeleteElement { kind, index } => {
+                check_index(doc, *kind, *index)?;
+                let element = match kind {
+                    ElementKind::Border => Element::Border(doc.borders.remove(*index)),
+                    ElementKind::Image => Element::Image(doc.images.remove(*index)),
+                    ElementKind::Button => Element::Button(Box::new(doc.buttons.remove(*index))),
+                };
+                Ok(EditCommand::AddElement {
+                    element: Box::new(element),
+                    index: Some(*index),
+                })
+            }
+            EditCommand::MoveElement {
+                kind,
+                index,
+                row,
+                column,
+            } => {
+                check_index(doc, *kind, *index)?;
+                let (target_row, target_column) = match kind {
+                    ElementKind::Border => {
+                        let border = &mut doc.borders[*index];
+                        (&mut border.row_start, &mut border.column_start)
+                    }
+                    ElementKind::Image => {
+                        let image = &mut doc.images[*index];
+                        (&mut image.row_start, &mut image.column_start)
+                    }
+                    ElementKind::Button => {
+                        let button = &mut doc.buttons[*index];
+                        (&mut button.row, &mut button.column)
+                    }
+                };
+                let inverse = EditCommand::MoveElement {
+                    kind: *kind,
+                    index: *index,
+                    row: std::mem::replace(target_row, *row),
+                    column: std::mem::replace(target_column, *column),
+                };
+                Ok(inverse)
+            }
+            EditCommand::ResizeElement {
+                kind,
+                index,
+                row_span,
+                column_span,
+            } => {
+                check_index(doc, *kind, *index)?;
+                let (old_row_span, old_column_span) = match kind {
+                    ElementKind::Border | ElementKind::Image => {
+                        let (Some(row_span), Some(column_span)) = (row_span, column_span) else {
+                            return Err(format!("A {} needs both spans", kind_name(*kind)));
+                        };
+                        let (rows, columns) = match kind {
+                            ElementKind::Border => {
+                                let border = &mut doc.borders[*index];
+                                (&mut border.row_span, &mut border.column_span)
+                            }
+                            _ => {
+                                let image = &mut doc.images[*index];
+                                (&mut image.row_span, &mut image.column_span)
+                            }
+                        };
+                        (
+                            Some(std::mem::replace(rows, *row_span)),
+                            Some(std::mem::replace(columns, *column_span)),
+                        )
+                    }
+                    ElementKind::Button => {
+                        let button = &mut doc.buttons[*index];
+                        (
+                            std::mem::replace(&mut button.row_span, *row_span),
+                            std::mem::replace(&mut button.column_span, *column_span),
+                        )
+                    }
+                };
+                Ok(EditCommand::ResizeElement {
+                    kind: *kind,
+                    index: *index,
+                    row_span: old_row_span,
+                    column_span: old_column_span,
+                })
+            }
+            EditCommand::SetProperty {
+                element,
+                name,
+                value,
+            } => {
+                let old = match element {
+                    Some(element) => {
+                        check_index(doc, element.kind, element.index)?;
+                        match element.kind {
+                            ElementKind::Border => {
+                                set_field(&mut doc.borders[element.index], name, value)?
+                            }
+                            ElementKind::Image => {
+                                set_field(&mut doc.images[element.index], name, value)?
+                            }
+                            ElementKind::Button => {
+                                set_field(&mut doc.buttons[element.index], name, value)?
+                            }
+                        }
+                    }
+                    None => set_document_field(doc, name, value)?,
+                };
+                Ok(EditCommand::SetProperty {
+                    element: *element,
+                    name: name.clone(),
+                    value: old,
+                })
+            }
+            EditCommand::ResizeGrid {
+                row_count,
+                column_count,
+            } => Ok(EditCommand::ResizeGrid {
+                row_count: std::mem::replace(&mut doc.row_count, *row_count),
+                column_count: std::mem::replace(&mut doc.column_count, *column_count),
+            }),
+        }
+    }
+}
+
+impl Element {
+    pub fn kind(&self) -> ElementKind {
+        match self {
+            Element::Border(_) => ElementKind::Border,
+            Element::Image(_) => ElementKind::Image,
+            Element::Button(_) => ElementKind::Button,
+        }
+    }
+}
+
+/// A step in the history: the edits as the frontend sent them and the edits
+/// that revert them, in the order they must run.
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    label: String,
+    edits: Vec<EditCommand>,
+    inverse: Vec<EditCommand>,
+}
+
+/// Every edit made to a document since it was opened, with no limit.
+///
+/// Only the edits are kept, never copies of the document, so each step costs
+/// about as much memory as the change it records.
+#[derive(Debug, Clone)]
+pub struct EditHistory {
+    entries: Vec<HistoryEntry>,
+    position: usize,
+    saved_position: Option<usize>,
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        EditHistory {
+            entries: Vec::new(),
+            position: 0,
+            saved_position: Some(0),
+        }
+    }
+}
+
+impl EditHistory {
+    /// Applies `edits` to `doc` as one step named `label`, discarding any
+    /// steps that were undone. If an edit fails, the ones before it are
+    /// reverted and the history is unchanged.
+    pub fn apply(
+        &mut self,
+        doc: &mut VcpDocument,
+        edits: Vec<EditCommand>,
+        label: String,
+    ) -> Result<(), String> {
+        if edits.is_empty() {
+            return Ok(());
+        }
+        let inverse = apply_all(doc, &edits)?;
+        self.entries.truncate(self.position);
+        if self
+            .saved_position
+            .is_some_and(|saved| saved > self.position)
+        {
+            self.saved_position = None;
+        }
+        self.entries.push(HistoryEntry {
+            label,
+            edits,
+            inverse,
+        });
+        self.position += 1;
+        Ok(())
+    }
+
+    /// Reverts the last applied step. Returns `false` when there is none.
+    pub fn undo(&mut self, doc: &mut VcpDocument) -> Result<bool, String> {
+        if self.position == 0 {
+            return Ok(false);
+        }
+        let entry = &mut self.entries[self.position - 1];
+        entry.edits = apply_all(doc, &entry.inverse)?;
+        self.position -= 1;
+        Ok(true)
+    }
+
+    /// Re-applies the last undone step. Returns `false` when there is none.
+    pub fn redo(&mut self, doc: &mut VcpDocument) -> Result<bool, String> {
+        let Some(entry) = self.entries.get_mut(self.position) else {
+            return Ok(false);
+        };
+        entry.inverse = apply_all(doc, &entry.edits)?;
+        self.position += 1;
+        Ok(true)
+    }
+
+    /// Forgets every step, for a document that was opened, created or
+    /// replaced. `saved` tells whether it matches the file on disk.
+    pub fn reset(&mut self, saved: bool) {
+        *self = EditHistory::default();
+        if !saved {
+            self.saved_position = None;
+        }
+    }
+
+    /// Remembers the current position as the state on disk.
+    pub fn mark_saved(&mut self) {
+        self.saved_position = Some(self.position);
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.saved_position != Some(self.position)
+    }
+
+    pub fn list(&self) -> HistoryList {
+        HistoryList {
+            entries: self
+                .entries
+                .iter()
+                .map(|entry| entry.label.clone())
+                .collect(),
+            position: self.position,
+            saved_position: self.saved_position,
+        }
+    }
+}
+
+/// Applies `edits` in order, returning the edits that revert them in the
+/// order they must run. Rolls back and returns the error if one fails.
+fn apply_all(doc: &mut VcpDocument, edits: &[EditCommand]) -> Result<Vec<EditCommand>, String> {
+    let mut inverse = Vec::with_capacity(edits.len());
+    for edit in edits {
+        match edit.apply(doc) {
+            Ok(undo) => inverse.push(undo),
+            Err(e) => {
+                for undo in inverse.iter().rev() {
+                    // Inverses of edits that just succeeded cannot fail
+                    let _ = undo.apply(doc);
+                }
+                return Err(e);
+            }
+        }
+    }
+    inverse.reverse();
+    Ok(inverse)
+}
+
+/// Sets field `name` of `target` through its serde form, returning the old
+/// value. Unknown fields and values of the wrong type are rejected.
+fn set_field<T: Serialize + DeserializeOwned>(
+    target: &mut T,
+    name: &str,
+    value: &Value,
+) -> Result<Value, String> {
+    if name == "source" {
+        return Err("The source markup cannot be edited".to_string());
+    }
+    let serialize = |target: &T| {
+        serde_json::to_value(target).map_err(|e| format!("Failed to serialize element: {}", e))
+    };
+    let Value::Object(mut fields) = serialize(target)? else {
+        return Err("Element is not an object".to_string());
+    };
+    let old = fields.get(name).cloned().unwrap_or(Value::Null);
+    fields.insert(name.to_string(), value.clone());
+    let updated: T = serde_json::from_value(Value::Object(fields))
+        .map_err(|e| format!("Invalid value for '{}': {}", name, e))?;
+    // Unknown fields are silently dropped by deserialization, so check the
+    // value made it through
+    if serialize(&updated)?.get(name).unwrap_or(&Value::Null) != value {
+        return Err(format!("Unknown property '{}'", name));
+    }
+    *target = updated;
+    Ok(old)
+}
+
+fn set_document_field(doc: &mut VcpDocument, name: &str, value: &Value) -> Result<Value, String> {
+    let invalid = |e: serde_json::Error| format!("Invalid value for '{}': {}", name, e);
+    match name {
+        "background" => {
+            let background = serde_json::from_value(value.clone()).map_err(invalid)?;
+            Ok(Value::String(std::mem::replace(
+                &mut doc.background,
+                background,
+            )))
+        }
+        "on_click" => {
+            let on_click = serde_json::from_value(value.clone()).map_err(invalid)?;
+            Ok(json_value(std::mem::replace(&mut doc.on_click, on_click)))
+        }
+        "on_hover" => {
+            let on_hover = serde_json::from_value(value.clone()).map_err(invalid)?;
+            Ok(json_value(std::mem::replace(&mut doc.on_hover, on_hover)))
+        }
+        "row_count" | "column_count" => Err(format!("Use a grid resize to change '{}'", name)),
+        _ => Err(format!("Unknown property '{}'", name)),
+    }
+}
+
+fn json_value<T: Serialize>(value: T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn element_count(doc: &VcpDocument, kind: ElementKind) -> usize {
+    match kind {
+        ElementKind::Border => doc.borders.len(),
+        ElementKind::Image => doc.images.len(),
+        ElementKind::Button => doc.buttons.len(),
+    }
+}
+
+fn check_index(doc: &VcpDocument, kind: ElementKind, index: usize) -> Result<(), String> {
+    let len = element_count(doc, kind);
+    if index >= len {
+        return Err(format!(
+            "No {} at index {}, there are only {}",
+            kind_name(kind),
+            index,
+            len
+        ));
+    }
+    Ok(())
+}
+
+fn kind_name(kind: ElementKind) -> &'static str {
+    match kind {
+        ElementKind::Border => "border",
+        ElementKind::Image => "image",
+        ElementKind::Button => "button",
+    }
+}
@@ -0,0 +1,158 @@
+use std::fs;
+use std::path::Path;
+
+/// Per-project list of files exports leave out, in the VCP root.
+pub const IGNORE_FILE: &str = ".vcpignore";
+
+/// Files no CNC needs: OS litter, editor backups and earlier exports. `.*`
+/// covers `.DS_Store`, `.vcpignore` itself and folders such as `.git`. The
+/// editor's own files in the VCP root, such as the project settings, the
+/// symbol catalog and a deployed export's manifest, start with a dot so
+/// that it covers them too.
+const BUILTIN_PATTERNS: &[&str] = &[
+    ".*",
+    "Thumbs.db",
+    "desktop.ini",
+    "*~",
+    "*.bak",
+    "*.swp",
+    "*.tmp",
+    "#*#",
+    "vcp_export_*.zip",
+];
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    glob: Vec<char>,
+    negated: bool,
+    folders_only: bool,
+    /// Matched against the whole path rather than the name
+    anchored: bool,
+}
+
+/// Decides which files of a project an export leaves out, in the style of
+/// `.gitignore`: one pattern per line, `#` starts a comment, `*` and `?`
+/// match within a name and `**` across folders. A pattern containing `/` is
+/// matched against the path from the VCP root, any other against the name
+/// alone. A trailing `/` matches folders only, and `!` brings back what an
+/// earlier pattern left out. The last matching pattern wins. Names are
+/// compared without regard to case, as on Windows.
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    patterns: Vec<Pattern>,
+}
+
+impl IgnoreRules {
+    /// The built-in rules followed by the patterns in `content`.
+    pub fn parse(content: &str) -> IgnoreRules {
+        let lines = BUILTIN_PATTERNS.iter().copied().chain(content.lines());
+        IgnoreRules {
+            patterns: lines.filter_map(parse_pattern).collect(),
+        }
+    }
+
+    /// The built-in rules followed by the project's `.vcpignore`, if it has one.
+    pub fn load(vcp_root: &Path) -> Result<IgnoreRules, String> {
+        let path = vcp_root.join(IGNORE_FILE);
+        if !path.exists() {
+            return Ok(IgnoreRules::parse(""));
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", IGNORE_FILE, e))?;
+        Ok(IgnoreRules::parse(&content))
+    }
+
+    /// Whether `path`, relative to the VCP root and separated by `/`, is left
+    /// out. Files in a folder that is left out must be skipped by the caller.
+    pub fn is_ignored(&self, path: &str, is_folder: bool) -> bool {
+        let path: Vec<char> = path.chars().collect();
+        let name_start = path
+            .iter()
+            .rposition(|&c| c == '/')
+            .map_or(0, |slash| slash + 1);
+        let mut ignored = false;
+        for pattern in &self.patterns {
+            if pattern.folders_only && !is_folder {
+                continue;
+            }
+            let text = if pattern.anchored {
+                &path[..]
+            } else {
+                &path[name_start..]
+            };
+            if glob_match(&pattern.glob, text) {
+                ignored = !pattern.negated;
+            }
+        }
+        ignored
+    }
+
+    /// Whether the file at `path` is left out, by itself or through one of
+    /// its folders.
+    pub fn is_ignored_file(&self, path: &str) -> bool {
+        path.match_indices('/')
+            .any(|(slash, _)| self.is_ignored(&path[..slash], true))
+            || self.is_ignored(path, false)
+    }
+}
+
+fn parse_pattern(line: &str) -> Option<Pattern> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (folders_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let anchored = line.contains('/');
+    let line = line.strip_prefix('/').unwrap_or(line);
+    if line.is_empty() {
+        return None;
+    }
+    Some(Pattern {
+        glob: line.chars().collect(),
+        negated,
+        folders_only,
+        anchored,
+    })
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => match rest.split_first() {
+            Some(('*', rest)) => {
+                // `**/` also matches no folder at all
+                if let Some(after) = rest.strip_prefix(&['/']) {
+                    if glob_match(after, text) {
+                        return true;
+                    }
+                }
+                (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+            }
+            _ => {
+                for i in 0..=text.len() {
+                    if glob_match(rest, &text[i..]) {
+                        return true;
+                    }
+                    if text.get(i) == Some(&'/') {
+                        break;
+                    }
+                }
+                false
+            }
+        },
+        Some(('?', rest)) => {
+            matches!(text.first(), Some(&c) if c != '/') && glob_match(rest, &text[1..])
+        }
+        Some((&c, rest)) => {
+            matches!(text.first(), Some(t) if t.eq_ignore_ascii_case(&c))
+                && glob_match(rest, &text[1..])
+        }
+    }
+}
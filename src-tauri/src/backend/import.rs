@@ -0,0 +1,341 @@
+use super::deploy::{match_permissions, parent_of, switch};
+use super::encoding::{decode_text, encode_text};
+use super::ignore::IgnoreRules;
+use super::manifest::{parse_manifest, relative_path, MANIFEST_FILE};
+use super::models::{ImportReport, RewrittenPath};
+use super::xml::{escape_text, parse_document, XmlElement, XmlNodeKind};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Where a file to import is read from.
+enum Origin {
+    File(PathBuf),
+    /// Index of the entry in the zip
+    Entry(usize),
+}
+
+/// Turns an export zip or a `vcp` folder taken off a CNC back into a
+/// project at `destination`, returning what was written.
+///
+/// Image paths the export made absolute, such as
+/// `C:\cncm\resources\vcp/images/logo.svg`, become `images/logo.svg` again,
+/// and backslashes in them become forward slashes. The `skins`, `images` and
+/// `Buttons` folders are created whether or not the source has them. Litter
+/// the export would skip, and the export's manifest, are left out. Unless
+/// `overwrite` is set, nothing is written when any file already exists.
+///
+/// The new folder is put together next to `destination`, with any files
+/// already there, and swapped in once complete, so a failed import leaves
+/// `destination` as it was.
+pub fn import_cnc(
+    source: &Path,
+    destination: &Path,
+    overwrite: bool,
+) -> Result<ImportReport, String> {
+    let rules = IgnoreRules::parse("");
+    let mut files: Vec<(String, Origin)> = Vec::new();
+    let mut manifest = None;
+    let mut archive = None;
+
+    if source.is_dir() {
+        let root = folder_root(source)?;
+        let walker = walkdir::WalkDir::new(&root)
+            .follow_links(true)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0
+                    || !rules.is_ignored(
+                        &relative_path(&root, entry.path()),
+                        entry.file_type().is_dir(),
+                    )
+            });
+        for entry in walker {
+            let entry = entry.map_err(|e| format!("Failed to read {}: {}", root.display(), e))?;
+            if entry.file_type().is_file() {
+                let path = relative_path(&root, entry.path());
+                files.push((path, Origin::File(entry.path().to_path_buf())));
+            }
+        }
+        let manifest_path = root.join(MANIFEST_FILE);
+        if manifest_path.is_file() {
+            manifest = fs::read_to_string(&manifest_path).ok();
+        }
+    } else {
+        let file = fs::File::open(source).map_err(|e| format!("Failed to open zip file: {}", e))?;
+        let mut zip =
+            zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip file: {}", e))?;
+        let names: Vec<String> = zip
+            .file_names()
+            .map(|name| name.replace('\\', "/"))
+            .collect();
+        // Exports keep the project in `vcp/`; a zip of the folder's contents
+        // has it at the top
+        let prefix = if names.iter().any(|name| name.starts_with("vcp/")) {
+            "vcp/"
+        } else {
+            ""
+        };
+        for index in 0..zip.len() {
+            let mut entry = zip
+                .by_index(index)
+                .map_err(|e| format!("Failed to read zip file: {}", e))?;
+            let name = entry.name().replace('\\', "/");
+            let Some(path) = name.strip_prefix(prefix) else {
+                continue;
+            };
+            if entry.is_dir() || path.is_empty() {
+                continue;
+            }
+            check_entry_path(path)?;
+            if path == MANIFEST_FILE {
+                let mut content = String::new();
+                if entry.read_to_string(&mut content).is_ok() {
+                    manifest = Some(content);
+                }
+            } else if !rules.is_ignored_file(path) {
+                files.push((path.to_string(), Origin::Entry(index)));
+            }
+        }
+        archive = Some(zip);
+    }
+
+    if !files.iter().any(|(path, _)| is_skin(path)) {
+        return Err(format!("No skins found in {}", source.display()));
+    }
+    if !overwrite {
+        if let Some((path, _)) = files
+            .iter()
+            .find(|(path, _)| destination.join(path).exists())
+        {
+            return Err(format!(
+                "{} already exists in {}, import into an empty folder or allow overwriting",
+                path,
+                destination.display()
+            ));
+        }
+    }
+    // Exports from this editor record the folder images were moved to
+    let cnc_base_path = manifest
+        .and_then(|content| parse_manifest(&content).ok())
+        .map(|manifest| manifest.cnc_base_path);
+
+    let parent = parent_of(destination);
+    fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    let staging = tempfile::Builder::new()
+        .prefix(".vcp_import_")
+        .tempdir_in(parent)
+        .map_err(|e| format!("Failed to create staging folder: {}", e))?;
+    for folder in ["skins", "images", "Buttons"] {
+        fs::create_dir_all(staging.path().join(folder))
+            .map_err(|e| format!("Failed to create {} folder: {}", folder, e))?;
+    }
+    let mut written = Vec::new();
+    let mut rewritten = Vec::new();
+    for (path, origin) in files {
+        let target = staging.path().join(&path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let mut reader: Box<dyn Read + '_> = match origin {
+            Origin::File(file) => Box::new(
+                fs::File::open(&file).map_err(|e| format!("Failed to open {}: {}", path, e))?,
+            ),
+            Origin::Entry(index) => Box::new(
+                archive
+                    .as_mut()
+                    .expect("Zip entries come from an archive")
+                    .by_index(index)
+                    .map_err(|e| format!("Failed to read {}: {}", path, e))?,
+            ),
+        };
+
+        if is_skin(&path) {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            let (content, format) = decode_text(&bytes);
+            let (content, changes) = rewrite_image_paths(&content, cnc_base_path.as_deref());
+            fs::write(&target, encode_text(&content, &format))
+                .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+            rewritten.extend(changes.into_iter().map(|(from, to)| RewrittenPath {
+                skin: path.clone(),
+                from,
+                to,
+            }));
+        } else {
+            let mut file = fs::File::create(&target)
+                .map_err(|e| format!("Failed to create {}: {}", path, e))?;
+            io::copy(&mut reader, &mut file)
+                .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        }
+        written.push(path);
+    }
+    move_into_place(staging.path(), destination)?;
+
+    Ok(ImportReport {
+        root: destination.to_string_lossy().to_string(),
+        files: written,
+        rewritten,
+    })
+}
+
+/// The path an export wrote as `path`, relative to the VCP root again, with
+/// forward slashes. `cnc_base_path` is the folder the export made image
+/// paths absolute under, when known; otherwise anything up to a `vcp` or
+/// `images` folder is taken off absolute paths. Relative paths only get
+/// their slashes fixed.
+pub fn relative_image_path(path: &str, cnc_base_path: Option<&str>) -> String {
+    let path = path.trim().replace('\\', "/");
+    // ASCII lowercase keeps byte offsets, so they apply to `path` too
+    let lower = path.to_ascii_lowercase();
+    if let Some(base) = cnc_base_path {
+        let base = format!(
+            "{}/",
+            base.replace('\\', "/")
+                .trim_end_matches('/')
+                .to_ascii_lowercase()
+        );
+        if lower.starts_with(&base) {
+            return path[base.len()..].to_string();
+        }
+    }
+    let absolute = path.starts_with('/') || path.as_bytes().get(1) == Some(&b':');
+    if !absolute {
+        return path;
+    }
+    if let Some(start) = lower.rfind("/vcp/") {
+        return path[start + "/vcp/".len()..].to_string();
+    }
+    match lower.rfind("/images/") {
+        Some(start) => path[start + 1..].to_string(),
+        None => path,
+    }
+}
+
+/// Rewrites the text of every `<path>` element in a skin, leaving the rest
+/// of the file as it is. Returns the new content and the paths it changed.
+/// Skins that do not parse are left alone, for the editor to report when
+/// they are opened.
+fn rewrite_image_paths(
+    content: &str,
+    cnc_base_path: Option<&str>,
+) -> (String, Vec<(String, String)>) {
+    let Ok(document) = parse_document(content) else {
+        return (content.to_string(), Vec::new());
+    };
+    let mut paths = Vec::new();
+    if let Some(root) = document.root() {
+        path_elements(root, &mut paths);
+    }
+
+    let mut output = String::with_capacity(content.len());
+    let mut changes = Vec::new();
+    let mut copied = 0;
+    for element in paths {
+        let Some(inner) = &element.inner else {
+            continue;
+        };
+        let value = element.text();
+        let relative = relative_image_path(&value, cnc_base_path);
+        if relative == value {
+            continue;
+        }
+        output.push_str(&content[copied..inner.start]);
+        output.push_str(&escape_text(&relative));
+        copied = inner.end;
+        changes.push((value, relative));
+    }
+    output.push_str(&content[copied..]);
+    (output, changes)
+}
+
+/// The `<path>` elements below `element`, in document order.
+fn path_elements<'a>(element: &'a XmlElement, paths: &mut Vec<&'a XmlElement>) {
+    for child in element.child_elements() {
+        // A path holding markup is not one the export wrote
+        let text_only = child
+            .children
+            .iter()
+            .all(|node| matches!(node.kind, XmlNodeKind::Text(_) | XmlNodeKind::CData(_)));
+        if child.name == "path" && text_only {
+            paths.push(child);
+        } else {
+            path_elements(child, paths);
+        }
+    }
+}
+
+/// Puts the import in `staging` in place of `destination`. Files already in
+/// `destination` that the import does not replace are copied into `staging`
+/// first; then the two folders are swapped the way a deploy swaps them.
+fn move_into_place(staging: &Path, destination: &Path) -> Result<(), String> {
+    if destination.exists() {
+        copy_missing(destination, staging)?;
+    }
+    match_permissions(staging, destination)?;
+    if let Some(backup) = switch(staging, destination)? {
+        fs::remove_dir_all(&backup).map_err(|e| {
+            format!(
+                "Imported into {}, but failed to remove the folder it replaced, now at {}: {}",
+                destination.display(),
+                backup.display(),
+                e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Copies every file and folder in `from` that `to` does not have.
+fn copy_missing(from: &Path, to: &Path) -> Result<(), String> {
+    for entry in walkdir::WalkDir::new(from).min_depth(1) {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+        let path = relative_path(from, entry.path());
+        let target = to.join(&path);
+        if target.exists() {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+        } else {
+            fs::copy(entry.path(), &target)
+                .map_err(|e| format!("Failed to copy {}: {}", path, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// The `vcp` folder in `source`: `source` itself when it has `skins/`, or
+/// its `vcp` subfolder.
+fn folder_root(source: &Path) -> Result<PathBuf, String> {
+    if source.join("skins").is_dir() {
+        Ok(source.to_path_buf())
+    } else if source.join("vcp").join("skins").is_dir() {
+        Ok(source.join("vcp"))
+    } else {
+        Err(format!("No skins folder in {}", source.display()))
+    }
+}
+
+fn is_skin(path: &str) -> bool {
+    let path = path.to_ascii_lowercase();
+    path.starts_with("skins/") && path.ends_with(".vcp")
+}
+
+/// Zip entries are written below the destination, so they must not climb
+/// out of it.
+pub fn check_entry_path(path: &str) -> Result<(), String> {
+    let unsafe_part = path
+        .split('/')
+        .any(|part| part.is_empty() || part == "." || part == ".." || part.contains(':'));
+    if unsafe_part {
+        return Err(format!("Refusing to import '{}' from the zip", path));
+    }
+    Ok(())
+}
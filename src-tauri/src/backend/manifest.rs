@@ -0,0 +1,203 @@
+use super::ignore::IgnoreRules;
+use super::models::{ExportManifest, ManifestFile, VerifyIssue, VerifyIssueKind, VerifyReport};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// Manifest file name, in the `vcp/` folder of an export.
+pub const MANIFEST_FILE: &str = ".vcp-manifest.json";
+
+/// Version written into new manifests.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Passes everything written on to `inner` while taking its SHA-256 and
+/// size, so files are hashed as they are streamed into an export.
+pub struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        HashingWriter {
+            inner,
+            hasher: Sha256::new(),
+            size: 0,
+        }
+    }
+
+    /// The manifest entry for what was written, as `path`.
+    pub fn finish(self, path: String) -> ManifestFile {
+        ManifestFile {
+            path,
+            size: self.size,
+            sha256: to_hex(&self.hasher.finalize()),
+        }
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Checks an export zip, or a folder it was unpacked or deployed to, against
+/// the manifest inside it. For a folder, give the `vcp` folder or the one
+/// holding it.
+pub fn verify_export(path: &Path) -> Result<VerifyReport, String> {
+    let (manifest, found) = if path.is_dir() {
+        verify_folder(path)?
+    } else {
+        verify_zip(path)?
+    };
+
+    let mut issues = Vec::new();
+    for file in &manifest.files {
+        let kind = match found.get(&file.path) {
+            None => VerifyIssueKind::Missing,
+            Some(actual) if actual.sha256 != file.sha256 || actual.size != file.size => {
+                VerifyIssueKind::Modified
+            }
+            Some(_) => continue,
+        };
+        issues.push(VerifyIssue {
+            kind,
+            path: file.path.clone(),
+        });
+    }
+    for path in found.keys() {
+        if !manifest.files.iter().any(|file| &file.path == path) {
+            issues.push(VerifyIssue {
+                kind: VerifyIssueKind::Unexpected,
+                path: path.clone(),
+            });
+        }
+    }
+    Ok(VerifyReport { manifest, issues })
+}
+
+pub fn parse_manifest(content: &str) -> Result<ExportManifest, String> {
+    let manifest: ExportManifest =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse manifest: {}", e))?;
+    if manifest.version == 0 || manifest.version > MANIFEST_VERSION {
+        return Err(format!(
+            "Unsupported manifest version {}, this editor reads version {}",
+            manifest.version, MANIFEST_VERSION
+        ));
+    }
+    Ok(manifest)
+}
+
+type Found = BTreeMap<String, ManifestFile>;
+
+fn verify_zip(path: &Path) -> Result<(ExportManifest, Found), String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open zip file: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip file: {}", e))?;
+
+    let mut manifest = None;
+    let mut found = BTreeMap::new();
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read zip file: {}", e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        // Files outside `vcp/` keep their full name and show up as unexpected
+        let name = entry.name().replace('\\', "/");
+        let name = name.strip_prefix("vcp/").unwrap_or(&name).to_string();
+        if name == MANIFEST_FILE {
+            let mut content = String::new();
+            entry
+                .read_to_string(&mut content)
+                .map_err(|e| format!("Failed to read manifest: {}", e))?;
+            manifest = Some(parse_manifest(&content)?);
+        } else {
+            found.insert(name.clone(), hash_reader(&mut entry, name)?);
+        }
+    }
+    let manifest = manifest.ok_or_else(|| format!("{} has no manifest", path.display()))?;
+    Ok((manifest, found))
+}
+
+fn verify_folder(path: &Path) -> Result<(ExportManifest, Found), String> {
+    let root = if path.join(MANIFEST_FILE).is_file() {
+        path.to_path_buf()
+    } else if path.join("vcp").join(MANIFEST_FILE).is_file() {
+        path.join("vcp")
+    } else {
+        return Err(format!("{} has no manifest", path.display()));
+    };
+    let content = fs::read_to_string(root.join(MANIFEST_FILE))
+        .map_err(|e| format!("Failed to read manifest: {}", e))?;
+    let manifest = parse_manifest(&content)?;
+
+    // Leave out the litter exports skip, which the OS may add after deploying,
+    // but not what `.vcpignore` brought back and the manifest lists
+    let rules = IgnoreRules::parse("");
+    let listed = |path: &str| manifest.files.iter().any(|file| file.path == path);
+    let lists_within = |folder: &str| {
+        let prefix = format!("{}/", folder);
+        manifest
+            .files
+            .iter()
+            .any(|file| file.path.starts_with(&prefix))
+    };
+    let mut found = BTreeMap::new();
+    let walker = walkdir::WalkDir::new(&root)
+        .follow_links(true)
+        .into_iter()
+        .filter_entry(|entry| {
+            let path = relative_path(&root, entry.path());
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || !rules.is_ignored(&path, true)
+                || lists_within(&path)
+        });
+    for entry in walker {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", root.display(), e))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let name = relative_path(&root, entry.path());
+        if name == MANIFEST_FILE || (!listed(&name) && rules.is_ignored_file(&name)) {
+            continue;
+        }
+        let mut file =
+            fs::File::open(entry.path()).map_err(|e| format!("Failed to open {}: {}", name, e))?;
+        found.insert(name.clone(), hash_reader(&mut file, name)?);
+    }
+    Ok((manifest, found))
+}
+
+fn hash_reader(reader: &mut impl Read, path: String) -> Result<ManifestFile, String> {
+    let mut writer = HashingWriter::new(io::sink());
+    io::copy(reader, &mut writer).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    Ok(writer.finish(path))
+}
+
+/// `path` relative to `root`, separated by `/` on every platform.
+pub fn relative_path(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -1,2 +1,21 @@
+pub mod assets;
+pub mod button;
+pub mod catalog;
+pub mod deploy;
+pub mod documents;
+pub mod encoding;
+pub mod events;
+pub mod export;
+pub mod history;
+pub mod ignore;
+pub mod import;
+pub mod manifest;
 pub mod models;
 pub mod parser;
+pub mod paths;
+pub mod plc;
+pub mod plc_stub;
+pub mod project;
+pub mod settings;
+pub mod validate;
+pub mod xml;
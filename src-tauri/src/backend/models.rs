@@ -11,6 +11,9 @@ pub struct Border {
     pub outline_thickness: i32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub plc_word: Option<PlcWord>,
+    /// Markup this border was read from, written back verbatim while unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +36,9 @@ pub struct Image {
     pub row_span: i32,
     pub column_span: i32,
     pub path: String,
+    /// Markup this image was read from, written back verbatim while unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +54,12 @@ pub struct Button {
     pub file: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default_image: Option<String>,
+    /// Contents of `Buttons/<name>/<name>.xml`, resolved when the skin is opened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition: Option<VcpButton>,
+    /// Markup this button was read from, written back verbatim while unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,6 +89,61 @@ pub struct VcpDocument {
     pub images: Vec<Image>,
     #[serde(default)]
     pub buttons: Vec<Button>,
+    /// Original file structure, present for documents loaded from disk
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<DocumentLayout>,
+    /// Encoding and line endings the file was read with, reused on save
+    #[serde(default)]
+    pub format: FileFormat,
+}
+
+/// Everything needed to write a loaded skin back byte-for-byte: the text
+/// around the root element and the order of its children, including
+/// comments and elements the editor does not understand.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentLayout {
+    /// Text up to and including the root start tag
+    pub head: String,
+    pub nodes: Vec<LayoutNode>,
+    /// Root end tag and everything after it
+    pub tail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LayoutNode {
+    /// Whitespace, comments and unrecognised elements, kept verbatim
+    Markup {
+        text: String,
+    },
+    /// A single-valued element such as `<background>` and its original markup
+    Field {
+        name: String,
+        source: String,
+    },
+    /// Position of a border, image or button, identified by its original markup
+    Border {
+        source: String,
+    },
+    Image {
+        source: String,
+    },
+    Button {
+        source: String,
+    },
+}
+
+impl LayoutNode {
+    /// Original markup of the element at this position, if any.
+    pub fn source(&self) -> Option<&str> {
+        match self {
+            LayoutNode::Markup { .. } => None,
+            LayoutNode::Field { source, .. }
+            | LayoutNode::Border { source }
+            | LayoutNode::Image { source }
+            | LayoutNode::Button { source } => Some(source),
+        }
+    }
 }
 
 impl Default for VcpDocument {
@@ -96,6 +163,701 @@ impl Default for VcpDocument {
             borders: Vec::new(),
             images: Vec::new(),
             buttons: Vec::new(),
+            layout: None,
+            format: FileFormat::default(),
+        }
+    }
+}
+
+/// How a text file is stored on disk, so it can be written back the same way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFormat {
+    pub encoding: TextEncoding,
+    /// Whether the file starts with a byte order mark
+    pub bom: bool,
+    pub line_ending: LineEnding,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    Utf16le,
+    Utf16be,
+    Windows1252,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl TextEncoding {
+    /// Name used for this encoding in an XML declaration.
+    pub fn xml_name(self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16le | TextEncoding::Utf16be => "UTF-16",
+            TextEncoding::Windows1252 => "windows-1252",
+        }
+    }
+}
+
+/// How the editor formats the XML it writes.
+///
+/// The defaults match the skins CNC12 ships: CRLF line endings, four-space
+/// indentation, double-quoted attributes and no XML declaration. Elements
+/// keep the order they were drawn in unless sorting is switched on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SerializeOptions {
+    /// `None` leaves line breaks as they are in the document
+    pub line_ending: Option<LineEnding>,
+    pub indent: String,
+    pub declaration: bool,
+    pub attribute_quote: AttributeQuote,
+    pub element_order: ElementOrder,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        SerializeOptions {
+            line_ending: Some(LineEnding::Crlf),
+            indent: "    ".to_string(),
+            declaration: false,
+            attribute_quote: AttributeQuote::Double,
+            element_order: ElementOrder::Original,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeQuote {
+    #[default]
+    Double,
+    Single,
+}
+
+impl AttributeQuote {
+    pub fn as_char(self) -> char {
+        match self {
+            AttributeQuote::Double => '"',
+            AttributeQuote::Single => '\'',
         }
     }
 }
+
+/// Order borders, images and buttons are written in.
+///
+/// CNC12 draws elements in file order, so later elements paint over earlier
+/// ones. Sorting gives stable diffs but changes which overlapping element ends
+/// up on top, so it is off by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElementOrder {
+    /// Document order
+    #[default]
+    Original,
+    /// Top to bottom, then left to right
+    Sorted,
+}
+
+/// Per-project settings, stored in the VCP root folder.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectSettings {
+    pub output: SerializeOptions,
+    pub validation: ValidationSettings,
+    /// Machine the skins are exported for unless an export names another
+    pub machine: MachineProfile,
+    /// Folder the CNC reads the `vcp` folder from, for exports that do not
+    /// name one; `None` for CNC12's own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cnc_base_path: Option<String>,
+}
+
+/// Whether save and export refuse documents with validation errors.
+///
+/// Saving work in progress is allowed by default; exporting a broken skin to
+/// the CNC is not.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidationSettings {
+    pub block_save: bool,
+    /// Also refuses exports with missing, unreadable or empty assets
+    pub block_export: bool,
+}
+
+impl Default for ValidationSettings {
+    fn default() -> Self {
+        ValidationSettings {
+            block_save: false,
+            block_export: true,
+        }
+    }
+}
+
+/// Centroid controller board.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Controller {
+    #[default]
+    Acorn,
+    AcornSix,
+    Oak,
+    Hickory,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MachineType {
+    #[default]
+    Mill,
+    Lathe,
+    Router,
+    Plasma,
+}
+
+/// Controller and machine type, which together decide the skin file CNC12
+/// loads, e.g. `acorn_mill_vcp_skin.vcp`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MachineProfile {
+    pub controller: Controller,
+    pub machine: MachineType,
+}
+
+/// File name an exported skin gets in `skins/` on the CNC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkinTarget {
+    /// The skin file CNC12 loads for this machine
+    Machine(MachineProfile),
+    /// Any other name ending in `.vcp`
+    File(String),
+}
+
+/// A skin file to include in an export.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportSkin {
+    /// Path of the skin, absolute or relative to the VCP root
+    pub source: String,
+    /// `None` names it for the project's machine
+    #[serde(default)]
+    pub target: Option<SkinTarget>,
+}
+
+/// What an export contains and where it came from, stored in the zip as
+/// `vcp/.vcp-manifest.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub version: u32,
+    pub editor_version: String,
+    /// UTC time of the export, RFC 3339
+    pub created: String,
+    /// VCP root the project was exported from
+    pub source: String,
+    pub cnc_base_path: String,
+    pub skins: Vec<ManifestSkin>,
+    pub options: SerializeOptions,
+    /// Every file in the export but the manifest, sorted by path
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestSkin {
+    /// File name in `skins/`
+    pub file: String,
+    /// Skin it was exported from; `None` for a document never saved
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Path below `vcp/`, with forward slashes
+    pub path: String,
+    pub size: u64,
+    /// Lowercase hex
+    pub sha256: String,
+}
+
+/// Result of checking an export against its manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub manifest: ExportManifest,
+    pub issues: Vec<VerifyIssue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyIssue {
+    pub kind: VerifyIssueKind,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyIssueKind {
+    Missing,
+    /// Contents differ from the checksum
+    Modified,
+    /// Not listed in the manifest
+    Unexpected,
+}
+
+/// What importing an export or a deployed `vcp` folder wrote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// VCP root the project was written to
+    pub root: String,
+    /// Files written, relative to `root`
+    pub files: Vec<String>,
+    /// Image paths in skins that were made relative to the project again
+    pub rewritten: Vec<RewrittenPath>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RewrittenPath {
+    /// Skin file, relative to the VCP root
+    pub skin: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// Where an export was written and the asset problems found on the way.
+/// Problems only stop an export when the project's validation settings say
+/// so.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportReport {
+    pub zip: String,
+    pub asset_issues: Vec<AssetIssue>,
+}
+
+/// What deploying to, or rolling back, a CNC's `vcp` folder did.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeployReport {
+    /// The `vcp` folder that was replaced
+    pub target: String,
+    /// Where the folder it replaced was moved, if there was one
+    pub backup: Option<String>,
+    /// Backup put back in place, when rolling back
+    pub restored: Option<String>,
+    /// Asset problems the export found, when deploying a project
+    #[serde(default)]
+    pub asset_issues: Vec<AssetIssue>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// A problem found while reading a skin or PLC source, located by 1-based
+/// line and column.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseDiagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// A rule broken by a document, found by `validate::validate_document`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Issue {
+    /// Stable identifier of the broken rule, e.g. `out-of-grid`
+    pub rule: String,
+    pub severity: Severity,
+    /// Offending element, `None` for document-wide problems
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub element: Option<ElementRef>,
+    pub message: String,
+}
+
+/// Position of an element in one of the document's element lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElementRef {
+    pub kind: ElementKind,
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElementKind {
+    Border,
+    Image,
+    Button,
+}
+
+/// A file a skin or button definition points to that CNC12 could not load.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetIssue {
+    pub kind: AssetIssueKind,
+    /// Asset path relative to the VCP root, with forward slashes
+    pub path: String,
+    /// Skin, button definition or button folder that needs the asset
+    pub referenced_by: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetIssueKind {
+    Missing,
+    Unreadable,
+    /// Zero bytes long, typically an unsynced cloud placeholder
+    Empty,
+}
+
+/// A problem with the skin events a project's buttons are bound to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventIssue {
+    /// Stable identifier of the broken rule, e.g. `duplicate-event`
+    pub rule: String,
+    pub severity: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<i32>,
+    /// Buttons involved, sorted by name
+    #[serde(default)]
+    pub buttons: Vec<String>,
+    pub message: String,
+}
+
+/// Symbol definitions read from a PLC program or `SkinEventsList.txt`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlcDefinitions {
+    pub skin_events: Vec<SkinEventDefinition>,
+    pub symbols: Vec<PlcSymbol>,
+    #[serde(default)]
+    pub diagnostics: Vec<ParseDiagnostic>,
+}
+
+/// `Name IS SV_SKIN_EVENT_n`, with the `; Row N Column M` hint if present.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkinEventDefinition {
+    pub name: String,
+    pub number: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<i32>,
+    /// The definition is commented out with a leading `;`
+    pub commented: bool,
+    pub line: usize,
+}
+
+/// `Name IS OUTn`, `Name IS INPn` or `Name IS MEMn`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlcSymbol {
+    pub name: String,
+    pub kind: PlcSymbolKind,
+    pub number: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// The definition is commented out with a leading `;`
+    pub commented: bool,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlcSymbolKind {
+    Out,
+    Inp,
+    Mem,
+}
+
+/// How a skin's buttons line up with a machine's PLC program.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlcCrossCheck {
+    /// Buttons raising a skin event the PLC never defines
+    pub undefined_events: Vec<ButtonBinding>,
+    /// `plc_output` numbers with no `OUT` definition
+    pub undefined_outputs: Vec<ButtonBinding>,
+    /// `plc_input` numbers with no `INP` definition
+    pub undeclared_inputs: Vec<ButtonBinding>,
+    /// PLC skin events no button in the skin raises
+    pub untriggered_events: Vec<SkinEventDefinition>,
+    /// Buttons without a definition, which could not be checked
+    pub unresolved_buttons: Vec<String>,
+}
+
+/// A number a button's definition refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ButtonBinding {
+    pub button: String,
+    pub number: i32,
+}
+
+/// Skin events, LED outputs and inputs offered by the button editor.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SymbolCatalog {
+    /// Set once the catalog is refreshed from a PLC program, which then lists
+    /// everything the machine defines. Otherwise the entries add to or
+    /// override the built-in Acorn defaults.
+    pub replaces_defaults: bool,
+    pub skin_events: Vec<CatalogEntry>,
+    pub outputs: Vec<CatalogEntry>,
+    pub inputs: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub number: i32,
+    /// PLC symbol name, e.g. `SkinCoolFlood_M`
+    pub name: String,
+    /// Text shown in dropdowns
+    #[serde(default)]
+    pub label: String,
+}
+
+/// What refreshing a catalog from PLC source changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogDiff {
+    pub skin_events: CatalogListDiff,
+    pub outputs: CatalogListDiff,
+    pub inputs: CatalogListDiff,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogListDiff {
+    pub added: Vec<CatalogEntry>,
+    pub removed: Vec<CatalogEntry>,
+    /// Numbers whose symbol name changed, as (before, after)
+    pub renamed: Vec<(CatalogEntry, CatalogEntry)>,
+}
+
+/// A parsed skin together with everything the parser had to default or skip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadedDocument {
+    pub document: VcpDocument,
+    #[serde(default)]
+    pub diagnostics: Vec<ParseDiagnostic>,
+    /// Buttons whose definition file exists but could not be read or parsed
+    #[serde(default)]
+    pub button_errors: Vec<ButtonLoadError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ButtonLoadError {
+    pub button: String,
+    pub message: String,
+}
+
+/// A border, image or button, as added to or removed from a skin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Element {
+    Border(Border),
+    Image(Image),
+    Button(Box<Button>),
+}
+
+/// One undoable change to a skin. Elements are addressed by kind and index
+/// in their list, as in `ElementRef`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EditCommand {
+    /// Inserts at `index`, or appends when there is none
+    AddElement {
+        element: Box<Element>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<usize>,
+    },
+    DeleteElement {
+        kind: ElementKind,
+        index: usize,
+    },
+    /// Places the element's top-left cell at `row`, `column`
+    MoveElement {
+        kind: ElementKind,
+        index: usize,
+        row: i32,
+        column: i32,
+    },
+    /// `None` clears a button's span; borders and images need both spans
+    ResizeElement {
+        kind: ElementKind,
+        index: usize,
+        #[serde(default)]
+        row_span: Option<i32>,
+        #[serde(default)]
+        column_span: Option<i32>,
+    },
+    /// Sets a field of an element, or of the skin itself (`background`,
+    /// `on_click`, `on_hover`) when `element` is absent. `null` clears
+    /// optional fields.
+    SetProperty {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        element: Option<ElementRef>,
+        name: String,
+        value: serde_json::Value,
+    },
+    ResizeGrid {
+        row_count: i32,
+        column_count: i32,
+    },
+}
+
+/// Undo history of the open skin, as shown in the Edit menu.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryList {
+    /// Labels of every recorded step, oldest first
+    pub entries: Vec<String>,
+    /// Number of steps currently applied; later entries can be redone
+    pub position: usize,
+    /// Position the skin was last opened or saved at, if still reachable
+    pub saved_position: Option<usize>,
+}
+
+/// A whole project as one data file: every skin in `skins/` and every button
+/// definition in `Buttons/`. The format is described in
+/// `docs/PROJECT_FORMAT.md`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VcpProject {
+    /// Format version the file was written with
+    pub version: u32,
+    #[serde(default)]
+    pub skins: Vec<ProjectSkin>,
+    #[serde(default)]
+    pub buttons: Vec<VcpButton>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSkin {
+    /// File name in `skins/`, e.g. `acorn_mill_vcp_skin.vcp`
+    pub file: String,
+    pub document: VcpDocument,
+}
+
+/// Data file formats a project can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+    Json,
+    Yaml,
+}
+
+/// Identifies a document open in the editor for as long as it stays open.
+pub type DocumentId = u32;
+
+/// A document just created or opened, with the id later commands use.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenedDocument {
+    pub id: DocumentId,
+    #[serde(flatten)]
+    pub loaded: LoadedDocument,
+}
+
+/// What the editor knows about an open document besides its contents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentInfo {
+    pub id: DocumentId,
+    /// `None` until the document is saved
+    pub path: Option<String>,
+    pub dirty: bool,
+    /// Label of the window editing the document
+    pub window: Option<String>,
+}
+
+/// A button definition from `Buttons/<name>/<name>.xml`.
+///
+/// Serialized in camelCase so it matches `VcpButtonDefinition` on the frontend.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VcpButton {
+    /// Folder name of the button; not stored in the XML itself
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skin_event_num: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_click_swap: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<ButtonRun>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plc_output: Option<PlcOutput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plc_input: Option<PlcInput>,
+    /// Comments and unrecognised elements, written back verbatim
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<ButtonExtra>,
+    #[serde(default)]
+    pub format: FileFormat,
+}
+
+/// A comment or unrecognised element of a button definition, with where it
+/// goes when the definition is written back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ButtonExtra {
+    /// Known element it follows, such as `run`; `None` when it comes before
+    /// all of them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    pub markup: String,
+}
+
+impl VcpButton {
+    /// Every image file this definition refers to, in file order.
+    pub fn referenced_images(&self) -> Vec<&str> {
+        let output = self.plc_output.as_ref();
+        let input = self.plc_input.as_ref();
+        [
+            self.default_image.as_deref(),
+            self.on_click_swap.as_deref(),
+            output.and_then(|o| o.image_on.as_deref()),
+            output.and_then(|o| o.image_off.as_deref()),
+            input.and_then(|i| i.image_active.as_deref()),
+            input.and_then(|i| i.image_inactive.as_deref()),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|image| !image.is_empty())
+        .collect()
+    }
+}
+
+/// What a button executes when pressed: `<run><line>` or `<run><macro>`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum ButtonRun {
+    Line(String),
+    Macro(String),
+}
+
+/// LED indicator driven by a PLC output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlcOutput {
+    pub number: i32,
+    pub color_on: String,
+    pub color_off: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_on: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_off: Option<String>,
+}
+
+/// Image swap driven by a PLC input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlcInput {
+    pub number: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_active: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_inactive: Option<String>,
+}
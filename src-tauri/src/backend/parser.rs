@@ -1,50 +1,214 @@
+use super::button::{button_xml_path, load_button};
+use super::encoding::{encode_text, read_text_file, to_crlf};
 use super::models::*;
+use super::xml::{
+    escape_attribute, escape_text, line_column, parse_document, XmlElement, XmlNodeKind,
+};
 use std::fs;
+use std::path::Path;
 
 pub fn parse_vcp(content: &str) -> Result<VcpDocument, String> {
+    parse_vcp_with_diagnostics(content).map(|loaded| loaded.document)
+}
+
+/// Parses a skin and reports every value that was malformed, unknown or
+/// filled in with a default, with its line and column in `content`.
+pub fn parse_vcp_with_diagnostics(content: &str) -> Result<LoadedDocument, String> {
     let mut doc = VcpDocument::default();
-    
-    // Simple XML parsing without external dependencies
-    let lines: Vec<&str> = content.lines().collect();
-    let mut i = 0;
-    
-    while i < lines.len() {
-        let line = lines[i].trim();
-        
-        if line.starts_with("<background>") {
-            doc.background = extract_text_content(line);
-        } else if line.starts_with("<column_count>") {
-            doc.column_count = extract_text_content(line).parse().unwrap_or(6);
-        } else if line.starts_with("<row_count>") {
-            doc.row_count = extract_text_content(line).parse().unwrap_or(14);
-        } else if line.starts_with("<border>") {
-            let border = parse_border(&lines, &mut i)?;
-            doc.borders.push(border);
-        } else if line.starts_with("<image>") {
-            let image = parse_image(&lines, &mut i)?;
-            doc.images.push(image);
-        } else if line.starts_with("<button ") {
-            let button = parse_button(line)?;
-            doc.buttons.push(button);
-        } else if line.starts_with("<on_click>") {
-            doc.on_click = Some(parse_on_click(&lines, &mut i)?);
-        } else if line.starts_with("<on_hover>") {
-            doc.on_hover = Some(parse_on_hover(&lines, &mut i)?);
-        }
-        
-        i += 1;
+    let tree = parse_document(content)?;
+    let root = tree.root().ok_or("No root element found in VCP file")?;
+    let mut ctx = ParseContext::new(content);
+    let mut nodes = Vec::new();
+
+    if root.name != "vcp_skin" {
+        ctx.report(
+            root,
+            Severity::Warning,
+            format!("Expected root element <vcp_skin>, found <{}>", root.name),
+        );
+    }
+
+    for node in &root.children {
+        let source = content[node.span.clone()].to_string();
+        let element = match &node.kind {
+            XmlNodeKind::Element(element) => element,
+            _ => {
+                nodes.push(LayoutNode::Markup { text: source });
+                continue;
+            }
+        };
+
+        let layout_node = match element.name.as_str() {
+            "background" => {
+                doc.background = element.text();
+                field_node(element, source)
+            }
+            "column_count" => {
+                doc.column_count = ctx.int(element, 6);
+                field_node(element, source)
+            }
+            "row_count" => {
+                doc.row_count = ctx.int(element, 14);
+                field_node(element, source)
+            }
+            "on_click" => {
+                doc.on_click = Some(parse_on_click(&mut ctx, element));
+                field_node(element, source)
+            }
+            "on_hover" => {
+                doc.on_hover = Some(parse_on_hover(&mut ctx, element));
+                field_node(element, source)
+            }
+            "border" => {
+                let mut border = parse_border(&mut ctx, element);
+                border.source = Some(source.clone());
+                doc.borders.push(border);
+                LayoutNode::Border { source }
+            }
+            "image" => {
+                let mut image = parse_image(&mut ctx, element);
+                image.source = Some(source.clone());
+                doc.images.push(image);
+                LayoutNode::Image { source }
+            }
+            "button" => {
+                let mut button = parse_button(&mut ctx, element);
+                button.source = Some(source.clone());
+                doc.buttons.push(button);
+                LayoutNode::Button { source }
+            }
+            _ => {
+                ctx.unknown(element, root);
+                LayoutNode::Markup { text: source }
+            }
+        };
+        nodes.push(layout_node);
+    }
+
+    ctx.require(root, "background", &doc.background);
+    ctx.require(root, "column_count", &doc.column_count);
+    ctx.require(root, "row_count", &doc.row_count);
+
+    // A self-closing root has nowhere to put children, so it is written canonically
+    doc.layout = root.inner.as_ref().map(|inner| DocumentLayout {
+        head: content[..inner.start].to_string(),
+        nodes,
+        tail: content[inner.end..].to_string(),
+    });
+
+    Ok(LoadedDocument {
+        document: doc,
+        diagnostics: ctx.diagnostics,
+        button_errors: Vec::new(),
+    })
+}
+
+fn field_node(element: &XmlElement, source: String) -> LayoutNode {
+    LayoutNode::Field {
+        name: element.name.clone(),
+        source,
     }
-    
-    Ok(doc)
 }
 
-fn extract_text_content(line: &str) -> String {
-    let start = line.find('>').unwrap_or(0) + 1;
-    let end = line.rfind('<').unwrap_or(line.len());
-    line[start..end].to_string()
+/// Collects diagnostics while the XML tree is mapped onto the document model.
+struct ParseContext<'a> {
+    content: &'a str,
+    diagnostics: Vec<ParseDiagnostic>,
+}
+
+impl<'a> ParseContext<'a> {
+    fn new(content: &'a str) -> Self {
+        ParseContext {
+            content,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, element: &XmlElement, severity: Severity, message: String) {
+        let (line, column) = line_column(self.content, element.span.start);
+        self.diagnostics.push(ParseDiagnostic {
+            line,
+            column,
+            severity,
+            message,
+        });
+    }
+
+    /// Parses the element's text as an integer, falling back to `default`.
+    fn int(&mut self, element: &XmlElement, default: i32) -> i32 {
+        let text = element.text();
+        self.number(element, &format!("<{}>", element.name), &text, default)
+    }
+
+    /// Parses the named attribute as an integer, falling back to `default`.
+    fn attr_int(&mut self, element: &XmlElement, name: &str, default: i32) -> i32 {
+        let text = element
+            .attribute(name)
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        self.number(element, &format!("attribute {}", name), &text, default)
+    }
+
+    fn number(&mut self, element: &XmlElement, what: &str, text: &str, default: i32) -> i32 {
+        match text.parse() {
+            Ok(value) => value,
+            Err(_) => {
+                self.report(
+                    element,
+                    Severity::Warning,
+                    format!(
+                        "Invalid number '{}' in {}, using default {}",
+                        text, what, default
+                    ),
+                );
+                default
+            }
+        }
+    }
+
+    fn bool(&mut self, element: &XmlElement, default: bool) -> bool {
+        match element.text().as_str() {
+            "true" => true,
+            "false" => false,
+            other => {
+                self.report(
+                    element,
+                    Severity::Warning,
+                    format!(
+                        "Invalid boolean '{}' in <{}>, using default {}",
+                        other, element.name, default
+                    ),
+                );
+                default
+            }
+        }
+    }
+
+    fn unknown(&mut self, element: &XmlElement, parent: &XmlElement) {
+        self.report(
+            element,
+            Severity::Warning,
+            format!("Unknown element <{}> in <{}>", element.name, parent.name),
+        );
+    }
+
+    /// Reports a child element that was absent and therefore left at `default`.
+    fn require(&mut self, parent: &XmlElement, name: &str, default: &dyn std::fmt::Display) {
+        if parent.child(name).is_none() {
+            self.report(
+                parent,
+                Severity::Info,
+                format!(
+                    "Missing <{}> in <{}>, using default {}",
+                    name, parent.name, default
+                ),
+            );
+        }
+    }
 }
 
-fn parse_border(lines: &[&str], index: &mut usize) -> Result<Border, String> {
+fn parse_border(ctx: &mut ParseContext, element: &XmlElement) -> Border {
     let mut border = Border {
         row_start: 1,
         column_start: 1,
@@ -54,39 +218,35 @@ fn parse_border(lines: &[&str], index: &mut usize) -> Result<Border, String> {
         outline_color: "#000000".to_string(),
         outline_thickness: 1,
         plc_word: None,
+        source: None,
     };
-    
-    *index += 1;
-    while *index < lines.len() {
-        let line = lines[*index].trim();
-        
-        if line.starts_with("</border>") {
-            break;
-        } else if line.starts_with("<row_start>") {
-            border.row_start = extract_text_content(line).parse().unwrap_or(1);
-        } else if line.starts_with("<column_start>") {
-            border.column_start = extract_text_content(line).parse().unwrap_or(1);
-        } else if line.starts_with("<row_span>") {
-            border.row_span = extract_text_content(line).parse().unwrap_or(1);
-        } else if line.starts_with("<column_span>") {
-            border.column_span = extract_text_content(line).parse().unwrap_or(1);
-        } else if line.starts_with("<fill>") {
-            border.fill = extract_text_content(line);
-        } else if line.starts_with("<outline_color>") {
-            border.outline_color = extract_text_content(line);
-        } else if line.starts_with("<outline_thickness>") {
-            border.outline_thickness = extract_text_content(line).parse().unwrap_or(1);
-        } else if line.starts_with("<plc_word>") {
-            border.plc_word = Some(parse_plc_word(lines, index)?);
+
+    for child in element.child_elements() {
+        match child.name.as_str() {
+            "row_start" => border.row_start = ctx.int(child, 1),
+            "column_start" => border.column_start = ctx.int(child, 1),
+            "row_span" => border.row_span = ctx.int(child, 1),
+            "column_span" => border.column_span = ctx.int(child, 1),
+            "fill" => border.fill = child.text(),
+            "outline_color" => border.outline_color = child.text(),
+            "outline_thickness" => border.outline_thickness = ctx.int(child, 1),
+            "plc_word" => border.plc_word = Some(parse_plc_word(ctx, child)),
+            _ => ctx.unknown(child, element),
         }
-        
-        *index += 1;
     }
-    
-    Ok(border)
+
+    ctx.require(element, "row_start", &border.row_start);
+    ctx.require(element, "column_start", &border.column_start);
+    ctx.require(element, "row_span", &border.row_span);
+    ctx.require(element, "column_span", &border.column_span);
+    ctx.require(element, "fill", &border.fill);
+    ctx.require(element, "outline_color", &border.outline_color);
+    ctx.require(element, "outline_thickness", &border.outline_thickness);
+
+    border
 }
 
-fn parse_plc_word(lines: &[&str], index: &mut usize) -> Result<PlcWord, String> {
+fn parse_plc_word(ctx: &mut ParseContext, element: &XmlElement) -> PlcWord {
     let mut plc = PlcWord {
         number: 0,
         color: "#000000".to_string(),
@@ -98,73 +258,64 @@ fn parse_plc_word(lines: &[&str], index: &mut usize) -> Result<PlcWord, String>
         marginbottom: 0,
         percentage: false,
     };
-    
-    *index += 1;
-    while *index < lines.len() {
-        let line = lines[*index].trim();
-        
-        if line.starts_with("</plc_word>") {
-            break;
-        } else if line.starts_with("<number>") {
-            plc.number = extract_text_content(line).parse().unwrap_or(0);
-        } else if line.starts_with("<color>") {
-            plc.color = extract_text_content(line);
-        } else if line.starts_with("<fontsize>") {
-            plc.fontsize = extract_text_content(line).parse().unwrap_or(12);
-        } else if line.starts_with("<font>") {
-            plc.font = extract_text_content(line);
-        } else if line.starts_with("<fontstyle>") {
-            plc.fontstyle = extract_text_content(line);
-        } else if line.starts_with("<verticalalignment>") {
-            plc.verticalalignment = extract_text_content(line);
-        } else if line.starts_with("<horizontalalignment>") {
-            plc.horizontalalignment = extract_text_content(line);
-        } else if line.starts_with("<marginbottom>") {
-            plc.marginbottom = extract_text_content(line).parse().unwrap_or(0);
-        } else if line.starts_with("<percentage>") {
-            plc.percentage = extract_text_content(line) == "true";
+
+    for child in element.child_elements() {
+        match child.name.as_str() {
+            "number" => plc.number = ctx.int(child, 0),
+            "color" => plc.color = child.text(),
+            "fontsize" => plc.fontsize = ctx.int(child, 12),
+            "font" => plc.font = child.text(),
+            "fontstyle" => plc.fontstyle = child.text(),
+            "verticalalignment" => plc.verticalalignment = child.text(),
+            "horizontalalignment" => plc.horizontalalignment = child.text(),
+            "marginbottom" => plc.marginbottom = ctx.int(child, 0),
+            "percentage" => plc.percentage = ctx.bool(child, false),
+            _ => ctx.unknown(child, element),
         }
-        
-        *index += 1;
     }
-    
-    Ok(plc)
+
+    ctx.require(element, "number", &plc.number);
+
+    plc
 }
 
-fn parse_image(lines: &[&str], index: &mut usize) -> Result<Image, String> {
+fn parse_image(ctx: &mut ParseContext, element: &XmlElement) -> Image {
     let mut image = Image {
         row_start: 1,
         column_start: 1,
         row_span: 1,
         column_span: 1,
         path: String::new(),
+        source: None,
     };
-    
-    *index += 1;
-    while *index < lines.len() {
-        let line = lines[*index].trim();
-        
-        if line.starts_with("</image>") {
-            break;
-        } else if line.starts_with("<row_start>") {
-            image.row_start = extract_text_content(line).parse().unwrap_or(1);
-        } else if line.starts_with("<column_start>") {
-            image.column_start = extract_text_content(line).parse().unwrap_or(1);
-        } else if line.starts_with("<row_span>") {
-            image.row_span = extract_text_content(line).parse().unwrap_or(1);
-        } else if line.starts_with("<column_span>") {
-            image.column_span = extract_text_content(line).parse().unwrap_or(1);
-        } else if line.starts_with("<path>") {
-            image.path = extract_text_content(line);
+
+    for child in element.child_elements() {
+        match child.name.as_str() {
+            "row_start" => image.row_start = ctx.int(child, 1),
+            "column_start" => image.column_start = ctx.int(child, 1),
+            "row_span" => image.row_span = ctx.int(child, 1),
+            "column_span" => image.column_span = ctx.int(child, 1),
+            "path" => image.path = child.text(),
+            _ => ctx.unknown(child, element),
         }
-        
-        *index += 1;
     }
-    
-    Ok(image)
+
+    ctx.require(element, "row_start", &image.row_start);
+    ctx.require(element, "column_start", &image.column_start);
+    ctx.require(element, "row_span", &image.row_span);
+    ctx.require(element, "column_span", &image.column_span);
+    if image.path.is_empty() {
+        ctx.report(
+            element,
+            Severity::Warning,
+            "Image has no <path>".to_string(),
+        );
+    }
+
+    image
 }
 
-fn parse_button(line: &str) -> Result<Button, String> {
+fn parse_button(ctx: &mut ParseContext, element: &XmlElement) -> Button {
     let mut button = Button {
         row: 1,
         column: 1,
@@ -173,222 +324,851 @@ fn parse_button(line: &str) -> Result<Button, String> {
         name: String::new(),
         file: None,
         default_image: None,
+        definition: None,
+        source: None,
     };
-    
-    // Parse attributes
-    if let Some(row_pos) = line.find("row=\"") {
-        let start = row_pos + 5;
-        if let Some(end) = line[start..].find('"') {
-            button.row = line[start..start + end].parse().unwrap_or(1);
-        }
-    }
-    
-    if let Some(col_pos) = line.find("column=\"") {
-        let start = col_pos + 8;
-        if let Some(end) = line[start..].find('"') {
-            button.column = line[start..start + end].parse().unwrap_or(1);
+
+    for attr in &element.attributes {
+        match attr.name.as_str() {
+            "row" => button.row = ctx.attr_int(element, "row", 1),
+            "column" => button.column = ctx.attr_int(element, "column", 1),
+            "row_span" => button.row_span = Some(ctx.attr_int(element, "row_span", 1)),
+            "column_span" => button.column_span = Some(ctx.attr_int(element, "column_span", 1)),
+            other => ctx.report(
+                element,
+                Severity::Warning,
+                format!("Unknown attribute {} on <button>", other),
+            ),
         }
     }
-    
-    if let Some(row_span_pos) = line.find("row_span=\"") {
-        let start = row_span_pos + 10;
-        if let Some(end) = line[start..].find('"') {
-            button.row_span = line[start..start + end].parse().ok();
+
+    for attr in ["row", "column"] {
+        if element.attribute(attr).is_none() {
+            ctx.report(
+                element,
+                Severity::Info,
+                format!("Missing attribute {} on <button>, using default 1", attr),
+            );
         }
     }
-    
-    if let Some(col_span_pos) = line.find("column_span=\"") {
-        let start = col_span_pos + 13;
-        if let Some(end) = line[start..].find('"') {
-            button.column_span = line[start..start + end].parse().ok();
-        }
+
+    // Button name is the element's text content
+    button.name = element.text();
+    if button.name.is_empty() {
+        ctx.report(element, Severity::Warning, "Button has no name".to_string());
     }
-    
-    // Parse button name (text content)
-    button.name = extract_text_content(line);
-    
-    Ok(button)
+
+    button
 }
 
-fn parse_on_click(lines: &[&str], index: &mut usize) -> Result<OnClick, String> {
+fn parse_on_click(ctx: &mut ParseContext, element: &XmlElement) -> OnClick {
     let mut on_click = OnClick {
         opacity: 100,
         outline_color: "#000000".to_string(),
     };
-    
-    *index += 1;
-    while *index < lines.len() {
-        let line = lines[*index].trim();
-        
-        if line.starts_with("</on_click>") {
-            break;
-        } else if line.starts_with("<opacity>") {
-            on_click.opacity = extract_text_content(line).parse().unwrap_or(100);
-        } else if line.starts_with("<outline_color>") {
-            on_click.outline_color = extract_text_content(line);
+
+    for child in element.child_elements() {
+        match child.name.as_str() {
+            "opacity" => on_click.opacity = ctx.int(child, 100),
+            "outline_color" => on_click.outline_color = child.text(),
+            _ => ctx.unknown(child, element),
         }
-        
-        *index += 1;
     }
-    
-    Ok(on_click)
+
+    ctx.require(element, "opacity", &on_click.opacity);
+    ctx.require(element, "outline_color", &on_click.outline_color);
+
+    on_click
 }
 
-fn parse_on_hover(lines: &[&str], index: &mut usize) -> Result<OnHover, String> {
+fn parse_on_hover(ctx: &mut ParseContext, element: &XmlElement) -> OnHover {
     let mut on_hover = OnHover {
         opacity: 100,
         outline_color: "#ffffff".to_string(),
     };
-    
-    *index += 1;
-    while *index < lines.len() {
-        let line = lines[*index].trim();
-        
-        if line.starts_with("</on_hover>") {
-            break;
-        } else if line.starts_with("<opacity>") {
-            on_hover.opacity = extract_text_content(line).parse().unwrap_or(100);
-        } else if line.starts_with("<outline_color>") {
-            on_hover.outline_color = extract_text_content(line);
+
+    for child in element.child_elements() {
+        match child.name.as_str() {
+            "opacity" => on_hover.opacity = ctx.int(child, 100),
+            "outline_color" => on_hover.outline_color = child.text(),
+            _ => ctx.unknown(child, element),
         }
-        
-        *index += 1;
     }
-    
-    Ok(on_hover)
+
+    ctx.require(element, "opacity", &on_hover.opacity);
+    ctx.require(element, "outline_color", &on_hover.outline_color);
+
+    on_hover
 }
 
+/// Writes `doc` as VCP XML with the default formatting, leaving line breaks
+/// as they are.
 pub fn serialize_vcp(doc: &VcpDocument) -> String {
+    let options = SerializeOptions {
+        line_ending: None,
+        ..Default::default()
+    };
+    serialize_vcp_with(doc, &options)
+}
+
+/// Writes `doc` as VCP XML.
+///
+/// Documents loaded from disk keep their original layout: unchanged elements,
+/// comments and unknown elements are written back exactly as they were read,
+/// and only edited, added or removed elements touch the output. `options`
+/// decide how new markup is indented and quoted, whether a declaration is
+/// added, which line endings the whole output uses and whether elements are
+/// sorted first.
+pub fn serialize_vcp_with(doc: &VcpDocument, options: &SerializeOptions) -> String {
+    let sorted;
+    let doc = match options.element_order {
+        ElementOrder::Original => doc,
+        ElementOrder::Sorted => {
+            sorted = sorted_elements(doc);
+            &sorted
+        }
+    };
+
+    let style = Style {
+        indent: &options.indent,
+        unit: &options.indent,
+        quote: options.attribute_quote.as_char(),
+    };
+    let mut xml = match &doc.layout {
+        Some(layout) => serialize_with_layout(doc, layout, style),
+        None => serialize_canonical(doc, style),
+    };
+
+    if options.declaration && !xml.trim_start().starts_with("<?xml") {
+        let declaration = format!(
+            "<?xml version=\"1.0\" encoding=\"{}\"?>\n",
+            doc.format.encoding.xml_name()
+        );
+        xml.insert_str(0, &declaration);
+    }
+
+    match options.line_ending {
+        Some(LineEnding::Crlf) => to_crlf(&xml),
+        Some(LineEnding::Lf) => xml.replace("\r\n", "\n"),
+        None => xml,
+    }
+}
+
+/// Copy of `doc` with borders, images and buttons ordered top to bottom, then
+/// left to right. The sort is stable, so elements sharing a cell keep their
+/// relative order. In loaded skins the sorted elements fill the existing
+/// element positions, leaving comments where they were.
+pub fn sorted_elements(doc: &VcpDocument) -> VcpDocument {
+    let mut doc = doc.clone();
+    doc.borders
+        .sort_by_key(|border| (border.row_start, border.column_start));
+    doc.images
+        .sort_by_key(|image| (image.row_start, image.column_start));
+    doc.buttons
+        .sort_by_key(|button| (button.row, button.column));
+    doc
+}
+
+/// Formatting for markup rendered afresh.
+#[derive(Clone, Copy)]
+struct Style<'a> {
+    /// Indentation of the line the element starts on
+    indent: &'a str,
+    /// One level of indentation
+    unit: &'a str,
+    quote: char,
+}
+
+impl<'a> Style<'a> {
+    fn at(self, indent: &'a str) -> Style<'a> {
+        Style { indent, ..self }
+    }
+
+    fn child(&self) -> String {
+        format!("{}{}", self.indent, self.unit)
+    }
+
+    fn attribute(&self, name: &str, value: &str) -> String {
+        format!(
+            " {}={quote}{}{quote}",
+            name,
+            escape_attribute(value),
+            quote = self.quote
+        )
+    }
+}
+
+fn serialize_canonical(doc: &VcpDocument, style: Style) -> String {
     let mut xml = String::new();
     xml.push_str("<vcp_skin>\n");
-    
+
     // Document root elements in canonical order
-    xml.push_str(&format!("    <background>{}</background>\n", doc.background));
-    xml.push_str(&format!("    <column_count>{}</column_count>\n", doc.column_count));
-    xml.push_str(&format!("    <row_count>{}</row_count>\n", doc.row_count));
-    
-    // Borders
-    for border in &doc.borders {
-        xml.push_str("    <border>\n");
-        xml.push_str(&format!("        <column_span>{}</column_span>\n", border.column_span));
-        xml.push_str(&format!("        <column_start>{}</column_start>\n", border.column_start));
-        xml.push_str(&format!("        <fill>{}</fill>\n", border.fill));
-        xml.push_str(&format!("        <row_span>{}</row_span>\n", border.row_span));
-        xml.push_str(&format!("        <row_start>{}</row_start>\n", border.row_start));
-        xml.push_str(&format!("        <outline_color>{}</outline_color>\n", border.outline_color));
-        xml.push_str(&format!("        <outline_thickness>{}</outline_thickness>\n", border.outline_thickness));
-        
-        if let Some(plc) = &border.plc_word {
-            xml.push_str("        <plc_word>\n");
-            xml.push_str(&format!("            <number>{}</number>\n", plc.number));
-            xml.push_str(&format!("            <color>{}</color>\n", plc.color));
-            xml.push_str(&format!("            <fontsize>{}</fontsize>\n", plc.fontsize));
-            xml.push_str(&format!("            <font>{}</font>\n", plc.font));
-            xml.push_str(&format!("            <fontstyle>{}</fontstyle>\n", plc.fontstyle));
-            xml.push_str(&format!("            <verticalalignment>{}</verticalalignment>\n", plc.verticalalignment));
-            xml.push_str(&format!("            <horizontalalignment>{}</horizontalalignment>\n", plc.horizontalalignment));
-            xml.push_str(&format!("            <marginbottom>{}</marginbottom>\n", plc.marginbottom));
-            xml.push_str(&format!("            <percentage>{}</percentage>\n", plc.percentage));
-            xml.push_str("        </plc_word>\n");
+    for slot in SLOT_ORDER {
+        for index in 0..slot.count(doc) {
+            let markup =
+                render_slot(doc, slot, index, None, style).expect("index is within the slot count");
+            xml.push_str(style.indent);
+            xml.push_str(&markup);
+            xml.push('\n');
         }
-        
-        xml.push_str("    </border>\n");
-    }
-    
-    // Images
-    for image in &doc.images {
-        xml.push_str("    <image>\n");
-        xml.push_str(&format!("        <column_span>{}</column_span>\n", image.column_span));
-        xml.push_str(&format!("        <column_start>{}</column_start>\n", image.column_start));
-        xml.push_str(&format!("        <row_span>{}</row_span>\n", image.row_span));
-        xml.push_str(&format!("        <row_start>{}</row_start>\n", image.row_start));
-        xml.push_str(&format!("        <path>{}</path>\n", image.path));
-        xml.push_str("    </image>\n");
-    }
-    
-    // On click and on hover
-    if let Some(on_click) = &doc.on_click {
-        xml.push_str("    <on_click>\n");
-        xml.push_str(&format!("        <opacity>{}</opacity>\n", on_click.opacity));
-        xml.push_str(&format!("        <outline_color>{}</outline_color>\n", on_click.outline_color));
-        xml.push_str("    </on_click>\n");
-    }
-    
-    if let Some(on_hover) = &doc.on_hover {
-        xml.push_str("    <on_hover>\n");
-        xml.push_str(&format!("        <opacity>{}</opacity>\n", on_hover.opacity));
-        xml.push_str(&format!("        <outline_color>{}</outline_color>\n", on_hover.outline_color));
-        xml.push_str("    </on_hover>\n");
-    }
-    
-    // Buttons
-    for button in &doc.buttons {
-        xml.push_str(&format!("    <button row=\"{}\" column=\"{}\"", button.row, button.column));
-        if let Some(row_span) = button.row_span {
-            xml.push_str(&format!(" row_span=\"{}\"", row_span));
+    }
+
+    xml.push_str("</vcp_skin>\n");
+    xml
+}
+
+fn serialize_with_layout(doc: &VcpDocument, layout: &DocumentLayout, style: Style) -> String {
+    let slots: Vec<Option<Slot>> = layout.nodes.iter().map(Slot::of).collect();
+    let default_prefix =
+        whitespace_before_first_slot(layout).unwrap_or_else(|| format!("\n{}", style.unit));
+
+    // Kinds the file never contained are placed after the last element that
+    // precedes them in canonical order
+    let mut inserts: Vec<(Option<usize>, Slot)> = Vec::new();
+    for slot in SLOT_ORDER {
+        if slots.contains(&Some(slot)) || !slot.has_new_content(doc) {
+            continue;
         }
-        if let Some(col_span) = button.column_span {
-            xml.push_str(&format!(" column_span=\"{}\"", col_span));
+        let after = slots
+            .iter()
+            .rposition(|s| matches!(s, Some(s) if *s < slot));
+        inserts.push((after, slot));
+    }
+
+    let mut groups: Vec<Vec<Vec<usize>>> = vec![Vec::new(); SLOT_ORDER.len()];
+    for slot in SLOT_ORDER.into_iter().filter(|slot| slot.is_list()) {
+        let slot_sources: Vec<&str> = layout
+            .nodes
+            .iter()
+            .filter(|node| Slot::of(node) == Some(slot))
+            .filter_map(LayoutNode::source)
+            .collect();
+        groups[slot as usize] = assign_slots(&slot_sources, &element_sources(doc, slot));
+    }
+
+    let mut xml = layout.head.clone();
+    let mut pending: Option<&str> = None;
+    let mut ordinal = [0usize; SLOT_ORDER.len()];
+
+    for (after, slot) in &inserts {
+        if after.is_none() {
+            write_all_of(&mut xml, doc, *slot, &default_prefix, style);
         }
-        xml.push_str(&format!(">{}</button>\n", button.name));
     }
-    
-    xml.push_str("</vcp_skin>\n");
+
+    for (position, node) in layout.nodes.iter().enumerate() {
+        match (node, slots[position]) {
+            (LayoutNode::Markup { text }, _) => {
+                if let Some(prefix) = pending.take() {
+                    xml.push_str(prefix);
+                }
+                if text.trim().is_empty() {
+                    pending = Some(text);
+                } else {
+                    xml.push_str(text);
+                }
+            }
+            (_, Some(slot)) if slot.is_list() => {
+                // A slot whose element was deleted takes its leading whitespace with it
+                let prefix = pending.take().unwrap_or("");
+                let group = &groups[slot as usize][ordinal[slot as usize]];
+                ordinal[slot as usize] += 1;
+                for &index in group {
+                    if let Some(markup) = render_slot(
+                        doc,
+                        slot,
+                        index,
+                        None,
+                        style.at(indent_of(prefix).unwrap_or(style.unit)),
+                    ) {
+                        xml.push_str(if prefix.is_empty() {
+                            &default_prefix
+                        } else {
+                            prefix
+                        });
+                        xml.push_str(&markup);
+                    }
+                }
+            }
+            (_, Some(slot)) => {
+                let prefix = pending.take().unwrap_or("");
+                if let Some(markup) = render_slot(
+                    doc,
+                    slot,
+                    0,
+                    node.source(),
+                    style.at(indent_of(prefix).unwrap_or(style.unit)),
+                ) {
+                    xml.push_str(prefix);
+                    xml.push_str(&markup);
+                }
+            }
+            (_, None) => {}
+        }
+
+        for (after, slot) in &inserts {
+            if *after == Some(position) {
+                write_all_of(&mut xml, doc, *slot, &default_prefix, style);
+            }
+        }
+    }
+
+    if let Some(prefix) = pending {
+        xml.push_str(prefix);
+    }
+    xml.push_str(&layout.tail);
     xml
 }
 
-pub fn load_file(path: &str) -> Result<VcpDocument, String> {
-    let content = fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-    let mut doc = parse_vcp(&content)?;
-    
-    // Get the VCP root directory
-    // Skin file can be at vcp/skins/skin.vcp or vcp/skin.vcp
-    let skin_path = std::path::Path::new(path);
-    let mut vcp_root = skin_path.parent().ok_or("Failed to get parent directory")?;
-    
-    // If parent is "skins", go up one more level to get vcp root
-    if vcp_root.file_name().and_then(|n| n.to_str()) == Some("skins") {
-        vcp_root = vcp_root.parent().ok_or("Failed to get VCP root directory")?;
+/// Decides which elements of one list kind are written at each of its layout
+/// slots. An element still carrying a slot's original markup keeps that slot,
+/// so deleting an element removes its own slot instead of shifting the others
+/// past comments. Elements added since loading follow the element before them
+/// in document order.
+fn assign_slots(slot_sources: &[&str], element_sources: &[Option<&str>]) -> Vec<Vec<usize>> {
+    let mut groups = vec![Vec::new(); slot_sources.len()];
+    if slot_sources.is_empty() {
+        return groups;
+    }
+
+    let mut claimed = vec![false; element_sources.len()];
+    let mut surviving = Vec::new();
+    for (slot, source) in slot_sources.iter().enumerate() {
+        let found = element_sources
+            .iter()
+            .enumerate()
+            .position(|(index, element)| !claimed[index] && *element == Some(*source));
+        if let Some(index) = found {
+            claimed[index] = true;
+            surviving.push(slot);
+        }
+    }
+
+    let mut next_surviving = surviving.iter();
+    let mut target = surviving.first().copied().unwrap_or(slot_sources.len() - 1);
+    for (index, &is_original) in claimed.iter().enumerate() {
+        if is_original {
+            target = *next_surviving
+                .next()
+                .expect("every claimed element has a surviving slot");
+        }
+        groups[target].push(index);
+    }
+    groups
+}
+
+fn element_sources(doc: &VcpDocument, slot: Slot) -> Vec<Option<&str>> {
+    match slot {
+        Slot::Border => doc.borders.iter().map(|b| b.source.as_deref()).collect(),
+        Slot::Image => doc.images.iter().map(|i| i.source.as_deref()).collect(),
+        Slot::Button => doc.buttons.iter().map(|b| b.source.as_deref()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn whitespace_before_first_slot(layout: &DocumentLayout) -> Option<String> {
+    let first = layout
+        .nodes
+        .iter()
+        .position(|node| Slot::of(node).is_some())?;
+    match layout.nodes[..first].last() {
+        Some(LayoutNode::Markup { text }) if text.trim().is_empty() && !text.is_empty() => {
+            Some(text.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Indentation at the end of `prefix`, if it ends on a fresh line.
+fn indent_of(prefix: &str) -> Option<&str> {
+    prefix.rfind('\n').map(|pos| &prefix[pos + 1..])
+}
+
+fn write_all_of(xml: &mut String, doc: &VcpDocument, slot: Slot, prefix: &str, style: Style) {
+    for index in 0..slot.count(doc) {
+        if let Some(markup) = render_slot(
+            doc,
+            slot,
+            index,
+            None,
+            style.at(indent_of(prefix).unwrap_or(style.unit)),
+        ) {
+            xml.push_str(prefix);
+            xml.push_str(&markup);
+        }
+    }
+}
+
+/// Kinds of root child elements, in the order the editor writes them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Slot {
+    Background,
+    ColumnCount,
+    RowCount,
+    Border,
+    Image,
+    OnClick,
+    OnHover,
+    Button,
+}
+
+const SLOT_ORDER: [Slot; 8] = [
+    Slot::Background,
+    Slot::ColumnCount,
+    Slot::RowCount,
+    Slot::Border,
+    Slot::Image,
+    Slot::OnClick,
+    Slot::OnHover,
+    Slot::Button,
+];
+
+impl Slot {
+    fn of(node: &LayoutNode) -> Option<Slot> {
+        match node {
+            LayoutNode::Markup { .. } => None,
+            LayoutNode::Field { name, .. } => match name.as_str() {
+                "background" => Some(Slot::Background),
+                "column_count" => Some(Slot::ColumnCount),
+                "row_count" => Some(Slot::RowCount),
+                "on_click" => Some(Slot::OnClick),
+                "on_hover" => Some(Slot::OnHover),
+                _ => None,
+            },
+            LayoutNode::Border { .. } => Some(Slot::Border),
+            LayoutNode::Image { .. } => Some(Slot::Image),
+            LayoutNode::Button { .. } => Some(Slot::Button),
+        }
+    }
+
+    fn is_list(self) -> bool {
+        matches!(self, Slot::Border | Slot::Image | Slot::Button)
+    }
+
+    fn count(self, doc: &VcpDocument) -> usize {
+        match self {
+            Slot::Background | Slot::ColumnCount | Slot::RowCount => 1,
+            Slot::OnClick => doc.on_click.iter().count(),
+            Slot::OnHover => doc.on_hover.iter().count(),
+            Slot::Border => doc.borders.len(),
+            Slot::Image => doc.images.len(),
+            Slot::Button => doc.buttons.len(),
+        }
+    }
+
+    /// Whether a file without this element needs it written. Values still at
+    /// the parser's defaults are left out so untouched files stay identical.
+    fn has_new_content(self, doc: &VcpDocument) -> bool {
+        let defaults = VcpDocument::default();
+        let style = Style {
+            indent: "",
+            unit: " ",
+            quote: '"',
+        };
+        match self {
+            Slot::Background => doc.background != defaults.background,
+            Slot::ColumnCount => doc.column_count != defaults.column_count,
+            Slot::RowCount => doc.row_count != defaults.row_count,
+            Slot::OnClick => {
+                let render = |v: &Option<OnClick>| v.as_ref().map(|v| render_on_click(v, style));
+                render(&doc.on_click) != render(&defaults.on_click)
+            }
+            Slot::OnHover => {
+                let render = |v: &Option<OnHover>| v.as_ref().map(|v| render_on_hover(v, style));
+                render(&doc.on_hover) != render(&defaults.on_hover)
+            }
+            _ => self.count(doc) > 0,
+        }
+    }
+}
+
+/// Renders the `index`th element of `slot`, reusing its original markup when
+/// the value has not changed since it was read.
+fn render_slot(
+    doc: &VcpDocument,
+    slot: Slot,
+    index: usize,
+    field_source: Option<&str>,
+    style: Style,
+) -> Option<String> {
+    let markup = match slot {
+        Slot::Background => reuse_or_render(
+            field_source,
+            &doc.background,
+            |v, _| format!("<background>{}</background>", escape_text(v)),
+            |_, element| element.text(),
+            &[],
+            style,
+        ),
+        Slot::ColumnCount => reuse_or_render(
+            field_source,
+            &doc.column_count,
+            |v, _| format!("<column_count>{}</column_count>", v),
+            |ctx, element| ctx.int(element, 6),
+            &[],
+            style,
+        ),
+        Slot::RowCount => reuse_or_render(
+            field_source,
+            &doc.row_count,
+            |v, _| format!("<row_count>{}</row_count>", v),
+            |ctx, element| ctx.int(element, 14),
+            &[],
+            style,
+        ),
+        Slot::OnClick => reuse_or_render(
+            field_source,
+            doc.on_click.as_ref()?,
+            render_on_click,
+            parse_on_click,
+            POINTER_STYLE_FIELDS,
+            style,
+        ),
+        Slot::OnHover => reuse_or_render(
+            field_source,
+            doc.on_hover.as_ref()?,
+            render_on_hover,
+            parse_on_hover,
+            POINTER_STYLE_FIELDS,
+            style,
+        ),
+        Slot::Border => {
+            let border = doc.borders.get(index)?;
+            reuse_or_render(
+                border.source.as_deref(),
+                border,
+                render_border,
+                parse_border,
+                BORDER_FIELDS,
+                style,
+            )
+        }
+        Slot::Image => {
+            let image = doc.images.get(index)?;
+            reuse_or_render(
+                image.source.as_deref(),
+                image,
+                render_image,
+                parse_image,
+                IMAGE_FIELDS,
+                style,
+            )
+        }
+        Slot::Button => {
+            let button = doc.buttons.get(index)?;
+            reuse_or_render(
+                button.source.as_deref(),
+                button,
+                render_button,
+                parse_button,
+                &[],
+                style,
+            )
+        }
+    };
+    Some(markup)
+}
+
+const BORDER_FIELDS: &[&str] = &[
+    "row_start",
+    "column_start",
+    "row_span",
+    "column_span",
+    "fill",
+    "outline_color",
+    "outline_thickness",
+    "plc_word",
+];
+const IMAGE_FIELDS: &[&str] = &[
+    "row_start",
+    "column_start",
+    "row_span",
+    "column_span",
+    "path",
+];
+const POINTER_STYLE_FIELDS: &[&str] = &["opacity", "outline_color"];
+const BUTTON_ATTRIBUTES: &[&str] = &["row", "column", "row_span", "column_span"];
+
+/// Returns `source` if it still describes `value`; otherwise renders `value`
+/// afresh, carrying over any comments and unknown children from `source`.
+fn reuse_or_render<T>(
+    source: Option<&str>,
+    value: &T,
+    render: fn(&T, Style) -> String,
+    reparse: fn(&mut ParseContext, &XmlElement) -> T,
+    known_children: &[&str],
+    style: Style,
+) -> String {
+    let fresh = render(value, style);
+    let Some(source) = source else {
+        return fresh;
+    };
+    let Ok(tree) = parse_document(source) else {
+        return fresh;
+    };
+    let Some(element) = tree.root() else {
+        return fresh;
+    };
+
+    let mut ctx = ParseContext::new(source);
+    if render(&reparse(&mut ctx, element), style) == fresh {
+        return source.to_string();
+    }
+    carry_over_extras(fresh, source, element, known_children, style)
+}
+
+fn carry_over_extras(
+    mut fresh: String,
+    source: &str,
+    element: &XmlElement,
+    known_children: &[&str],
+    style: Style,
+) -> String {
+    if element.name == "button" {
+        let extras: String = element
+            .attributes
+            .iter()
+            .filter(|attr| !BUTTON_ATTRIBUTES.contains(&attr.name.as_str()))
+            .map(|attr| style.attribute(&attr.name, &attr.value))
+            .collect();
+        if let Some(end) = fresh.find('>') {
+            fresh.insert_str(end, &extras);
+        }
+        return fresh;
+    }
+
+    let mut extras = String::new();
+    for node in &element.children {
+        let keep = match &node.kind {
+            XmlNodeKind::Comment(_) => true,
+            XmlNodeKind::Element(child) => !known_children.contains(&child.name.as_str()),
+            _ => false,
+        };
+        if keep {
+            extras.push('\n');
+            extras.push_str(&style.child());
+            extras.push_str(&source[node.span.clone()]);
+        }
+    }
+    if let Some(close) = fresh.rfind('\n') {
+        fresh.insert_str(close, &extras);
+    }
+    fresh
+}
+
+fn render_border(border: &Border, style: Style) -> String {
+    let child = style.child();
+    let mut xml = String::from("<border>\n");
+    xml.push_str(&format!(
+        "{}<column_span>{}</column_span>\n",
+        child, border.column_span
+    ));
+    xml.push_str(&format!(
+        "{}<column_start>{}</column_start>\n",
+        child, border.column_start
+    ));
+    xml.push_str(&format!(
+        "{}<fill>{}</fill>\n",
+        child,
+        escape_text(&border.fill)
+    ));
+    xml.push_str(&format!(
+        "{}<row_span>{}</row_span>\n",
+        child, border.row_span
+    ));
+    xml.push_str(&format!(
+        "{}<row_start>{}</row_start>\n",
+        child, border.row_start
+    ));
+    xml.push_str(&format!(
+        "{}<outline_color>{}</outline_color>\n",
+        child,
+        escape_text(&border.outline_color)
+    ));
+    xml.push_str(&format!(
+        "{}<outline_thickness>{}</outline_thickness>\n",
+        child, border.outline_thickness
+    ));
+
+    if let Some(plc) = &border.plc_word {
+        let field = format!("{}{}", child, style.unit);
+        xml.push_str(&format!("{}<plc_word>\n", child));
+        xml.push_str(&format!("{}<number>{}</number>\n", field, plc.number));
+        xml.push_str(&format!(
+            "{}<color>{}</color>\n",
+            field,
+            escape_text(&plc.color)
+        ));
+        xml.push_str(&format!("{}<fontsize>{}</fontsize>\n", field, plc.fontsize));
+        xml.push_str(&format!(
+            "{}<font>{}</font>\n",
+            field,
+            escape_text(&plc.font)
+        ));
+        xml.push_str(&format!(
+            "{}<fontstyle>{}</fontstyle>\n",
+            field,
+            escape_text(&plc.fontstyle)
+        ));
+        xml.push_str(&format!(
+            "{}<verticalalignment>{}</verticalalignment>\n",
+            field,
+            escape_text(&plc.verticalalignment)
+        ));
+        xml.push_str(&format!(
+            "{}<horizontalalignment>{}</horizontalalignment>\n",
+            field,
+            escape_text(&plc.horizontalalignment)
+        ));
+        xml.push_str(&format!(
+            "{}<marginbottom>{}</marginbottom>\n",
+            field, plc.marginbottom
+        ));
+        xml.push_str(&format!(
+            "{}<percentage>{}</percentage>\n",
+            field, plc.percentage
+        ));
+        xml.push_str(&format!("{}</plc_word>\n", child));
+    }
+
+    xml.push_str(&format!("{}</border>", style.indent));
+    xml
+}
+
+fn render_image(image: &Image, style: Style) -> String {
+    let child = style.child();
+    let mut xml = String::from("<image>\n");
+    xml.push_str(&format!(
+        "{}<column_span>{}</column_span>\n",
+        child, image.column_span
+    ));
+    xml.push_str(&format!(
+        "{}<column_start>{}</column_start>\n",
+        child, image.column_start
+    ));
+    xml.push_str(&format!(
+        "{}<row_span>{}</row_span>\n",
+        child, image.row_span
+    ));
+    xml.push_str(&format!(
+        "{}<row_start>{}</row_start>\n",
+        child, image.row_start
+    ));
+    xml.push_str(&format!(
+        "{}<path>{}</path>\n",
+        child,
+        escape_text(&image.path)
+    ));
+    xml.push_str(&format!("{}</image>", style.indent));
+    xml
+}
+
+fn render_on_click(on_click: &OnClick, style: Style) -> String {
+    render_pointer_style("on_click", on_click.opacity, &on_click.outline_color, style)
+}
+
+fn render_on_hover(on_hover: &OnHover, style: Style) -> String {
+    render_pointer_style("on_hover", on_hover.opacity, &on_hover.outline_color, style)
+}
+
+fn render_pointer_style(name: &str, opacity: i32, outline_color: &str, style: Style) -> String {
+    let child = style.child();
+    let mut xml = format!("<{}>\n", name);
+    xml.push_str(&format!("{}<opacity>{}</opacity>\n", child, opacity));
+    xml.push_str(&format!(
+        "{}<outline_color>{}</outline_color>\n",
+        child,
+        escape_text(outline_color)
+    ));
+    xml.push_str(&format!("{}</{}>", style.indent, name));
+    xml
+}
+
+fn render_button(button: &Button, style: Style) -> String {
+    let mut xml = String::from("<button");
+    xml.push_str(&style.attribute("row", &button.row.to_string()));
+    xml.push_str(&style.attribute("column", &button.column.to_string()));
+    if let Some(row_span) = button.row_span {
+        xml.push_str(&style.attribute("row_span", &row_span.to_string()));
+    }
+    if let Some(col_span) = button.column_span {
+        xml.push_str(&style.attribute("column_span", &col_span.to_string()));
     }
-    
-    println!("VCP root directory: {:?}", vcp_root);
-    
+    xml.push_str(&format!(">{}</button>", escape_text(&button.name)));
+    xml
+}
+
+pub fn load_file(path: &str) -> Result<LoadedDocument, String> {
+    let (content, format) = read_text_file(Path::new(path))?;
+    let mut loaded = parse_vcp_with_diagnostics(&content)?;
+    let doc = &mut loaded.document;
+    doc.format = format;
+
+    let vcp_root = vcp_root_of(Path::new(path))?;
+
     // Check each button for an accompanying XML file
     for button in &mut doc.buttons {
         if !button.name.is_empty() {
-            let xml_path = vcp_root.join("Buttons").join(&button.name).join(format!("{}.xml", button.name));
-            println!("Checking for XML at: {:?}", xml_path);
+            let xml_path = match button_xml_path(vcp_root, &button.name) {
+                Ok(path) => path,
+                Err(e) => {
+                    loaded.button_errors.push(ButtonLoadError {
+                        button: button.name.clone(),
+                        message: e,
+                    });
+                    continue;
+                }
+            };
             if xml_path.exists() {
-                println!("Found XML file for button: {}", button.name);
                 button.file = Some(format!("{}.xml", button.name));
-                
-                // Parse the XML to get defaultImage
-                if let Ok(xml_content) = fs::read_to_string(&xml_path) {
-                    // Simple XML parsing to extract default_image value
-                    // Look for <default_image>filename.svg</default_image>
-                    if let Some(start_pos) = xml_content.find("<default_image>") {
-                        let content_start = start_pos + 15; // Length of "<default_image>"
-                        if let Some(end_pos) = xml_content[content_start..].find("</default_image>") {
-                            let default_image = xml_content[content_start..content_start + end_pos].trim();
-                            if !default_image.is_empty() {
-                                button.default_image = Some(default_image.to_string());
-                                println!("Found default_image: {}", default_image);
-                            }
-                        }
+
+                match load_button(vcp_root, &button.name) {
+                    Ok(definition) => {
+                        button.default_image = definition.default_image.clone();
+                        button.definition = Some(definition);
                     }
+                    Err(e) => loaded.button_errors.push(ButtonLoadError {
+                        button: button.name.clone(),
+                        message: e,
+                    }),
                 }
-            } else {
-                println!("No XML file found for button: {}", button.name);
             }
         }
     }
-    
-    Ok(doc)
+
+    Ok(loaded)
+}
+
+/// Writes `doc` to `path`. A document read from disk keeps that file's
+/// encoding, line endings and header; `options` shape everything else.
+pub fn save_file(path: &str, doc: &VcpDocument, options: &SerializeOptions) -> Result<(), String> {
+    let mut options = options.clone();
+    if doc.layout.is_some() {
+        options.line_ending = None;
+        options.declaration = false;
+    }
+    fs::write(path, render_file(doc, &options)).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// The bytes of `doc` written with `options`, in the document's encoding.
+pub fn render_file(doc: &VcpDocument, options: &SerializeOptions) -> Vec<u8> {
+    let mut format = doc.format;
+    if let Some(line_ending) = options.line_ending {
+        format.line_ending = line_ending;
+    }
+    encode_text(&serialize_vcp_with(doc, options), &format)
 }
 
-pub fn save_file(path: &str, doc: &VcpDocument) -> Result<(), String> {
-    let xml = serialize_vcp(doc);
-    fs::write(path, xml)
-        .map_err(|e| format!("Failed to write file: {}", e))
+/// The VCP root folder a skin belongs to. Skins live either at
+/// `vcp/skins/skin.vcp` or directly at `vcp/skin.vcp`.
+pub fn vcp_root_of(skin_path: &Path) -> Result<&Path, String> {
+    let mut vcp_root = skin_path.parent().ok_or("Failed to get parent directory")?;
+
+    // If parent is "skins", go up one more level to get vcp root
+    if vcp_root.file_name().and_then(|n| n.to_str()) == Some("skins") {
+        vcp_root = vcp_root
+            .parent()
+            .ok_or("Failed to get VCP root directory")?;
+    }
+    Ok(vcp_root)
 }
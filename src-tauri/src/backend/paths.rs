@@ -0,0 +1,33 @@
+use std::fs;
+use std::path::Path;
+
+/// Skin and button names become file and folder names, so they must not
+/// reach outside their folder.
+pub fn check_file_name(name: &str, what: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', ':']) {
+        return Err(format!("Invalid {} name '{}'", what, name));
+    }
+    Ok(())
+}
+
+/// Sorted names of the files (or folders) in `folder`, skipping hidden ones.
+pub fn folder_entries(folder: &Path, folders: bool) -> Result<Vec<String>, String> {
+    if !folder.exists() {
+        return Ok(Vec::new());
+    }
+    let entries =
+        fs::read_dir(folder).map_err(|e| format!("Failed to read {}: {}", folder.display(), e))?;
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir() == folders)
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Sorted names of the button folders in `Buttons/` below `vcp_root`.
+pub fn button_folders(vcp_root: &Path) -> Result<Vec<String>, String> {
+    folder_entries(&vcp_root.join("Buttons"), true)
+}
@@ -0,0 +1,203 @@
+use super::encoding::read_text_file;
+use super::events::RESERVED_SKIN_EVENTS;
+use super::models::{
+    ButtonBinding, ParseDiagnostic, PlcCrossCheck, PlcDefinitions, PlcSymbol, PlcSymbolKind,
+    Severity, SkinEventDefinition, VcpDocument,
+};
+use std::collections::HashSet;
+use std::path::Path;
+
+const SKIN_EVENT_PREFIX: &str = "SV_SKIN_EVENT_";
+
+/// Reads a PLC program (`.src`) or `SkinEventsList.txt`.
+pub fn load_plc_source(path: &Path) -> Result<PlcDefinitions, String> {
+    let (content, _) = read_text_file(path)?;
+    Ok(parse_plc_source(&content))
+}
+
+/// Extracts skin event and `OUT`/`INP`/`MEM` definitions from PLC source.
+///
+/// Both files use `Name IS Target ; comment` lines, aligned with any mix of
+/// spaces and tabs. `IS` and the `SV_SKIN_EVENT_` prefix are matched without
+/// regard to case, since shipped programs contain spellings like
+/// `SV_Skin_EVENT_85`; those are reported as info diagnostics. Lines
+/// starting with `;` are still read, marked as commented out. Everything
+/// else in the program is ignored.
+pub fn parse_plc_source(content: &str) -> PlcDefinitions {
+    let mut definitions = PlcDefinitions::default();
+
+    for (index, raw_line) in content.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = raw_line.trim_start();
+        let commented = trimmed.starts_with(';');
+        let text = trimmed.trim_start_matches(';');
+        let (code, comment) = match text.split_once(';') {
+            Some((code, comment)) => (code, Some(comment.trim())),
+            None => (text, None),
+        };
+
+        let mut tokens = code.split_whitespace();
+        let (Some(name), Some(is), Some(target), None) =
+            (tokens.next(), tokens.next(), tokens.next(), tokens.next())
+        else {
+            continue;
+        };
+        if !is.eq_ignore_ascii_case("IS") {
+            continue;
+        }
+        // Found past the name and `IS`, which may contain the target's text
+        let rest = code.trim_start()[name.len()..].trim_start()[is.len()..].trim_start();
+        let target_start = raw_line.len() - text.len() + code.len() - rest.len();
+        let target_column = raw_line[..target_start].chars().count() + 1;
+        let mut report = |severity, message| {
+            definitions.diagnostics.push(ParseDiagnostic {
+                line,
+                column: target_column,
+                severity,
+                message,
+            })
+        };
+
+        if let Some(number) = strip_prefix_ignore_case(target, SKIN_EVENT_PREFIX) {
+            let Ok(number) = number.parse() else {
+                if !commented {
+                    report(
+                        Severity::Warning,
+                        format!("Invalid skin event number in '{}'", target),
+                    );
+                }
+                continue;
+            };
+            if !target.starts_with(SKIN_EVENT_PREFIX) {
+                report(
+                    Severity::Info,
+                    format!(
+                        "'{}' should be spelled {}{}",
+                        target, SKIN_EVENT_PREFIX, number
+                    ),
+                );
+            }
+            let (row, column) = comment.map(row_column_hint).unwrap_or_default();
+            definitions.skin_events.push(SkinEventDefinition {
+                name: name.to_string(),
+                number,
+                row,
+                column,
+                commented,
+                line,
+            });
+        } else if let Some((kind, number)) = symbol_target(target) {
+            definitions.symbols.push(PlcSymbol {
+                name: name.to_string(),
+                kind,
+                number,
+                comment: comment.filter(|c| !c.is_empty()).map(str::to_string),
+                commented,
+                line,
+            });
+        }
+    }
+
+    definitions
+}
+
+/// Compares the buttons of `doc`, with their definitions resolved, against
+/// what a PLC program defines, in both directions. Commented-out definitions
+/// count as missing, and events the PLC raises itself are never expected to
+/// have a button.
+pub fn cross_check(doc: &VcpDocument, definitions: &PlcDefinitions) -> PlcCrossCheck {
+    let events: HashSet<i32> = definitions
+        .skin_events
+        .iter()
+        .filter(|event| !event.commented)
+        .map(|event| event.number)
+        .collect();
+    let defined = |kind: PlcSymbolKind| -> HashSet<i32> {
+        definitions
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.kind == kind && !symbol.commented)
+            .map(|symbol| symbol.number)
+            .collect()
+    };
+    let outputs = defined(PlcSymbolKind::Out);
+    let inputs = defined(PlcSymbolKind::Inp);
+
+    let mut report = PlcCrossCheck::default();
+    let mut seen = HashSet::new();
+    let mut triggered = HashSet::new();
+    for button in &doc.buttons {
+        if !seen.insert(button.name.as_str()) {
+            continue;
+        }
+        let Some(definition) = &button.definition else {
+            report.unresolved_buttons.push(button.name.clone());
+            continue;
+        };
+        let binding = |number| ButtonBinding {
+            button: button.name.clone(),
+            number,
+        };
+
+        if let Some(event) = definition.skin_event_num {
+            triggered.insert(event);
+            if !events.contains(&event) {
+                report.undefined_events.push(binding(event));
+            }
+        }
+        if let Some(output) = &definition.plc_output {
+            if !outputs.contains(&output.number) {
+                report.undefined_outputs.push(binding(output.number));
+            }
+        }
+        if let Some(input) = &definition.plc_input {
+            if !inputs.contains(&input.number) {
+                report.undeclared_inputs.push(binding(input.number));
+            }
+        }
+    }
+
+    report.untriggered_events = definitions
+        .skin_events
+        .iter()
+        .filter(|event| !event.commented && !triggered.contains(&event.number))
+        .filter(|event| !RESERVED_SKIN_EVENTS.contains(&event.name.as_str()))
+        .cloned()
+        .collect();
+    report
+}
+
+/// `OUT12`, `INP1057` or `MEM795`, in any case.
+fn symbol_target(target: &str) -> Option<(PlcSymbolKind, i32)> {
+    [
+        ("OUT", PlcSymbolKind::Out),
+        ("INP", PlcSymbolKind::Inp),
+        ("MEM", PlcSymbolKind::Mem),
+    ]
+    .into_iter()
+    .find_map(|(prefix, kind)| {
+        let number = strip_prefix_ignore_case(target, prefix)?.parse().ok()?;
+        Some((kind, number))
+    })
+}
+
+/// Reads `Row N Column M` from a definition's comment.
+fn row_column_hint(comment: &str) -> (Option<i32>, Option<i32>) {
+    let mut row = None;
+    let mut column = None;
+    let mut words = comment.split_whitespace();
+    while let Some(word) = words.next() {
+        if word.eq_ignore_ascii_case("row") {
+            row = words.next().and_then(|n| n.parse().ok());
+        } else if word.eq_ignore_ascii_case("column") {
+            column = words.next().and_then(|n| n.parse().ok());
+        }
+    }
+    (row, column)
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = text.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &text[prefix.len()..])
+}
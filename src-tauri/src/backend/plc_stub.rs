@@ -0,0 +1,154 @@
+use super::models::{SymbolCatalog, VcpButton, VcpDocument};
+use std::collections::{BTreeMap, HashSet};
+
+/// Column the `IS` of generated definitions lines up on, as in the stock
+/// `SkinEventsList.txt`.
+const DEFINITION_WIDTH: usize = 30;
+
+/// Writes a PLC source fragment for the skin events of `doc`'s buttons that
+/// `known` does not define yet.
+///
+/// The fragment declares each new event as `Skin<Name>_M`, declares LED
+/// outputs that are missing too, and adds a toggle block per button: an
+/// output flips every time its skin event fires, and buttons without an
+/// output get a commented placeholder for their action. Skin events are
+/// one-scan pulses, so no one-shot is needed. Returns an empty string when
+/// every event is already defined.
+///
+/// Names clashing with one in `known` or generated earlier get the event
+/// number added, and buttons without letters or digits in their name become
+/// `Button<event>`, so the fragment always compiles. An output several new
+/// buttons share is defined once.
+pub fn generate_plc_stub(doc: &VcpDocument, known: &SymbolCatalog) -> String {
+    let mut buttons: BTreeMap<i32, (&str, &VcpButton)> = BTreeMap::new();
+    for button in &doc.buttons {
+        let Some(definition) = &button.definition else {
+            continue;
+        };
+        let Some(event) = definition.skin_event_num else {
+            continue;
+        };
+        if known.skin_events.iter().all(|entry| entry.number != event) {
+            buttons.entry(event).or_insert((&button.name, definition));
+        }
+    }
+    if buttons.is_empty() {
+        return String::new();
+    }
+
+    // PLC symbols are not case-sensitive
+    let mut used: HashSet<String> = [&known.skin_events, &known.outputs, &known.inputs]
+        .into_iter()
+        .flatten()
+        .map(|entry| entry.name.to_ascii_uppercase())
+        .collect();
+    let mut new_outputs: BTreeMap<i32, String> = BTreeMap::new();
+    let mut events = Vec::new();
+    let mut outputs = Vec::new();
+    let mut logic = Vec::new();
+    for (&event, &(name, definition)) in &buttons {
+        let base = symbol_base(name);
+        let event_symbol = unique_symbol(&mut used, &base, event, |stem| format!("Skin{}_M", stem));
+        events.push(definition_line(
+            &event_symbol,
+            &format!("SV_SKIN_EVENT_{}", event),
+            name,
+        ));
+
+        logic.push(format!(";--- {} (skin event {})", name, event));
+        match &definition.plc_output {
+            Some(output) => {
+                let existing = known
+                    .outputs
+                    .iter()
+                    .find(|o| o.number == output.number)
+                    .map(|o| &o.name)
+                    .or_else(|| new_outputs.get(&output.number));
+                let output_symbol = match existing {
+                    Some(existing) => existing.clone(),
+                    None => {
+                        let symbol =
+                            unique_symbol(&mut used, &base, event, |stem| format!("{}LED", stem));
+                        outputs.push(definition_line(
+                            &symbol,
+                            &format!("OUT{}", output.number),
+                            name,
+                        ));
+                        new_outputs.insert(output.number, symbol.clone());
+                        symbol
+                    }
+                };
+                logic.push(format!(
+                    "IF ({0} && !{1}) || (!{0} && {1}) THEN ({1})",
+                    event_symbol, output_symbol
+                ));
+            }
+            None => logic.push(format!(";IF {} THEN (...)", event_symbol)),
+        }
+        logic.push(String::new());
+    }
+
+    let mut fragment = String::from(";--- Skin events added by VCP Editor\n");
+    fragment.push_str(&events.join("\n"));
+    fragment.push('\n');
+    if !outputs.is_empty() {
+        fragment.push_str("\n;--- Button LED outputs\n");
+        fragment.push_str(&outputs.join("\n"));
+        fragment.push('\n');
+    }
+    fragment.push_str("\n;--- Toggle logic, to be placed in the program body\n");
+    fragment.push_str(logic.join("\n").trim_end());
+    fragment.push('\n');
+    fragment
+}
+
+fn definition_line(symbol: &str, target: &str, button: &str) -> String {
+    format!(
+        "{:<width$} IS {:<19}; Button {}",
+        symbol,
+        target,
+        button,
+        width = DEFINITION_WIDTH - 1
+    )
+}
+
+/// `format` applied to `base`, or when the symbol is taken, to `base` and
+/// the event number, then `<event>_2` and so on. Symbols must not start with
+/// a digit, so an empty `base` becomes `Button<event>` and one starting with
+/// a digit gets `Button` in front. The symbol returned is added to `used`.
+fn unique_symbol(
+    used: &mut HashSet<String>,
+    base: &str,
+    event: i32,
+    format: impl Fn(&str) -> String,
+) -> String {
+    let (base, mut attempt) = match base.chars().next() {
+        None => ("Button".to_string(), 1),
+        Some(c) if c.is_ascii_digit() => (format!("Button{}", base), 0),
+        Some(_) => (base.to_string(), 0),
+    };
+    loop {
+        let stem = match attempt {
+            0 => base.clone(),
+            1 => format!("{}{}", base, event),
+            _ => format!("{}{}_{}", base, event, attempt),
+        };
+        let symbol = format(&stem);
+        if used.insert(symbol.to_ascii_uppercase()) {
+            return symbol;
+        }
+        attempt += 1;
+    }
+}
+
+/// `vacuum_pump` becomes `VacuumPump`.
+fn symbol_base(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().map(|c| c.to_ascii_uppercase());
+            first.into_iter().chain(chars).collect::<String>()
+        })
+        .collect()
+}
@@ -0,0 +1,164 @@
+use super::button::{button_xml_path, load_button, save_button};
+use super::encoding::read_text_file;
+use super::models::{DataFormat, ProjectSkin, VcpDocument, VcpProject};
+use super::parser::{parse_vcp, save_file};
+use super::paths::{button_folders, check_file_name, folder_entries};
+use super::settings::load_project_settings;
+use std::fs;
+use std::path::Path;
+
+/// Version written into new project files. Files from a newer editor are
+/// refused rather than half-imported.
+pub const PROJECT_FORMAT_VERSION: u32 = 1;
+
+impl DataFormat {
+    /// `.json`, or `.yaml`/`.yml`, in any case.
+    pub fn from_path(path: &Path) -> Result<DataFormat, String> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Ok(DataFormat::Json),
+            "yaml" | "yml" => Ok(DataFormat::Yaml),
+            _ => Err(format!(
+                "Unknown project file type '{}', expected .json, .yaml or .yml",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Reads every skin in `skins/` and every button definition in `Buttons/`.
+///
+/// With `keep_layout`, each skin carries the formatting and comments of its
+/// file, so writing the project back reproduces the skins byte for byte.
+/// Without it the data is easier to read and edit, and skins are written in
+/// the project's output style. Button definitions are always kept whole.
+pub fn read_project(vcp_root: &Path, keep_layout: bool) -> Result<VcpProject, String> {
+    let mut skins = Vec::new();
+    for file in folder_entries(&vcp_root.join("skins"), false)? {
+        if !file.to_ascii_lowercase().ends_with(".vcp") {
+            continue;
+        }
+        let path = vcp_root.join("skins").join(&file);
+        let (content, format) = read_text_file(&path)?;
+        let mut document =
+            parse_vcp(&content).map_err(|e| format!("Failed to parse skins/{}: {}", file, e))?;
+        document.format = format;
+        if !keep_layout {
+            discard_layout(&mut document);
+        }
+        skins.push(ProjectSkin { file, document });
+    }
+
+    let mut buttons = Vec::new();
+    for name in button_folders(vcp_root)? {
+        if button_xml_path(vcp_root, &name).is_ok_and(|path| path.is_file()) {
+            buttons.push(load_button(vcp_root, &name)?);
+        }
+    }
+
+    Ok(VcpProject {
+        version: PROJECT_FORMAT_VERSION,
+        skins,
+        buttons,
+    })
+}
+
+/// Writes each skin to `skins/` and each button to `Buttons/<name>/`,
+/// creating folders as needed, and returns the files written relative to
+/// `vcp_root`. Files the project does not mention are left alone, and images
+/// are never touched.
+pub fn write_project(vcp_root: &Path, project: &VcpProject) -> Result<Vec<String>, String> {
+    check_version(project)?;
+    for skin in &project.skins {
+        check_file_name(&skin.file, "skin")?;
+        if !skin.file.to_ascii_lowercase().ends_with(".vcp") {
+            return Err(format!("Skin file '{}' must end in .vcp", skin.file));
+        }
+    }
+    for button in &project.buttons {
+        check_file_name(&button.name, "button")?;
+    }
+
+    let settings = load_project_settings(vcp_root)?;
+    let mut written = Vec::new();
+
+    let skins_dir = vcp_root.join("skins");
+    fs::create_dir_all(&skins_dir).map_err(|e| format!("Failed to create skins folder: {}", e))?;
+    for skin in &project.skins {
+        let path = skins_dir.join(&skin.file);
+        save_file(&path.to_string_lossy(), &skin.document, &settings.output)?;
+        written.push(format!("skins/{}", skin.file));
+    }
+
+    for button in &project.buttons {
+        let folder = vcp_root.join("Buttons").join(&button.name);
+        fs::create_dir_all(&folder)
+            .map_err(|e| format!("Failed to create button folder: {}", e))?;
+        save_button(&folder, button)?;
+        written.push(format!("Buttons/{0}/{0}.xml", button.name));
+    }
+
+    Ok(written)
+}
+
+pub fn to_data(project: &VcpProject, format: DataFormat) -> Result<String, String> {
+    match format {
+        DataFormat::Json => serde_json::to_string_pretty(project)
+            .map_err(|e| format!("Failed to serialize project: {}", e)),
+        DataFormat::Yaml => serde_yaml::to_string(project)
+            .map_err(|e| format!("Failed to serialize project: {}", e)),
+    }
+}
+
+pub fn from_data(content: &str, format: DataFormat) -> Result<VcpProject, String> {
+    let project: VcpProject = match format {
+        DataFormat::Json => serde_json::from_str(content)
+            .map_err(|e| format!("Failed to parse project file: {}", e))?,
+        DataFormat::Yaml => serde_yaml::from_str(content)
+            .map_err(|e| format!("Failed to parse project file: {}", e))?,
+    };
+    check_version(&project)?;
+    Ok(project)
+}
+
+/// Writes `project` to `path` as JSON or YAML, going by its extension.
+pub fn save_project_file(path: &Path, project: &VcpProject) -> Result<(), String> {
+    let content = to_data(project, DataFormat::from_path(path)?)?;
+    fs::write(path, content).map_err(|e| format!("Failed to write project file: {}", e))
+}
+
+pub fn load_project_file(path: &Path) -> Result<VcpProject, String> {
+    let format = DataFormat::from_path(path)?;
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read project file: {}", e))?;
+    from_data(&content, format)
+}
+
+/// Forgets how a skin's file was laid out, so every element is rendered
+/// afresh when it is written.
+pub fn discard_layout(doc: &mut VcpDocument) {
+    doc.layout = None;
+    for border in &mut doc.borders {
+        border.source = None;
+    }
+    for image in &mut doc.images {
+        image.source = None;
+    }
+    for button in &mut doc.buttons {
+        button.source = None;
+    }
+}
+
+fn check_version(project: &VcpProject) -> Result<(), String> {
+    if project.version == 0 || project.version > PROJECT_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported project format version {}, this editor reads version {}",
+            project.version, PROJECT_FORMAT_VERSION
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use super::models::ProjectSettings;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings file in the VCP root.
+pub const PROJECT_SETTINGS_FILE: &str = ".vcp-editor.json";
+
+pub fn project_settings_path(vcp_root: &Path) -> PathBuf {
+    vcp_root.join(PROJECT_SETTINGS_FILE)
+}
+
+/// Reads the project's settings, falling back to the defaults when the
+/// project has none yet.
+pub fn load_project_settings(vcp_root: &Path) -> Result<ProjectSettings, String> {
+    let path = project_settings_path(vcp_root);
+    if !path.exists() {
+        return Ok(ProjectSettings::default());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read project settings: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse project settings: {}", e))
+}
+
+pub fn save_project_settings(vcp_root: &Path, settings: &ProjectSettings) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize project settings: {}", e))?;
+    fs::write(project_settings_path(vcp_root), content)
+        .map_err(|e| format!("Failed to write project settings: {}", e))
+}
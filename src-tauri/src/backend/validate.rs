@@ -0,0 +1,220 @@
+use super::models::{ElementKind, ElementRef, Issue, Severity, VcpDocument};
+
+/// Cells covered by an element, 1-based and inclusive. Kept in `i64` so
+/// positions and spans read from any skin add up without overflowing.
+#[derive(Clone, Copy)]
+struct Area {
+    row: i64,
+    column: i64,
+    last_row: i64,
+    last_column: i64,
+}
+
+impl Area {
+    fn new(row: i32, column: i32, row_span: i32, column_span: i32) -> Area {
+        let (row, column) = (i64::from(row), i64::from(column));
+        Area {
+            row,
+            column,
+            last_row: row + i64::from(row_span) - 1,
+            last_column: column + i64::from(column_span) - 1,
+        }
+    }
+
+    /// Top-left cell shared with `other`, if the two overlap.
+    fn overlap(self, other: Area) -> Option<(i64, i64)> {
+        let row = self.row.max(other.row);
+        let column = self.column.max(other.column);
+        (row <= self.last_row.min(other.last_row)
+            && column <= self.last_column.min(other.last_column))
+        .then_some((row, column))
+    }
+}
+
+/// A placed element as the rules see it.
+struct Placed {
+    element: ElementRef,
+    label: String,
+    row_span: i32,
+    column_span: i32,
+    area: Area,
+}
+
+/// Checks a document against the grid rules CNC12 relies on.
+///
+/// Every element must have positive spans and fit inside `row_count` by
+/// `column_count`, buttons must not share cells with each other, and images
+/// should not cover buttons or other images. Overlaps are reported on the
+/// later element. An empty list means the skin is valid.
+pub fn validate_document(doc: &VcpDocument) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    if doc.column_count < 1 || doc.row_count < 1 {
+        issues.push(Issue {
+            rule: "grid-size".to_string(),
+            severity: Severity::Error,
+            element: None,
+            message: format!(
+                "Grid must have at least one row and column, found {} columns and {} rows",
+                doc.column_count, doc.row_count
+            ),
+        });
+    }
+
+    let elements = placed_elements(doc);
+    let mut valid = Vec::new();
+    for placed in &elements {
+        if placed.element.kind == ElementKind::Button
+            && doc.buttons[placed.element.index].name.is_empty()
+        {
+            issues.push(issue(
+                "missing-name",
+                Severity::Error,
+                placed,
+                format!("{} has no name", placed.label),
+            ));
+        }
+        if placed.row_span < 1 || placed.column_span < 1 {
+            issues.push(issue(
+                "invalid-span",
+                Severity::Error,
+                placed,
+                format!(
+                    "{} has a span of {} rows by {} columns",
+                    placed.label, placed.row_span, placed.column_span
+                ),
+            ));
+            continue;
+        }
+        if placed.area.row < 1
+            || placed.area.column < 1
+            || (doc.row_count >= 1 && placed.area.last_row > i64::from(doc.row_count))
+            || (doc.column_count >= 1 && placed.area.last_column > i64::from(doc.column_count))
+        {
+            issues.push(issue(
+                "out-of-grid",
+                Severity::Error,
+                placed,
+                format!(
+                    "{} covers rows {}-{} and columns {}-{}, outside the {} by {} grid",
+                    placed.label,
+                    placed.area.row,
+                    placed.area.last_row,
+                    placed.area.column,
+                    placed.area.last_column,
+                    doc.row_count,
+                    doc.column_count
+                ),
+            ));
+        }
+        valid.push(placed);
+    }
+
+    for (position, placed) in valid.iter().enumerate() {
+        for earlier in &valid[..position] {
+            let Some((row, column)) = placed.area.overlap(earlier.area) else {
+                continue;
+            };
+            let (rule, severity) = match (earlier.element.kind, placed.element.kind) {
+                (ElementKind::Button, ElementKind::Button) => ("button-overlap", Severity::Error),
+                (ElementKind::Border, _) | (_, ElementKind::Border) => continue,
+                _ => ("image-overlap", Severity::Warning),
+            };
+            issues.push(issue(
+                rule,
+                severity,
+                placed,
+                format!(
+                    "{} overlaps {} at row {}, column {}",
+                    placed.label, earlier.label, row, column
+                ),
+            ));
+        }
+    }
+
+    issues
+}
+
+/// `Err` listing every error in `doc`, for commands that refuse to write
+/// invalid skins. Warnings never block.
+pub fn check_document(doc: &VcpDocument) -> Result<(), String> {
+    let errors: Vec<String> = validate_document(doc)
+        .into_iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .map(|issue| issue.message)
+        .collect();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Skin has {} validation error(s): {}",
+        errors.len(),
+        errors.join("; ")
+    ))
+}
+
+fn placed_elements(doc: &VcpDocument) -> Vec<Placed> {
+    let borders = doc
+        .borders
+        .iter()
+        .enumerate()
+        .map(|(index, border)| Placed {
+            element: ElementRef {
+                kind: ElementKind::Border,
+                index,
+            },
+            label: format!("Border {}", index + 1),
+            row_span: border.row_span,
+            column_span: border.column_span,
+            area: Area::new(
+                border.row_start,
+                border.column_start,
+                border.row_span,
+                border.column_span,
+            ),
+        });
+    let images = doc.images.iter().enumerate().map(|(index, image)| Placed {
+        element: ElementRef {
+            kind: ElementKind::Image,
+            index,
+        },
+        label: format!("Image '{}'", image.path),
+        row_span: image.row_span,
+        column_span: image.column_span,
+        area: Area::new(
+            image.row_start,
+            image.column_start,
+            image.row_span,
+            image.column_span,
+        ),
+    });
+    let buttons = doc.buttons.iter().enumerate().map(|(index, button)| {
+        // CNC12 treats a missing span as one cell
+        let row_span = button.row_span.unwrap_or(1);
+        let column_span = button.column_span.unwrap_or(1);
+        Placed {
+            element: ElementRef {
+                kind: ElementKind::Button,
+                index,
+            },
+            label: if button.name.is_empty() {
+                format!("Button {}", index + 1)
+            } else {
+                format!("Button '{}'", button.name)
+            },
+            row_span,
+            column_span,
+            area: Area::new(button.row, button.column, row_span, column_span),
+        }
+    });
+    borders.chain(images).chain(buttons).collect()
+}
+
+fn issue(rule: &str, severity: Severity, placed: &Placed, message: String) -> Issue {
+    Issue {
+        rule: rule.to_string(),
+        severity,
+        element: Some(placed.element),
+        message,
+    }
+}
@@ -0,0 +1,222 @@
+use quick_xml::escape::{escape, partial_escape};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// A lightweight XML tree built on top of quick-xml's streaming reader.
+///
+/// Every node remembers the byte range it was read from so callers can report
+/// positions and, where needed, reuse the original markup.
+#[derive(Debug, Clone)]
+pub struct XmlDocument {
+    pub nodes: Vec<XmlNode>,
+}
+
+#[derive(Debug, Clone)]
+pub struct XmlNode {
+    pub kind: XmlNodeKind,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub enum XmlNodeKind {
+    Element(XmlElement),
+    Text(String),
+    CData(String),
+    Comment(String),
+    Declaration,
+    ProcessingInstruction,
+    DocType,
+}
+
+#[derive(Debug, Clone)]
+pub struct XmlElement {
+    pub name: String,
+    pub attributes: Vec<XmlAttribute>,
+    pub children: Vec<XmlNode>,
+    pub span: Range<usize>,
+    /// Range between the start and end tags; `None` for self-closing elements.
+    pub inner: Option<Range<usize>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct XmlAttribute {
+    pub name: String,
+    pub value: String,
+}
+
+impl XmlDocument {
+    /// The first (and for well-formed files, only) top-level element.
+    pub fn root(&self) -> Option<&XmlElement> {
+        self.nodes.iter().find_map(|node| match &node.kind {
+            XmlNodeKind::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+}
+
+impl XmlElement {
+    pub fn child_elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|node| match &node.kind {
+            XmlNodeKind::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    pub fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.child_elements().find(|child| child.name == name)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name == name)
+            .map(|attr| attr.value.as_str())
+    }
+
+    /// Concatenated text and CDATA content of this element, trimmed.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for node in &self.children {
+            match &node.kind {
+                XmlNodeKind::Text(value) | XmlNodeKind::CData(value) => text.push_str(value),
+                _ => {}
+            }
+        }
+        text.trim().to_string()
+    }
+}
+
+/// Parses `content` into an [`XmlDocument`].
+///
+/// Whitespace, comments and declarations are kept as nodes; entities in text
+/// and attribute values are unescaped.
+pub fn parse_document(content: &str) -> Result<XmlDocument, String> {
+    let mut reader = Reader::from_str(content);
+    reader.check_end_names(true);
+
+    let mut stack: Vec<XmlElement> = Vec::new();
+    let mut nodes: Vec<XmlNode> = Vec::new();
+
+    loop {
+        let start = reader.buffer_position();
+        let event = reader
+            .read_event()
+            .map_err(|e| error_at(content, reader.buffer_position(), &e.to_string()))?;
+        let end = reader.buffer_position();
+
+        let kind = match event {
+            Event::Start(tag) => {
+                let mut element = element_from_tag(content, &tag, start..end)?;
+                element.inner = Some(end..end);
+                stack.push(element);
+                continue;
+            }
+            Event::End(_) => {
+                let mut element = stack
+                    .pop()
+                    .ok_or_else(|| error_at(content, start, "Unexpected closing tag"))?;
+                element.span.end = end;
+                if let Some(inner) = element.inner.as_mut() {
+                    inner.end = start;
+                }
+                XmlNodeKind::Element(element)
+            }
+            Event::Empty(tag) => XmlNodeKind::Element(element_from_tag(content, &tag, start..end)?),
+            Event::Text(text) => XmlNodeKind::Text(
+                text.unescape()
+                    .map_err(|e| error_at(content, start, &e.to_string()))?
+                    .into_owned(),
+            ),
+            Event::CData(data) => {
+                XmlNodeKind::CData(String::from_utf8_lossy(&data.into_inner()).into_owned())
+            }
+            Event::Comment(comment) => {
+                XmlNodeKind::Comment(String::from_utf8_lossy(&comment).into_owned())
+            }
+            Event::Decl(_) => XmlNodeKind::Declaration,
+            Event::PI(_) => XmlNodeKind::ProcessingInstruction,
+            Event::DocType(_) => XmlNodeKind::DocType,
+            Event::Eof => break,
+        };
+
+        let span = match &kind {
+            XmlNodeKind::Element(element) => element.span.clone(),
+            _ => start..end,
+        };
+        let node = XmlNode { kind, span };
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => nodes.push(node),
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        return Err(error_at(
+            content,
+            open.span.start,
+            &format!("Unclosed element <{}>", open.name),
+        ));
+    }
+
+    Ok(XmlDocument { nodes })
+}
+
+fn element_from_tag(
+    content: &str,
+    tag: &BytesStart,
+    span: Range<usize>,
+) -> Result<XmlElement, String> {
+    let mut attributes = Vec::new();
+    for attr in tag.attributes() {
+        let attr = attr.map_err(|e| error_at(content, span.start, &e.to_string()))?;
+        let value = attr
+            .unescape_value()
+            .map_err(|e| error_at(content, span.start, &e.to_string()))?;
+        attributes.push(XmlAttribute {
+            name: String::from_utf8_lossy(attr.key.as_ref()).into_owned(),
+            value: value.into_owned(),
+        });
+    }
+
+    Ok(XmlElement {
+        name: String::from_utf8_lossy(tag.name().as_ref()).into_owned(),
+        attributes,
+        children: Vec::new(),
+        span,
+        inner: None,
+    })
+}
+
+/// Escapes `&`, `<` and `>` so `value` can be written as element text.
+pub fn escape_text(value: &str) -> Cow<'_, str> {
+    partial_escape(value)
+}
+
+/// Escapes `value` for use inside a quoted attribute.
+pub fn escape_attribute(value: &str) -> Cow<'_, str> {
+    escape(value)
+}
+
+/// Converts a byte offset into a 1-based (line, column) pair.
+pub fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(content.len());
+    let before = &content.as_bytes()[..offset];
+    let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+    let line_start = before
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map(|pos| pos + 1)
+        .unwrap_or(0);
+    let column = String::from_utf8_lossy(&before[line_start..])
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+fn error_at(content: &str, offset: usize, message: &str) -> String {
+    let (line, column) = line_column(content, offset);
+    format!("XML error at line {}, column {}: {}", line, column, message)
+}
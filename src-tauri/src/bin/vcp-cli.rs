@@ -0,0 +1,381 @@
+//! Command-line access to the editor's backend, for scripts and CI.
+//!
+//! Every subcommand prints a JSON object on stdout. The exit code is 0 when
+//! everything is fine, 1 when the command found problems (validation errors,
+//! missing assets, unformatted files) and 2 when it could not run at all.
+
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+use vcp_editor_lib::backend::assets::{check_project_assets, check_skin_assets};
+use vcp_editor_lib::backend::deploy::{deploy_export, list_backups, rollback_deploy};
+use vcp_editor_lib::backend::export::{export_project_into, ExportEntry, DEFAULT_CNC_BASE_PATH};
+use vcp_editor_lib::backend::import::import_cnc;
+use vcp_editor_lib::backend::manifest::verify_export;
+use vcp_editor_lib::backend::models::{MachineProfile, Severity, SkinTarget};
+use vcp_editor_lib::backend::parser::{load_file, render_file, vcp_root_of};
+use vcp_editor_lib::backend::project::{
+    discard_layout, load_project_file, read_project, save_project_file, write_project,
+};
+use vcp_editor_lib::backend::settings::load_project_settings;
+use vcp_editor_lib::backend::validate::validate_document;
+
+const USAGE: &str = "Usage: vcp-cli <command> [arguments]
+
+Commands:
+  validate <skin.vcp>                 Check the skin against the grid rules
+  fmt [--check] [--keep-layout] <skin.vcp>...
+                                      Rewrite skins in place with the project's output settings;
+                                      --keep-layout keeps comments and the file's own markup, changing
+                                      only line endings, the declaration and element order
+  export <skin.vcp>[=<name>]... --target <dir> [--cnc-base <path>]
+                                      Write vcp_export_<timestamp>.zip into <dir>; <name> is a file
+                                      name or a machine such as oak-lathe, and defaults to the
+                                      project's machine. Image paths point into <path> on the CNC,
+                                      by default the project's setting or C:\\cncm\\resources\\vcp
+  verify <zip|folder>                 Check an export, or the folder it was deployed to, against its
+                                      manifest
+  deploy <zip> <vcp-dir>              Replace <vcp-dir> with the export, keeping the old folder as
+                                      <vcp-dir>_backup_<timestamp>
+  backups <vcp-dir>                   List the backups of <vcp-dir>, newest first
+  rollback <vcp-dir> [<backup>]       Put <backup>, or the newest backup, back in place of <vcp-dir>
+  import-cnc <zip|folder> <dir> [--overwrite]
+                                      Turn an export or a vcp folder copied off a CNC back into a
+                                      project in <dir>, with image paths made relative again
+  info <skin.vcp>                     Print grid size and element counts
+  check-assets <vcp-root|skin.vcp>    Report missing, unreadable or empty files
+  project export <vcp-root> <file> [--no-layout]
+                                      Write all skins and buttons to a .json or .yaml file
+  project import <file> <vcp-root>    Write the skins and buttons of a project file to XML";
+
+/// What a subcommand found: its JSON report and whether it found problems.
+struct Outcome {
+    report: Value,
+    problems: bool,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((command, rest)) = args.split_first() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let result = match command.as_str() {
+        "validate" => single_path(rest).and_then(validate),
+        "fmt" => fmt(rest),
+        "export" => export(rest),
+        "info" => single_path(rest).and_then(info),
+        "check-assets" => single_path(rest).and_then(check_assets),
+        "project" => project(rest),
+        "verify" => single_path(rest).and_then(verify),
+        "import-cnc" => import(rest),
+        "deploy" => deploy(rest),
+        "backups" => single_path(rest).and_then(backups),
+        "rollback" => rollback(rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        _ => Err(format!("Unknown command '{}', see vcp-cli help", command)),
+    };
+
+    match result {
+        Ok(outcome) => {
+            print_json(&outcome.report);
+            if outcome.problems {
+                ExitCode::from(1)
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Err(e) => {
+            print_json(&json!({ "error": e }));
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn print_json(value: &Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+    );
+}
+
+fn single_path(args: &[String]) -> Result<&str, String> {
+    match args {
+        [path] if !path.starts_with("--") => Ok(path),
+        _ => Err("Expected a single path".to_string()),
+    }
+}
+
+fn validate(path: &str) -> Result<Outcome, String> {
+    let loaded = load_file(path)?;
+    let issues = validate_document(&loaded.document);
+    let problems = issues.iter().any(|issue| issue.severity == Severity::Error)
+        || loaded
+            .diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error);
+    Ok(Outcome {
+        report: json!({
+            "file": path,
+            "valid": !problems,
+            "issues": issues,
+            "diagnostics": loaded.diagnostics,
+            "button_errors": loaded.button_errors,
+        }),
+        problems,
+    })
+}
+
+fn fmt(args: &[String]) -> Result<Outcome, String> {
+    let mut check = false;
+    let mut keep_layout = false;
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--keep-layout" => keep_layout = true,
+            flag if flag.starts_with("--") => return Err(format!("Unknown option '{}'", flag)),
+            path => paths.push(path),
+        }
+    }
+    if paths.is_empty() {
+        return Err("Expected at least one skin".to_string());
+    }
+
+    let mut files = Vec::new();
+    let mut unformatted = false;
+    for path in paths {
+        let settings = load_project_settings(vcp_root_of(Path::new(path))?)?;
+        let mut doc = load_file(path)?.document;
+        if !keep_layout {
+            discard_layout(&mut doc);
+        }
+        let current = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let formatted = render_file(&doc, &settings.output);
+        let changed = formatted != current;
+        if changed && !check {
+            fs::write(path, formatted).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        }
+        unformatted |= changed;
+        files.push(json!({ "file": path, "changed": changed }));
+    }
+
+    Ok(Outcome {
+        report: json!({ "files": files }),
+        problems: check && unformatted,
+    })
+}
+
+fn export(args: &[String]) -> Result<Outcome, String> {
+    let mut skins = Vec::new();
+    let mut target = None;
+    let mut cnc_base_path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--target" => target = args.next(),
+            "--cnc-base" => {
+                cnc_base_path = Some(args.next().ok_or("Expected a path after --cnc-base")?)
+            }
+            flag if flag.starts_with("--") => return Err(format!("Unknown option '{}'", flag)),
+            _ => skins.push(arg),
+        }
+    }
+    let Some(target) = target.filter(|_| !skins.is_empty()) else {
+        return Err("Expected one or more skins and --target <dir>".to_string());
+    };
+
+    let mut vcp_root = None;
+    let mut entries = Vec::new();
+    let mut files = Vec::new();
+    for skin in skins {
+        let (path, name) = match skin.rsplit_once('=') {
+            Some((path, name)) => (path, Some(name)),
+            None => (skin.as_str(), None),
+        };
+        let root = vcp_root_of(Path::new(path))?;
+        if *vcp_root.get_or_insert(root) != root {
+            return Err(format!(
+                "{} is not in the same project as the other skins",
+                path
+            ));
+        }
+        let target = match name {
+            Some(name) if name.to_ascii_lowercase().ends_with(".vcp") => {
+                Some(SkinTarget::File(name.to_string()))
+            }
+            Some(name) => Some(SkinTarget::Machine(MachineProfile::parse(name)?)),
+            None => None,
+        };
+        entries.push(ExportEntry {
+            document: load_file(path)?.document,
+            target,
+            source: Some(path.to_string()),
+        });
+        files.push(path);
+    }
+
+    let vcp_root = vcp_root.ok_or("Expected one or more skins")?;
+    let cnc_base_path = match cnc_base_path {
+        Some(path) => path.clone(),
+        None => load_project_settings(vcp_root)?
+            .cnc_base_path
+            .unwrap_or_else(|| DEFAULT_CNC_BASE_PATH.to_string()),
+    };
+    let export = export_project_into(Path::new(target), vcp_root, &cnc_base_path, &entries, None)?;
+    Ok(Outcome {
+        problems: !export.asset_issues.is_empty(),
+        report: json!({
+            "files": files,
+            "zip": export.zip,
+            "asset_issues": export.asset_issues,
+        }),
+    })
+}
+
+fn verify(path: &str) -> Result<Outcome, String> {
+    let report = verify_export(Path::new(path))?;
+    Ok(Outcome {
+        problems: !report.issues.is_empty(),
+        report: json!({
+            "path": path,
+            "valid": report.issues.is_empty(),
+            "issues": report.issues,
+            "manifest": report.manifest,
+        }),
+    })
+}
+
+fn deploy(args: &[String]) -> Result<Outcome, String> {
+    let [zip, target] = args else {
+        return Err("Expected an export zip and the vcp folder to deploy it to".to_string());
+    };
+    let report = deploy_export(Path::new(zip), Path::new(target))?;
+    Ok(Outcome {
+        report: json!(report),
+        problems: false,
+    })
+}
+
+fn backups(target: &str) -> Result<Outcome, String> {
+    let backups = list_backups(Path::new(target))?;
+    Ok(Outcome {
+        report: json!({ "target": target, "backups": backups }),
+        problems: false,
+    })
+}
+
+fn rollback(args: &[String]) -> Result<Outcome, String> {
+    let (target, backup) = match args {
+        [target] => (target, None),
+        [target, backup] => (target, Some(backup.as_str())),
+        _ => return Err("Expected a vcp folder and optionally a backup".to_string()),
+    };
+    let report = rollback_deploy(Path::new(target), backup)?;
+    Ok(Outcome {
+        report: json!(report),
+        problems: false,
+    })
+}
+
+fn import(args: &[String]) -> Result<Outcome, String> {
+    let mut overwrite = false;
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--overwrite" => overwrite = true,
+            flag if flag.starts_with("--") => return Err(format!("Unknown option '{}'", flag)),
+            path => paths.push(path),
+        }
+    }
+    let [source, destination] = paths[..] else {
+        return Err("Expected a zip or folder and a destination folder".to_string());
+    };
+
+    let report = import_cnc(Path::new(source), Path::new(destination), overwrite)?;
+    Ok(Outcome {
+        report: json!({
+            "source": source,
+            "root": report.root,
+            "files": report.files,
+            "rewritten": report.rewritten,
+        }),
+        problems: false,
+    })
+}
+
+fn info(path: &str) -> Result<Outcome, String> {
+    let doc = load_file(path)?.document;
+    Ok(Outcome {
+        report: json!({
+            "file": path,
+            "columns": doc.column_count,
+            "rows": doc.row_count,
+            "background": doc.background,
+            "borders": doc.borders.len(),
+            "images": doc.images.len(),
+            "buttons": doc.buttons.len(),
+            "format": doc.format,
+        }),
+        problems: false,
+    })
+}
+
+fn check_assets(path: &str) -> Result<Outcome, String> {
+    let path = Path::new(path);
+    let (vcp_root, mut issues) = if path.is_dir() {
+        (path, Vec::new())
+    } else {
+        let vcp_root = vcp_root_of(path)?;
+        let doc = load_file(&path.to_string_lossy())?.document;
+        let label = path.strip_prefix(vcp_root).unwrap_or(path);
+        let label = label.to_string_lossy().replace('\\', "/");
+        (vcp_root, check_skin_assets(vcp_root, &label, &doc))
+    };
+    for issue in check_project_assets(vcp_root)? {
+        if !issues.contains(&issue) {
+            issues.push(issue);
+        }
+    }
+    Ok(Outcome {
+        problems: !issues.is_empty(),
+        report: json!({ "root": vcp_root, "issues": issues }),
+    })
+}
+
+fn project(args: &[String]) -> Result<Outcome, String> {
+    let (action, args) = args.split_first().ok_or("Expected export or import")?;
+    let keep_layout = !args.iter().any(|arg| arg == "--no-layout");
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--no-layout").collect();
+    match (action.as_str(), paths.as_slice()) {
+        ("export", [vcp_root, file]) => {
+            let project = read_project(Path::new(vcp_root), keep_layout)?;
+            save_project_file(Path::new(file), &project)?;
+            Ok(Outcome {
+                report: json!({
+                    "file": file,
+                    "skins": project.skins.len(),
+                    "buttons": project.buttons.len(),
+                }),
+                problems: false,
+            })
+        }
+        ("import", [file, vcp_root]) if keep_layout => {
+            let project = load_project_file(Path::new(file))?;
+            let written = write_project(Path::new(vcp_root), &project)?;
+            Ok(Outcome {
+                report: json!({ "root": vcp_root, "written": written }),
+                problems: false,
+            })
+        }
+        _ => Err(
+            "Expected project export <vcp-root> <file> or project import <file> <vcp-root>"
+                .to_string(),
+        ),
+    }
+}
@@ -1,68 +1,355 @@
-mod backend;
-
-use backend::models::VcpDocument;
-use backend::parser::{load_file, save_file};
+pub mod backend;
+
+use backend::assets;
+use backend::button::{button_folder, load_button, save_button};
+use backend::catalog::{catalog_from_plc, project_catalog, refresh_catalog};
+use backend::deploy::{deploy_project, list_backups, rollback_deploy};
+use backend::documents::DocumentRegistry;
+use backend::events;
+use backend::export::{export_project, read_export_skins, ExportEntry};
+use backend::import::import_cnc;
+use backend::manifest::verify_export;
+use backend::models::{
+    AssetIssue, CatalogDiff, DeployReport, DocumentId, DocumentInfo, EditCommand, EventIssue,
+    ExportReport, ExportSkin, HistoryList, ImportReport, Issue, LoadedDocument, OpenedDocument,
+    PlcCrossCheck, PlcDefinitions, ProjectSettings, SerializeOptions, SkinTarget, SymbolCatalog,
+    VcpButton, VcpDocument, VerifyReport,
+};
+use backend::parser::{load_file, save_file, serialize_vcp, serialize_vcp_with, vcp_root_of};
+use backend::plc;
+use backend::plc_stub::generate_plc_stub;
+use backend::project::{load_project_file, read_project, save_project_file, write_project};
+use backend::settings::{load_project_settings, save_project_settings};
+use backend::validate::{self, check_document};
 use std::path::Path;
 use std::sync::Mutex;
 use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
-use tauri::{Emitter, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 
-// Application state to manage documents
+// Application state: every open document, keyed by id
 struct AppState {
-    current_document: Mutex<Option<VcpDocument>>,
-    current_file_path: Mutex<Option<String>>,
+    documents: Mutex<DocumentRegistry>,
+}
+
+/// Puts `document` in place of `document_id`, or opens it as a new document
+/// of the calling window when no id is given.
+fn place_document(
+    state: &AppState,
+    document_id: Option<DocumentId>,
+    window: &tauri::Window,
+    document: VcpDocument,
+    path: Option<String>,
+) -> Result<DocumentId, String> {
+    let mut documents = state.documents.lock().unwrap();
+    match document_id {
+        Some(id) => {
+            documents.replace(id, document, path)?;
+            Ok(id)
+        }
+        None => Ok(documents.insert(document, path, Some(window.label().to_string()))),
+    }
 }
 
 #[tauri::command]
-fn new_document() -> Result<VcpDocument, String> {
-    Ok(VcpDocument::default())
+fn new_document(
+    document_id: Option<DocumentId>,
+    window: tauri::Window,
+    state: State<AppState>,
+) -> Result<OpenedDocument, String> {
+    let loaded = LoadedDocument {
+        document: VcpDocument::default(),
+        diagnostics: Vec::new(),
+        button_errors: Vec::new(),
+    };
+    let id = place_document(&state, document_id, &window, loaded.document.clone(), None)?;
+    Ok(OpenedDocument { id, loaded })
 }
 
 #[tauri::command]
-fn open_file(path: String, state: State<AppState>) -> Result<VcpDocument, String> {
-    let doc = load_file(&path)?;
-    *state.current_document.lock().unwrap() = Some(doc.clone());
-    *state.current_file_path.lock().unwrap() = Some(path);
-    Ok(doc)
+fn open_file(
+    path: String,
+    document_id: Option<DocumentId>,
+    window: tauri::Window,
+    state: State<AppState>,
+) -> Result<OpenedDocument, String> {
+    let loaded = load_file(&path)?;
+    let id = place_document(
+        &state,
+        document_id,
+        &window,
+        loaded.document.clone(),
+        Some(path),
+    )?;
+    Ok(OpenedDocument { id, loaded })
 }
 
+/// Saves document `document_id` to `path`. `doc`, when given, replaces the
+/// document first and, like `update_document`, starts its history afresh,
+/// as the recorded steps no longer apply to it.
 #[tauri::command]
-fn save_file_command(path: String, doc: VcpDocument, state: State<AppState>) -> Result<(), String> {
-    save_file(&path, &doc)?;
-    *state.current_document.lock().unwrap() = Some(doc);
-    *state.current_file_path.lock().unwrap() = Some(path);
+fn save_file_command(
+    document_id: DocumentId,
+    path: String,
+    doc: Option<VcpDocument>,
+    options: Option<SerializeOptions>,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let mut documents = state.documents.lock().unwrap();
+    let entry = documents.get_mut(document_id)?;
+    let document = doc.as_ref().unwrap_or(&entry.document);
+    let settings = load_project_settings(vcp_root_of(Path::new(&path))?)?;
+    if settings.validation.block_save {
+        check_document(document)?;
+    }
+    let options = options.unwrap_or(settings.output);
+    save_file(&path, document, &options)?;
+    match doc {
+        Some(doc) => {
+            entry.document = doc;
+            entry.history.reset(true);
+        }
+        None => entry.history.mark_saved(),
+    }
+    entry.path = Some(path);
     Ok(())
 }
 
 #[tauri::command]
-fn update_document(doc: VcpDocument, state: State<AppState>) -> Result<(), String> {
-    *state.current_document.lock().unwrap() = Some(doc);
+fn validate_document(doc: VcpDocument) -> Result<Vec<Issue>, String> {
+    Ok(validate::validate_document(&doc))
+}
+
+#[tauri::command]
+fn check_project_assets(vcp_resources_folder: String) -> Result<Vec<AssetIssue>, String> {
+    assets::check_project_assets(Path::new(&vcp_resources_folder))
+}
+
+#[tauri::command]
+fn analyze_skin_events(vcp_resources_folder: String) -> Result<Vec<EventIssue>, String> {
+    events::analyze_skin_events(Path::new(&vcp_resources_folder))
+}
+
+#[tauri::command]
+fn load_plc_source(path: String) -> Result<PlcDefinitions, String> {
+    plc::load_plc_source(Path::new(&path))
+}
+
+#[tauri::command]
+fn cross_check_plc(doc: VcpDocument, plc_source_path: String) -> Result<PlcCrossCheck, String> {
+    let definitions = plc::load_plc_source(Path::new(&plc_source_path))?;
+    Ok(plc::cross_check(&doc, &definitions))
+}
+
+#[tauri::command]
+fn generate_plc_source_stub(
+    vcp_resources_folder: String,
+    doc: VcpDocument,
+    plc_source_path: Option<String>,
+) -> Result<String, String> {
+    // Without a PLC program, skip the events the project catalog already knows
+    let project = project_catalog(Path::new(&vcp_resources_folder))?;
+    let known = match plc_source_path {
+        Some(path) => catalog_from_plc(&plc::load_plc_source(Path::new(&path))?, &project),
+        None => project,
+    };
+    Ok(generate_plc_stub(&doc, &known))
+}
+
+#[tauri::command]
+fn get_symbol_catalog(vcp_resources_folder: String) -> Result<SymbolCatalog, String> {
+    project_catalog(Path::new(&vcp_resources_folder))
+}
+
+#[tauri::command]
+fn refresh_symbol_catalog(
+    vcp_resources_folder: String,
+    plc_source_path: String,
+    apply: bool,
+) -> Result<CatalogDiff, String> {
+    refresh_catalog(
+        Path::new(&vcp_resources_folder),
+        Path::new(&plc_source_path),
+        apply,
+    )
+}
+
+#[tauri::command]
+fn export_project_file(
+    vcp_resources_folder: String,
+    path: String,
+    keep_layout: bool,
+) -> Result<(), String> {
+    let project = read_project(Path::new(&vcp_resources_folder), keep_layout)?;
+    save_project_file(Path::new(&path), &project)
+}
+
+#[tauri::command]
+fn import_project_file(vcp_resources_folder: String, path: String) -> Result<Vec<String>, String> {
+    let project = load_project_file(Path::new(&path))?;
+    write_project(Path::new(&vcp_resources_folder), &project)
+}
+
+/// Replaces the document outside the undo history, which starts over.
+#[tauri::command]
+fn update_document(
+    document_id: DocumentId,
+    doc: VcpDocument,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let mut documents = state.documents.lock().unwrap();
+    let entry = documents.get_mut(document_id)?;
+    entry.document = doc;
+    entry.history.reset(false);
     Ok(())
 }
 
 #[tauri::command]
-fn get_current_document(state: State<AppState>) -> Result<Option<VcpDocument>, String> {
-    Ok(state.current_document.lock().unwrap().clone())
+fn get_document(document_id: DocumentId, state: State<AppState>) -> Result<VcpDocument, String> {
+    Ok(state
+        .documents
+        .lock()
+        .unwrap()
+        .get(document_id)?
+        .document
+        .clone())
+}
+
+#[tauri::command]
+fn get_document_info(
+    document_id: DocumentId,
+    state: State<AppState>,
+) -> Result<DocumentInfo, String> {
+    state.documents.lock().unwrap().info(document_id)
+}
+
+#[tauri::command]
+fn list_documents(state: State<AppState>) -> Result<Vec<DocumentInfo>, String> {
+    Ok(state.documents.lock().unwrap().list())
+}
+
+/// Forgets a document and its history. Unsaved changes are lost.
+#[tauri::command]
+fn close_document(document_id: DocumentId, state: State<AppState>) -> Result<(), String> {
+    state
+        .documents
+        .lock()
+        .unwrap()
+        .close(document_id)
+        .map(|_| ())
+        .ok_or_else(|| format!("No open document with id {}", document_id))
+}
+
+/// Applies `edits` to a document as one undo step.
+#[tauri::command]
+fn apply_edits(
+    document_id: DocumentId,
+    edits: Vec<EditCommand>,
+    label: String,
+    state: State<AppState>,
+) -> Result<HistoryList, String> {
+    let mut documents = state.documents.lock().unwrap();
+    let entry = documents.get_mut(document_id)?;
+    entry.history.apply(&mut entry.document, edits, label)?;
+    Ok(entry.history.list())
+}
+
+/// Reverts the last step, returning the document as it is now, or `None`
+/// when there is nothing to undo.
+#[tauri::command]
+fn undo_edit(
+    document_id: DocumentId,
+    state: State<AppState>,
+) -> Result<Option<VcpDocument>, String> {
+    let mut documents = state.documents.lock().unwrap();
+    let entry = documents.get_mut(document_id)?;
+    let undone = entry.history.undo(&mut entry.document)?;
+    Ok(undone.then(|| entry.document.clone()))
+}
+
+#[tauri::command]
+fn redo_edit(
+    document_id: DocumentId,
+    state: State<AppState>,
+) -> Result<Option<VcpDocument>, String> {
+    let mut documents = state.documents.lock().unwrap();
+    let entry = documents.get_mut(document_id)?;
+    let redone = entry.history.redo(&mut entry.document)?;
+    Ok(redone.then(|| entry.document.clone()))
+}
+
+#[tauri::command]
+fn get_edit_history(
+    document_id: DocumentId,
+    state: State<AppState>,
+) -> Result<HistoryList, String> {
+    Ok(state
+        .documents
+        .lock()
+        .unwrap()
+        .get(document_id)?
+        .history
+        .list())
+}
+
+/// Opens `path`, or a blank skin, in a window of its own and returns the id
+/// of its document. The window finds the id in its URL.
+fn open_window_for(app: &AppHandle, path: Option<String>) -> Result<DocumentId, String> {
+    let document = match &path {
+        Some(path) => load_file(path)?.document,
+        None => VcpDocument::default(),
+    };
+    let state = app.state::<AppState>();
+    let mut documents = state.documents.lock().unwrap();
+    let id = documents.insert(document, path, None);
+    let label = format!("editor-{}", id);
+    documents.get_mut(id)?.window = Some(label.clone());
+    drop(documents);
+
+    let url = tauri::WebviewUrl::App(format!("index.html?document={}", id).into());
+    let built = tauri::WebviewWindowBuilder::new(app, &label, url)
+        .title("Untitled")
+        .inner_size(800.0, 800.0)
+        .visible(false)
+        .build();
+    if let Err(e) = built {
+        state.documents.lock().unwrap().close(id);
+        return Err(format!("Failed to open window: {}", e));
+    }
+    Ok(id)
+}
+
+// Async so the window is not built on the main thread, which deadlocks on
+// Windows
+#[tauri::command]
+async fn open_document_window(app: AppHandle, path: Option<String>) -> Result<DocumentId, String> {
+    open_window_for(&app, path)
+}
+
+/// Sends a menu event to the focused window only, so File > Save saves the
+/// document in front of the user rather than every open one.
+fn emit_to_focused(app: &AppHandle, event: &str) {
+    let focused = app
+        .webview_windows()
+        .into_iter()
+        .find(|(_, window)| window.is_focused().unwrap_or(false))
+        .map(|(label, _)| label);
+    let _ = match focused {
+        Some(label) => app.emit_to(label.as_str(), event, ()),
+        None => app.emit_to("main", event, ()),
+    };
 }
 
 #[tauri::command]
-async fn print_window(app: tauri::AppHandle) -> Result<(), String> {
-    use tauri::Manager;
-    let window = app.get_webview_window("main").ok_or("Window not found")?;
+async fn print_window(window: tauri::WebviewWindow) -> Result<(), String> {
     window.print().map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn create_button_folder(
-    base_path: String,
-    button_name: String,
-) -> Result<String, String> {
+fn create_button_folder(base_path: String, button_name: String) -> Result<String, String> {
     use std::fs;
     use std::path::Path;
 
-    let button_folder = Path::new(&base_path)
-        .join("Buttons")
-        .join(&button_name);
+    let button_folder = button_folder(Path::new(&base_path), &button_name)?;
 
     // Create folder if it doesn't exist, or just return existing path
     if !button_folder.exists() {
@@ -78,7 +365,7 @@ fn ensure_vcp_folder_structure(base_path: String) -> Result<(), String> {
     use std::fs;
     use std::path::Path;
 
-    let folders = vec!["skins", "images", "Buttons"];
+    let folders = ["skins", "images", "Buttons"];
 
     for folder in folders {
         let folder_path = format!("{}/{}", base_path, folder);
@@ -93,131 +380,113 @@ fn ensure_vcp_folder_structure(base_path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Exports `doc` as it is in the editor, together with any other `skins`
+/// of the project. Skins without a target are named for the project's
+/// machine. `source_path` is the file `doc` was opened from, if any.
 #[tauri::command]
 fn export_to_cnc(
     vcp_resources_folder: String,
     cnc_base_path: String,
-    vcp_content: String,
-) -> Result<String, String> {
-    use std::fs;
-    use std::io::{Read, Write};
-    use std::path::Path;
-    use zip::write::FileOptions;
-    use zip::ZipWriter;
-
-    // Convert relative image paths to absolute CNC paths
-    let converted_content = vcp_content.replace(
-        "<path>images/",
-        &format!("<path>{}/images/", cnc_base_path.replace("\\", "/"))
-    );
-
-    // Create temp directory for export
-    let temp_dir = std::env::temp_dir().join("vcp_cnc_export");
-    fs::create_dir_all(&temp_dir).map_err(|e| format!("Failed to create temp dir: {}", e))?;
-
-    // Copy VCP folder structure to temp
-    let vcp_source = Path::new(&vcp_resources_folder);
-    let vcp_dest = temp_dir.join("vcp");
-
-    // Copy all files and folders
-    copy_dir_recursive(vcp_source, &vcp_dest)?;
-
-    // Write converted VCP file
-    let vcp_file_path = vcp_dest.join("skins").join("acorn_mill_vcp_skin.vcp");
-    fs::write(&vcp_file_path, converted_content)
-        .map_err(|e| format!("Failed to write VCP file: {}", e))?;
-
-    // Create zip file in CNC directory
-    let zip_filename = format!("vcp_export_{}.zip", chrono::Utc::now().format("%Y%m%d_%H%M%S"));
-    let zip_path = Path::new(&cnc_base_path).join(&zip_filename);
-    let file = fs::File::create(&zip_path)
-        .map_err(|e| format!("Failed to create zip file: {}", e))?;
-    
-    let mut zip = ZipWriter::new(file);
-    let options = FileOptions::<()>::default().compression_method(zip::CompressionMethod::Deflated);
-
-    // Add all files from temp directory to zip
-    let walker = walkdir::WalkDir::new(&temp_dir)
-        .into_iter()
-        .filter_map(|e| e.ok());
-
-    for entry in walker {
-        let path = entry.path();
-        if path.is_file() {
-            let name = path.strip_prefix(&temp_dir)
-                .map_err(|e| format!("Path strip error: {}", e))?;
-
-            zip.start_file(name.to_string_lossy(), options)
-                .map_err(|e| format!("Failed to add file to zip: {}", e))?;
-
-            let mut file_data = fs::File::open(path)
-                .map_err(|e| format!("Failed to open file: {}", e))?;
-            let mut buffer = Vec::new();
-            file_data.read_to_end(&mut buffer)
-                .map_err(|e| format!("Failed to read file: {}", e))?;
-            zip.write_all(&buffer)
-                .map_err(|e| format!("Failed to write to zip: {}", e))?;
-        }
-    }
-
-    zip.finish()
-        .map_err(|e| format!("Failed to finalize zip: {}", e))?;
-
-    // Clean up temp directory
-    fs::remove_dir_all(&temp_dir)
-        .map_err(|e| format!("Failed to clean up temp dir: {}", e))?;
+    doc: VcpDocument,
+    source_path: Option<String>,
+    target: Option<SkinTarget>,
+    skins: Option<Vec<ExportSkin>>,
+    options: Option<SerializeOptions>,
+) -> Result<ExportReport, String> {
+    let vcp_root = Path::new(&vcp_resources_folder);
+    let entries = export_entries(vcp_root, doc, source_path, target, skins)?;
+    export_project(vcp_root, &cnc_base_path, &entries, options)
+}
 
-    Ok(zip_path.to_string_lossy().to_string())
+/// Exports like `export_to_cnc` and unpacks the result into `deploy_folder`,
+/// the CNC's `vcp` folder as reached from here. The folder it replaces is
+/// kept as a timestamped backup.
+#[tauri::command]
+fn deploy_to_cnc(
+    vcp_resources_folder: String,
+    cnc_base_path: String,
+    deploy_folder: String,
+    doc: VcpDocument,
+    source_path: Option<String>,
+    skins: Option<Vec<ExportSkin>>,
+    options: Option<SerializeOptions>,
+) -> Result<DeployReport, String> {
+    let vcp_root = Path::new(&vcp_resources_folder);
+    let entries = export_entries(vcp_root, doc, source_path, None, skins)?;
+    deploy_project(
+        vcp_root,
+        &cnc_base_path,
+        Path::new(&deploy_folder),
+        &entries,
+        options,
+    )
 }
 
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), String> {
-    use std::fs;
-    
-    if !dst.exists() {
-        fs::create_dir_all(dst).map_err(|e| format!("Failed to create dir {}: {}", dst.display(), e))?;
-    }
-    
-    for entry in fs::read_dir(src).map_err(|e| format!("Failed to read dir {}: {}", src.display(), e))? {
-        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
-        
-        if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
-        } else {
-            fs::copy(&src_path, &dst_path).map_err(|e| format!("Failed to copy {} to {}: {}", src_path.display(), dst_path.display(), e))?;
-        }
-    }
-    
-    Ok(())
+/// Backups of `deploy_folder` that can be rolled back to, newest first.
+#[tauri::command]
+fn list_deploy_backups(deploy_folder: String) -> Result<Vec<String>, String> {
+    let backups = list_backups(Path::new(&deploy_folder))?;
+    Ok(backups
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
 }
 
+/// Puts `backup`, or the newest backup, back in place of `deploy_folder`.
 #[tauri::command]
-fn save_button_xml(
-    button_folder: String,
-    button_name: String,
-    xml_content: String,
-) -> Result<(), String> {
-    use std::fs;
-    use std::path::Path;
+fn rollback_deploy_command(
+    deploy_folder: String,
+    backup: Option<String>,
+) -> Result<DeployReport, String> {
+    rollback_deploy(Path::new(&deploy_folder), backup.as_deref())
+}
 
-    let xml_path = Path::new(&button_folder).join(format!("{}.xml", button_name));
-    fs::write(&xml_path, xml_content).map_err(|e| format!("Failed to write button XML: {}", e))?;
+/// `doc` as it is in the editor, followed by the other `skins` to export.
+fn export_entries(
+    vcp_root: &Path,
+    doc: VcpDocument,
+    source_path: Option<String>,
+    target: Option<SkinTarget>,
+    skins: Option<Vec<ExportSkin>>,
+) -> Result<Vec<ExportEntry>, String> {
+    let mut entries = vec![ExportEntry {
+        document: doc,
+        target,
+        source: source_path,
+    }];
+    entries.extend(read_export_skins(vcp_root, &skins.unwrap_or_default())?);
+    Ok(entries)
+}
 
-    Ok(())
+/// Checks an export zip or deployed folder against its manifest.
+#[tauri::command]
+fn verify_export_command(path: String) -> Result<VerifyReport, String> {
+    verify_export(Path::new(&path))
 }
 
+/// Turns an export zip or a folder copied off a CNC back into a project in
+/// `destination`, with image paths made relative again.
 #[tauri::command]
-fn load_button_xml(base_path: String, button_name: String) -> Result<String, String> {
-    use std::fs;
-    use std::path::Path;
+fn import_from_cnc(
+    source: String,
+    destination: String,
+    overwrite: Option<bool>,
+) -> Result<ImportReport, String> {
+    import_cnc(
+        Path::new(&source),
+        Path::new(&destination),
+        overwrite.unwrap_or(false),
+    )
+}
 
-    let xml_path = Path::new(&base_path)
-        .join("Buttons")
-        .join(&button_name)
-        .join(format!("{}.xml", button_name));
+#[tauri::command]
+fn save_button_definition(button_folder: String, button: VcpButton) -> Result<(), String> {
+    save_button(Path::new(&button_folder), &button)
+}
 
-    fs::read_to_string(&xml_path).map_err(|e| format!("Failed to read button XML: {}", e))
+#[tauri::command]
+fn load_button_definition(base_path: String, button_name: String) -> Result<VcpButton, String> {
+    load_button(Path::new(&base_path), &button_name)
 }
 
 #[tauri::command]
@@ -245,11 +514,21 @@ fn copy_file_to_button_folder(
     }
 
     // Write to destination
-    let mut file = fs::File::create(&dest_path)
-        .map_err(|e| format!("Failed to create destination file '{}': {}", dest_path.display(), e))?;
-
-    file.write_all(&data)
-        .map_err(|e| format!("Failed to write to destination file '{}': {}", dest_path.display(), e))?;
+    let mut file = fs::File::create(&dest_path).map_err(|e| {
+        format!(
+            "Failed to create destination file '{}': {}",
+            dest_path.display(),
+            e
+        )
+    })?;
+
+    file.write_all(&data).map_err(|e| {
+        format!(
+            "Failed to write to destination file '{}': {}",
+            dest_path.display(),
+            e
+        )
+    })?;
 
     Ok(dest_path.to_string_lossy().to_string())
 }
@@ -264,13 +543,11 @@ fn list_existing_buttons(vcp_resources_folder: String) -> Result<Vec<String>, St
         .map_err(|e| format!("Failed to read Buttons directory: {}", e))?;
 
     let mut buttons = Vec::new();
-    for entry in entries {
-        if let Ok(entry) = entry {
-            if let Ok(file_type) = entry.file_type() {
-                if file_type.is_dir() {
-                    if let Some(name) = entry.file_name().to_str() {
-                        buttons.push(name.to_string());
-                    }
+    for entry in entries.flatten() {
+        if let Ok(file_type) = entry.file_type() {
+            if file_type.is_dir() {
+                if let Some(name) = entry.file_name().to_str() {
+                    buttons.push(name.to_string());
                 }
             }
         }
@@ -281,8 +558,27 @@ fn list_existing_buttons(vcp_resources_folder: String) -> Result<Vec<String>, St
 }
 
 #[tauri::command]
-fn serialize_vcp_document(doc: VcpDocument) -> Result<String, String> {
-    Ok(backend::parser::serialize_vcp(&doc))
+fn serialize_vcp_document(
+    doc: VcpDocument,
+    options: Option<SerializeOptions>,
+) -> Result<String, String> {
+    Ok(match options {
+        Some(options) => serialize_vcp_with(&doc, &options),
+        None => serialize_vcp(&doc),
+    })
+}
+
+#[tauri::command]
+fn get_project_settings(vcp_resources_folder: String) -> Result<ProjectSettings, String> {
+    load_project_settings(Path::new(&vcp_resources_folder))
+}
+
+#[tauri::command]
+fn set_project_settings(
+    vcp_resources_folder: String,
+    settings: ProjectSettings,
+) -> Result<(), String> {
+    save_project_settings(Path::new(&vcp_resources_folder), &settings)
 }
 
 #[tauri::command]
@@ -316,6 +612,13 @@ pub fn run() {
         .setup(|app| {
             // Create menu items
             let new_item = MenuItem::with_id(app, "new", "New", true, Some("CmdOrCtrl+N"))?;
+            let new_window_item = MenuItem::with_id(
+                app,
+                "new_window",
+                "New Window",
+                true,
+                Some("CmdOrCtrl+Shift+N"),
+            )?;
             let open_item = MenuItem::with_id(app, "open", "Open...", true, Some("CmdOrCtrl+O"))?;
             let save_item = MenuItem::with_id(app, "save", "Save", true, Some("CmdOrCtrl+S"))?;
             let save_as_item = MenuItem::with_id(
@@ -327,6 +630,17 @@ pub fn run() {
             )?;
             let export_item =
                 MenuItem::with_id(app, "export_cnc", "Export to CNC...", true, None::<&str>)?;
+            let import_item =
+                MenuItem::with_id(app, "import_cnc", "Import from CNC...", true, None::<&str>)?;
+            let deploy_item =
+                MenuItem::with_id(app, "deploy_cnc", "Deploy to CNC...", true, None::<&str>)?;
+            let rollback_item = MenuItem::with_id(
+                app,
+                "rollback_deploy",
+                "Roll Back Deploy...",
+                true,
+                None::<&str>,
+            )?;
             let print_item =
                 MenuItem::with_id(app, "print", "Print...", true, Some("CmdOrCtrl+P"))?;
             // Use platform-specific labels for Settings
@@ -342,20 +656,23 @@ pub fn run() {
             let quit_item =
                 MenuItem::with_id(app, "quit", "Quit VCP Editor", true, Some("CmdOrCtrl+Q"))?;
             #[cfg(not(target_os = "macos"))]
-            let quit_item =
-                MenuItem::with_id(app, "quit", "Exit", true, Some("CmdOrCtrl+Q"))?;
+            let quit_item = MenuItem::with_id(app, "quit", "Exit", true, Some("CmdOrCtrl+Q"))?;
 
             // Use platform-specific labels for About
             #[cfg(target_os = "macos")]
             let about_item =
                 MenuItem::with_id(app, "about", "About VCP Editor", true, None::<&str>)?;
             #[cfg(not(target_os = "macos"))]
-            let _about_item =
-                MenuItem::with_id(app, "about", "About", true, None::<&str>)?;
+            let _about_item = MenuItem::with_id(app, "about", "About", true, None::<&str>)?;
 
             // View menu items
-            let refresh_images_item =
-                MenuItem::with_id(app, "refresh_images", "Refresh Images", true, Some("CmdOrCtrl+R"))?;
+            let refresh_images_item = MenuItem::with_id(
+                app,
+                "refresh_images",
+                "Refresh Images",
+                true,
+                Some("CmdOrCtrl+R"),
+            )?;
             let zoom_in_item =
                 MenuItem::with_id(app, "zoom_in", "Zoom In", true, Some("CmdOrCtrl+="))?;
             let zoom_out_item =
@@ -419,12 +736,16 @@ pub fn run() {
                     true,
                     &[
                         &new_item,
+                        &new_window_item,
                         &open_item,
                         &PredefinedMenuItem::separator(app)?,
                         &save_item,
                         &save_as_item,
                         &PredefinedMenuItem::separator(app)?,
                         &export_item,
+                        &deploy_item,
+                        &rollback_item,
+                        &import_item,
                         &PredefinedMenuItem::separator(app)?,
                         &print_item,
                     ],
@@ -486,12 +807,16 @@ pub fn run() {
                     true,
                     &[
                         &new_item,
+                        &new_window_item,
                         &open_item,
                         &PredefinedMenuItem::separator(app)?,
                         &save_item,
                         &save_as_item,
                         &PredefinedMenuItem::separator(app)?,
                         &export_item,
+                        &deploy_item,
+                        &rollback_item,
+                        &import_item,
                         &PredefinedMenuItem::separator(app)?,
                         &print_item,
                         &PredefinedMenuItem::separator(app)?,
@@ -546,52 +871,32 @@ pub fn run() {
 
             // Handle menu events
             app.on_menu_event(|app, event| match event.id().as_ref() {
-                "new" => {
-                    let _ = app.emit("menu-new", ());
-                }
-                "open" => {
-                    let _ = app.emit("menu-open", ());
-                }
-                "save" => {
-                    let _ = app.emit("menu-save", ());
-                }
-                "save_as" => {
-                    let _ = app.emit("menu-save-as", ());
-                }
-                "export_cnc" => {
-                    let _ = app.emit("menu-export-cnc", ());
-                }
-                "print" => {
-                    let _ = app.emit("menu-print", ());
-                }
-                "settings" => {
-                    let _ = app.emit("menu-settings", ());
-                }
-                "refresh_images" => {
-                    let _ = app.emit("menu-refresh-images", ());
-                }
-                "zoom_in" => {
-                    let _ = app.emit("menu-zoom-in", ());
-                }
-                "zoom_out" => {
-                    let _ = app.emit("menu-zoom-out", ());
-                }
-                "toggle_grid" => {
-                    let _ = app.emit("menu-toggle-grid", ());
-                }
+                "new" => emit_to_focused(app, "menu-new"),
+                "new_window" => emit_to_focused(app, "menu-new-window"),
+                "open" => emit_to_focused(app, "menu-open"),
+                "save" => emit_to_focused(app, "menu-save"),
+                "save_as" => emit_to_focused(app, "menu-save-as"),
+                "export_cnc" => emit_to_focused(app, "menu-export-cnc"),
+                "import_cnc" => emit_to_focused(app, "menu-import-cnc"),
+                "deploy_cnc" => emit_to_focused(app, "menu-deploy-cnc"),
+                "rollback_deploy" => emit_to_focused(app, "menu-rollback-deploy"),
+                "print" => emit_to_focused(app, "menu-print"),
+                "settings" => emit_to_focused(app, "menu-settings"),
+                "refresh_images" => emit_to_focused(app, "menu-refresh-images"),
+                "zoom_in" => emit_to_focused(app, "menu-zoom-in"),
+                "zoom_out" => emit_to_focused(app, "menu-zoom-out"),
+                "toggle_grid" => emit_to_focused(app, "menu-toggle-grid"),
+                "about" => emit_to_focused(app, "menu-about"),
+                // Every window gets the chance to save its changes
                 "quit" => {
                     let _ = app.emit("menu-quit", ());
                 }
-                "about" => {
-                    let _ = app.emit("menu-about", ());
-                }
                 _ => {}
             });
 
             // Open DevTools in development mode
             #[cfg(debug_assertions)]
             {
-                use tauri::Manager;
                 if let Some(window) = app.get_webview_window("main") {
                     window.open_devtools();
                 }
@@ -599,26 +904,56 @@ pub fn run() {
 
             Ok(())
         })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                let state = window.state::<AppState>();
+                state.documents.lock().unwrap().close_window(window.label());
+            }
+        })
         .manage(AppState {
-            current_document: Mutex::new(None),
-            current_file_path: Mutex::new(None),
+            documents: Mutex::new(DocumentRegistry::default()),
         })
         .invoke_handler(tauri::generate_handler![
             new_document,
             open_file,
             save_file_command,
+            validate_document,
+            check_project_assets,
+            analyze_skin_events,
+            load_plc_source,
+            cross_check_plc,
+            generate_plc_source_stub,
+            get_symbol_catalog,
+            refresh_symbol_catalog,
+            export_project_file,
+            import_project_file,
             update_document,
-            get_current_document,
+            get_document,
+            get_document_info,
+            list_documents,
+            close_document,
+            open_document_window,
+            apply_edits,
+            undo_edit,
+            redo_edit,
+            get_edit_history,
             print_window,
             create_button_folder,
             ensure_vcp_folder_structure,
             export_to_cnc,
-            save_button_xml,
-            load_button_xml,
+            verify_export_command,
+            import_from_cnc,
+            deploy_to_cnc,
+            list_deploy_backups,
+            rollback_deploy_command,
+            save_button_definition,
+            load_button_definition,
             copy_file_to_button_folder,
             list_existing_buttons,
             check_svg_file,
-            serialize_vcp_document
+            serialize_vcp_document,
+            get_project_settings,
+            set_project_settings
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
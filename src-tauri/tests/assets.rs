@@ -0,0 +1,119 @@
+// Integration tests for project asset checks
+mod common;
+
+use common::*;
+use std::fs;
+use std::path::Path;
+use vcp_editor_lib::backend::assets::{
+    assets_error, check_export_assets, check_project_assets, check_skin_assets,
+};
+use vcp_editor_lib::backend::button::save_button;
+use vcp_editor_lib::backend::models::{AssetIssueKind, PlcOutput, VcpButton};
+use vcp_editor_lib::backend::parser::parse_vcp;
+
+const SKIN: &str = "<vcp_skin>\n    <image>\n        <row_start>1</row_start>\n        <column_start>1</column_start>\n        <row_span>1</row_span>\n        <column_span>2</column_span>\n        <path>images/logo.svg</path>\n    </image>\n    <button row=\"2\" column=\"1\">spin_cw</button>\n</vcp_skin>\n";
+
+fn write_button(root: &Path, name: &str, button: VcpButton) {
+    let folder = root.join("Buttons").join(name);
+    fs::create_dir_all(&folder).expect("Failed to create button folder");
+    let button = VcpButton {
+        name: name.to_string(),
+        ..button
+    };
+    save_button(&folder, &button).expect("Failed to save button");
+}
+
+/// A project where everything the skin refers to exists.
+fn complete_project(root: &Path) {
+    fs::write(root.join("skins/main.vcp"), SKIN).expect("Failed to write skin");
+    create_mock_image(&root.join("images"), "logo.svg");
+    write_button(
+        root,
+        "spin_cw",
+        VcpButton {
+            default_image: Some("spin_cw.svg".to_string()),
+            ..Default::default()
+        },
+    );
+    create_mock_image(&root.join("Buttons/spin_cw"), "spin_cw.svg");
+}
+
+#[test]
+fn test_complete_project_has_no_issues() {
+    let temp_base = setup_test_vcp_folder();
+    complete_project(temp_base.path());
+
+    assert_eq!(check_project_assets(temp_base.path()).unwrap(), vec![]);
+}
+
+#[test]
+fn test_missing_and_empty_assets_are_reported() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    complete_project(root);
+    fs::remove_file(root.join("images/logo.svg")).unwrap();
+    fs::write(root.join("Buttons/spin_cw/spin_cw.svg"), "").unwrap();
+
+    let issues = check_project_assets(root).unwrap();
+
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].kind, AssetIssueKind::Missing);
+    assert_eq!(issues[0].path, "images/logo.svg");
+    assert_eq!(issues[0].referenced_by, "skins/main.vcp");
+    assert_eq!(issues[1].kind, AssetIssueKind::Empty);
+    assert_eq!(issues[1].path, "Buttons/spin_cw/spin_cw.svg");
+    assert_eq!(issues[1].referenced_by, "Buttons/spin_cw/spin_cw.xml");
+}
+
+#[test]
+fn test_button_images_and_definitions_are_checked() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    complete_project(root);
+    write_button(
+        root,
+        "unused",
+        VcpButton {
+            plc_output: Some(PlcOutput {
+                number: 1057,
+                color_on: "#EC1C24".to_string(),
+                color_off: "#81151C".to_string(),
+                image_on: Some("led_on.svg".to_string()),
+                image_off: None,
+            }),
+            ..Default::default()
+        },
+    );
+    fs::create_dir_all(root.join("Buttons/broken")).unwrap();
+    fs::write(root.join("Buttons/broken/broken.xml"), "<vcp_button>").unwrap();
+
+    let issues = check_project_assets(root).unwrap();
+    let found: Vec<(AssetIssueKind, &str)> = issues
+        .iter()
+        .map(|issue| (issue.kind, issue.path.as_str()))
+        .collect();
+
+    assert_eq!(
+        found,
+        vec![
+            (AssetIssueKind::Unreadable, "Buttons/broken/broken.xml"),
+            (AssetIssueKind::Missing, "Buttons/unused/led_on.svg"),
+        ]
+    );
+}
+
+#[test]
+fn test_unsaved_skin_is_checked_before_export() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    complete_project(root);
+    let doc = parse_vcp(&SKIN.replace("spin_cw", "spin_ccw")).expect("Failed to parse skin");
+
+    let issues = check_skin_assets(root, "skins/new.vcp", &doc);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].path, "Buttons/spin_ccw/spin_ccw.xml");
+
+    let issues = check_export_assets(root, &[("skins/new.vcp".to_string(), &doc)]).unwrap();
+    assert_eq!(issues.len(), 1);
+    assert!(assets_error(&issues).starts_with("Project has 1 missing or unusable asset(s)"));
+}
@@ -0,0 +1,267 @@
+// Integration tests for button definition files
+mod common;
+
+use common::*;
+use vcp_editor_lib::backend::button::{
+    button_folder, load_button, parse_button_xml, save_button, serialize_button_xml,
+};
+use vcp_editor_lib::backend::models::{ButtonExtra, ButtonRun, PlcInput, PlcOutput, VcpButton};
+use vcp_editor_lib::backend::parser::load_file;
+
+#[test]
+fn test_parse_full_button_definition() {
+    let xml = r#"<vcp_button>
+  <skin_event_num>12</skin_event_num>
+  <default_image>coolant.svg</default_image>
+  <on_click_swap>coolant_pressed.svg</on_click_swap>
+  <run>
+    <macro>mfunc8.mac</macro>
+  </run>
+  <plc_output>
+    <number>7</number>
+    <color_on>#00FF00</color_on>
+    <color_off>#003300</color_off>
+    <image_on>coolant_on.svg</image_on>
+  </plc_output>
+</vcp_button>"#;
+
+    let button = parse_button_xml(xml).expect("Failed to parse button");
+
+    assert_eq!(button.skin_event_num, Some(12));
+    assert_eq!(button.default_image.as_deref(), Some("coolant.svg"));
+    assert_eq!(button.on_click_swap.as_deref(), Some("coolant_pressed.svg"));
+    assert_eq!(button.run, Some(ButtonRun::Macro("mfunc8.mac".to_string())));
+    let output = button.plc_output.expect("Missing plc_output");
+    assert_eq!(output.number, 7);
+    assert_eq!(output.color_on, "#00FF00");
+    assert_eq!(output.image_on.as_deref(), Some("coolant_on.svg"));
+    assert_eq!(output.image_off, None);
+    assert!(button.plc_input.is_none());
+    assert!(button.extra.is_empty());
+}
+
+#[test]
+fn test_serialize_button_definition() {
+    let button = VcpButton {
+        name: "work_light".to_string(),
+        skin_event_num: Some(3),
+        run: Some(ButtonRun::Line("G0 X0 Y0".to_string())),
+        plc_input: Some(PlcInput {
+            number: 14,
+            image_active: Some("light_on.svg".to_string()),
+            image_inactive: None,
+        }),
+        ..Default::default()
+    };
+
+    let xml = serialize_button_xml(&button);
+
+    assert_eq!(
+        xml,
+        "<vcp_button>\n  <skin_event_num>3</skin_event_num>\n  <run>\n    <line>G0 X0 Y0</line>\n  </run>\n  <plc_input>\n    <number>14</number>\n    <image_active>light_on.svg</image_active>\n  </plc_input>\n</vcp_button>"
+    );
+}
+
+#[test]
+fn test_button_definition_round_trip_escapes_text() {
+    let button = VcpButton {
+        name: "probe".to_string(),
+        default_image: Some("probe & touch.svg".to_string()),
+        app: Some(r#"C:\cncm\tools\<probe>.exe"#.to_string()),
+        plc_output: Some(PlcOutput {
+            number: 2,
+            color_on: "#EC1C24".to_string(),
+            color_off: "#81151C".to_string(),
+            image_on: None,
+            image_off: Some("Sondé aus.svg".to_string()),
+        }),
+        ..Default::default()
+    };
+
+    let xml = serialize_button_xml(&button);
+    assert!(
+        xml.contains("<app>C:\\cncm\\tools\\&lt;probe&gt;.exe</app>"),
+        "{}",
+        xml
+    );
+
+    let mut reparsed = parse_button_xml(&xml).expect("Failed to reparse button");
+    reparsed.name = button.name.clone();
+    assert_eq!(reparsed, button);
+}
+
+#[test]
+fn test_unknown_elements_and_comments_are_kept() {
+    let xml = "<vcp_button>\n  <!-- vendor note -->\n  <skin_event_num>4</skin_event_num>\n  <tooltip lang=\"en\">Flood</tooltip>\n</vcp_button>";
+
+    let button = parse_button_xml(xml).expect("Failed to parse button");
+
+    assert_eq!(
+        button.extra,
+        vec![
+            ButtonExtra {
+                after: None,
+                markup: "<!-- vendor note -->".to_string(),
+            },
+            ButtonExtra {
+                after: Some("skin_event_num".to_string()),
+                markup: "<tooltip lang=\"en\">Flood</tooltip>".to_string(),
+            },
+        ]
+    );
+    // Both stay where they were
+    assert_eq!(serialize_button_xml(&button), xml);
+    let reparsed = parse_button_xml(&serialize_button_xml(&button)).expect("Failed to reparse");
+    assert_eq!(reparsed, button);
+
+    // Even when the element they follow is gone
+    let button = VcpButton {
+        skin_event_num: None,
+        default_image: Some("flood.svg".to_string()),
+        ..button
+    };
+    assert_eq!(
+        serialize_button_xml(&button),
+        "<vcp_button>\n  <!-- vendor note -->\n  <tooltip lang=\"en\">Flood</tooltip>\n  <default_image>flood.svg</default_image>\n</vcp_button>"
+    );
+}
+
+#[test]
+fn test_invalid_button_definition_reports_position() {
+    let xml =
+        "<vcp_button>\n  <plc_output>\n    <number>x7</number>\n  </plc_output>\n</vcp_button>";
+
+    let err = parse_button_xml(xml).expect_err("Invalid number should fail");
+
+    assert!(err.contains("line 3"), "Unexpected error: {}", err);
+    assert!(err.contains("'x7'"), "Unexpected error: {}", err);
+
+    let err = parse_button_xml("<vcp_button><plc_input></plc_input></vcp_button>")
+        .expect_err("Missing number should fail");
+    assert!(
+        err.contains("Missing <number>"),
+        "Unexpected error: {}",
+        err
+    );
+}
+
+#[test]
+fn test_save_and_load_button_definition() {
+    let temp_dir = setup_test_vcp_folder();
+    let base_path = temp_dir.path();
+    let button_dir = create_mock_button(base_path, "spindle");
+    let mut button = load_button(base_path, "spindle").expect("Failed to load button");
+    assert_eq!(button.name, "spindle");
+
+    button.default_image = Some("spindle_cw.svg".to_string());
+    save_button(&button_dir, &button).expect("Failed to save button");
+    let reloaded = load_button(base_path, "spindle").expect("Failed to reload button");
+
+    assert_eq!(reloaded, button);
+}
+
+#[test]
+fn test_button_names_stay_inside_their_folder() {
+    let temp_dir = setup_test_vcp_folder();
+    let base_path = temp_dir.path();
+    let button_dir = create_mock_button(base_path, "spindle");
+
+    for name in ["..", "../spindle", r"spindle\..\..\x", "C:x"] {
+        let error = button_folder(base_path, name).unwrap_err();
+        assert!(error.starts_with("Invalid button name"), "{}", error);
+        assert!(load_button(base_path, name).is_err());
+        let button = VcpButton {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        assert!(save_button(&button_dir, &button).is_err(), "{}", name);
+    }
+    assert_eq!(
+        button_folder(base_path, "spindle").unwrap(),
+        base_path.join("Buttons").join("spindle")
+    );
+}
+
+#[test]
+fn test_load_file_reads_default_image_from_button_definition() {
+    let temp_dir = setup_test_vcp_folder();
+    let base_path = temp_dir.path();
+    let button_dir = create_mock_button(base_path, "cycle_start");
+    let button = VcpButton {
+        name: "cycle_start".to_string(),
+        default_image: Some("start_green.svg".to_string()),
+        ..Default::default()
+    };
+    save_button(&button_dir, &button).expect("Failed to save button");
+    let skin_path = base_path.join("skins").join("test.vcp");
+    std::fs::write(
+        &skin_path,
+        "<vcp_skin>\n    <button row=\"1\" column=\"1\">cycle_start</button>\n</vcp_skin>\n",
+    )
+    .expect("Failed to write skin");
+
+    let loaded = load_file(skin_path.to_str().unwrap()).expect("Failed to load skin");
+
+    let cycle_start = loaded
+        .document
+        .buttons
+        .iter()
+        .find(|b| b.name == "cycle_start")
+        .expect("Missing cycle_start button");
+    assert_eq!(
+        cycle_start.default_image.as_deref(),
+        Some("start_green.svg")
+    );
+}
+
+#[test]
+fn test_load_file_attaches_definitions_and_button_errors() {
+    let temp_dir = setup_test_vcp_folder();
+    let base_path = temp_dir.path();
+    let coolant_dir = create_mock_button(base_path, "coolant");
+    let broken_dir = create_mock_button(base_path, "broken");
+    let coolant = VcpButton {
+        name: "coolant".to_string(),
+        skin_event_num: Some(12),
+        default_image: Some("coolant.svg".to_string()),
+        run: Some(ButtonRun::Macro("mfunc8.mac".to_string())),
+        plc_output: Some(PlcOutput {
+            number: 7,
+            color_on: "#EC1C24".to_string(),
+            color_off: "#81151C".to_string(),
+            image_on: Some("coolant_on.svg".to_string()),
+            image_off: None,
+        }),
+        ..Default::default()
+    };
+    save_button(&coolant_dir, &coolant).expect("Failed to save button");
+    std::fs::write(
+        broken_dir.join("broken.xml"),
+        "<vcp_button><skin_event_num>1</vcp_button>",
+    )
+    .expect("Failed to write button XML");
+    let skin_path = base_path.join("skins").join("test.vcp");
+    std::fs::write(
+        &skin_path,
+        "<vcp_skin>\n    <button row=\"1\" column=\"1\">coolant</button>\n    <button row=\"1\" column=\"2\">broken</button>\n    <button row=\"1\" column=\"3\">legacy</button>\n</vcp_skin>\n",
+    )
+    .expect("Failed to write skin");
+
+    let loaded = load_file(skin_path.to_str().unwrap()).expect("Failed to load skin");
+
+    let buttons = &loaded.document.buttons;
+    assert_eq!(buttons[0].definition.as_ref(), Some(&coolant));
+    assert_eq!(
+        buttons[0].definition.as_ref().unwrap().referenced_images(),
+        vec!["coolant.svg", "coolant_on.svg"]
+    );
+    assert!(buttons[1].definition.is_none());
+    assert!(buttons[2].definition.is_none());
+    assert_eq!(loaded.button_errors.len(), 1);
+    assert_eq!(loaded.button_errors[0].button, "broken");
+    assert!(
+        loaded.button_errors[0].message.contains("line 1"),
+        "Unexpected error: {}",
+        loaded.button_errors[0].message
+    );
+}
@@ -0,0 +1,108 @@
+// Integration tests for per-project symbol catalogs
+mod common;
+
+use common::*;
+use std::fs;
+use vcp_editor_lib::backend::catalog::{
+    builtin_catalog, catalog_path, label_for, load_project_catalog, project_catalog,
+    refresh_catalog, save_project_catalog,
+};
+use vcp_editor_lib::backend::models::{CatalogEntry, SymbolCatalog};
+
+fn entry(number: i32, name: &str) -> CatalogEntry {
+    CatalogEntry {
+        number,
+        name: name.to_string(),
+        label: String::new(),
+    }
+}
+
+#[test]
+fn test_labels_from_symbol_names() {
+    assert_eq!(label_for("SkinCoolFlood_M"), "Cool Flood");
+    assert_eq!(label_for("SkinATCIndexPlus_M"), "ATC Index Plus");
+    assert_eq!(label_for("SkinAux12_M"), "Aux 12");
+    assert_eq!(label_for("SkinR7C2_M"), "R7C2");
+    assert_eq!(label_for("SpinOverPlusLED"), "Spin Over Plus LED");
+    assert_eq!(label_for("FeedOverLT100"), "Feed Over LT100");
+}
+
+#[test]
+fn test_project_without_catalog_gets_builtin_symbols() {
+    let temp_base = setup_test_vcp_folder();
+
+    let catalog = project_catalog(temp_base.path()).expect("Failed to load catalog");
+
+    assert_eq!(catalog, builtin_catalog());
+    assert_eq!(catalog.skin_events.len(), 97);
+    assert_eq!(catalog.outputs[0].name, "SpinOverPlusLED");
+    assert_eq!(catalog.outputs[0].number, 1057);
+}
+
+#[test]
+fn test_project_entries_add_to_and_override_defaults() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    let own = SymbolCatalog {
+        skin_events: vec![entry(210, "SkinVacuumPump_M"), entry(3, "SkinDoorLock_M")],
+        ..Default::default()
+    };
+    save_project_catalog(root, &own).expect("Failed to save catalog");
+
+    let catalog = project_catalog(root).expect("Failed to load catalog");
+
+    assert_eq!(catalog.skin_events.len(), 98);
+    assert_eq!(catalog.skin_events[2].name, "SkinDoorLock_M");
+    assert_eq!(catalog.skin_events[2].label, "Door Lock");
+    let last = catalog.skin_events.last().unwrap();
+    assert_eq!((last.number, last.name.as_str()), (210, "SkinVacuumPump_M"));
+    assert_eq!(catalog.inputs, builtin_catalog().inputs);
+}
+
+#[test]
+fn test_refresh_from_plc_source_reports_diff() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    let plc_path = root.join("mill.src");
+    fs::write(
+        &plc_path,
+        "SkinSpinOverPlus_M IS SV_SKIN_EVENT_1\r\n\
+         SkinCoolant_M IS SV_SKIN_EVENT_22\r\n\
+         SkinVacuumPump_M IS SV_SKIN_EVENT_210\r\n\
+         ;SkinOld_M IS SV_SKIN_EVENT_211\r\n\
+         SpinOverPlusLED IS OUT1057\r\n\
+         SpinOverPlusKey IS INP1057\r\n",
+    )
+    .unwrap();
+
+    let preview = refresh_catalog(root, &plc_path, false).expect("Failed to preview refresh");
+    assert!(!catalog_path(root).exists());
+
+    let diff = refresh_catalog(root, &plc_path, true).expect("Failed to refresh");
+    assert_eq!(diff, preview);
+    assert_eq!(diff.skin_events.added.len(), 1);
+    assert_eq!(diff.skin_events.added[0].name, "SkinVacuumPump_M");
+    assert_eq!(diff.skin_events.renamed.len(), 1);
+    assert_eq!(diff.skin_events.renamed[0].0.name, "SkinCoolFlood_M");
+    assert_eq!(diff.skin_events.renamed[0].1.name, "SkinCoolant_M");
+    assert_eq!(diff.skin_events.removed.len(), 97 - 2);
+    assert_eq!(diff.outputs.removed.len(), 49);
+    assert!(diff.inputs.added.is_empty());
+
+    let saved = load_project_catalog(root).expect("Failed to read catalog");
+    assert!(saved.replaces_defaults);
+    let catalog = project_catalog(root).expect("Failed to load catalog");
+    let numbers: Vec<i32> = catalog.skin_events.iter().map(|e| e.number).collect();
+    assert_eq!(numbers, vec![1, 22, 210]);
+}
+
+#[test]
+fn test_refresh_rejects_source_without_definitions() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    let plc_path = root.join("empty.src");
+    fs::write(&plc_path, "; nothing here\r\n").unwrap();
+
+    assert!(refresh_catalog(root, &plc_path, true).is_err());
+    assert!(!catalog_path(root).exists());
+}
@@ -0,0 +1,186 @@
+// Integration tests for the vcp-cli binary
+mod common;
+
+use common::*;
+use serde_json::Value;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+const SKIN: &str = "<vcp_skin>\n  <column_count>4</column_count>\n  <row_count>3</row_count>\n  <image>\n    <row_start>1</row_start>\n    <column_start>1</column_start>\n    <row_span>1</row_span>\n    <column_span>2</column_span>\n    <path>images/logo.svg</path>\n  </image>\n  <button row=\"3\" column=\"1\">spin_cw</button>\n  <button row=\"2\" column=\"2\">spin_ccw</button>\n</vcp_skin>\n";
+
+/// Runs the CLI, returning its exit code and JSON report.
+fn run(args: &[&str]) -> (i32, Value) {
+    let output = Command::new(env!("CARGO_BIN_EXE_vcp-cli"))
+        .args(args)
+        .output()
+        .expect("Failed to run vcp-cli");
+    let report = serde_json::from_slice(&output.stdout).expect("Output is not JSON");
+    (output.status.code().expect("Killed by a signal"), report)
+}
+
+fn write_skin(root: &Path, content: &str) -> String {
+    let path = root.join("skins/main.vcp");
+    fs::write(&path, content).expect("Failed to write skin");
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn test_info_reports_grid_and_counts() {
+    let temp = setup_test_vcp_folder();
+    let skin = write_skin(temp.path(), SKIN);
+
+    let (code, report) = run(&["info", &skin]);
+
+    assert_eq!(code, 0);
+    assert_eq!(report["columns"], 4);
+    assert_eq!(report["rows"], 3);
+    assert_eq!(report["images"], 1);
+    assert_eq!(report["buttons"], 2);
+    assert_eq!(report["borders"], 0);
+}
+
+#[test]
+fn test_validate_fails_on_errors() {
+    let temp = setup_test_vcp_folder();
+    let skin = write_skin(temp.path(), SKIN);
+    let (code, report) = run(&["validate", &skin]);
+    assert_eq!(code, 0);
+    assert_eq!(report["valid"], true);
+
+    let overlapping = SKIN.replace("row=\"2\" column=\"2\"", "row=\"3\" column=\"1\"");
+    let skin = write_skin(temp.path(), &overlapping);
+    let (code, report) = run(&["validate", &skin]);
+    assert_eq!(code, 1);
+    assert_eq!(report["valid"], false);
+    assert_eq!(report["issues"][0]["rule"], "button-overlap");
+}
+
+#[test]
+fn test_fmt_check_then_rewrite() {
+    let temp = setup_test_vcp_folder();
+    let skin = write_skin(temp.path(), SKIN);
+    fs::write(
+        temp.path().join(".vcp-editor.json"),
+        r#"{"output": {"element_order": "sorted"}}"#,
+    )
+    .expect("Failed to write settings");
+
+    let (code, report) = run(&["fmt", "--check", &skin]);
+    assert_eq!(code, 1);
+    assert_eq!(report["files"][0]["changed"], true);
+    assert_eq!(fs::read_to_string(&skin).unwrap(), SKIN);
+
+    let (code, _) = run(&["fmt", &skin]);
+    assert_eq!(code, 0);
+    let formatted = fs::read_to_string(&skin).unwrap();
+    assert!(formatted.find("spin_ccw").unwrap() < formatted.find("spin_cw").unwrap());
+
+    let (code, report) = run(&["fmt", "--check", &skin]);
+    assert_eq!(code, 0);
+    assert_eq!(report["files"][0]["changed"], false);
+}
+
+#[test]
+fn test_fmt_applies_line_endings_and_indentation() {
+    let temp = setup_test_vcp_folder();
+    // CNC12's own style, in a project set up for LF and two-space indents
+    let crlf = SKIN.replace("\n  ", "\n    ").replace('\n', "\r\n");
+    let crlf = crlf.replace("<column_count>", "<!-- grid -->\r\n    <column_count>");
+    let skin = write_skin(temp.path(), &crlf);
+    fs::write(
+        temp.path().join(".vcp-editor.json"),
+        r#"{"output": {"line_ending": "lf", "indent": "  "}}"#,
+    )
+    .expect("Failed to write settings");
+
+    let (code, report) = run(&["fmt", "--check", &skin]);
+    assert_eq!(code, 1);
+    assert_eq!(report["files"][0]["changed"], true);
+    assert_eq!(fs::read_to_string(&skin).unwrap(), crlf);
+
+    // Keeping the layout still changes the line endings, and keeps the comment
+    let (code, _) = run(&["fmt", "--keep-layout", &skin]);
+    assert_eq!(code, 0);
+    let kept = fs::read_to_string(&skin).unwrap();
+    assert!(!kept.contains('\r'));
+    assert!(
+        kept.contains("<!-- grid -->\n    <column_count>"),
+        "{}",
+        kept
+    );
+
+    let (code, _) = run(&["fmt", &skin]);
+    assert_eq!(code, 0);
+    let formatted = fs::read_to_string(&skin).unwrap();
+    assert!(
+        formatted.starts_with("<vcp_skin>\n  <background>"),
+        "{}",
+        formatted
+    );
+    assert!(!formatted.contains("\n    <image>"), "{}", formatted);
+    let (code, _) = run(&["fmt", "--check", &skin]);
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn test_check_assets_and_usage_errors() {
+    let temp = setup_test_vcp_folder();
+    let skin = write_skin(temp.path(), SKIN);
+    create_mock_button(temp.path(), "spin_cw");
+    create_mock_button(temp.path(), "spin_ccw");
+
+    let (code, report) = run(&["check-assets", &skin]);
+    assert_eq!(code, 1);
+    assert_eq!(report["issues"][0]["path"], "images/logo.svg");
+
+    create_mock_image(&temp.path().join("images"), "logo.svg");
+    let (code, report) = run(&["check-assets", &temp.path().to_string_lossy()]);
+    assert_eq!(code, 0, "{}", report);
+
+    let (code, report) = run(&["export", &skin]);
+    assert_eq!(code, 2);
+    assert!(report["error"].as_str().unwrap().contains("--target"));
+}
+
+#[test]
+fn test_export_writes_cnc_image_paths() {
+    let temp = setup_test_vcp_folder();
+    let skin = write_skin(temp.path(), SKIN);
+    create_mock_image(&temp.path().join("images"), "logo.svg");
+    create_mock_button(temp.path(), "spin_cw");
+    create_mock_button(temp.path(), "spin_ccw");
+    let out = TempDir::new().expect("Failed to create temp directory");
+    let out_path = out.path().to_string_lossy().to_string();
+
+    let exported_skin = |report: &Value| {
+        let zip = fs::File::open(report["zip"].as_str().unwrap()).unwrap();
+        let mut archive = zip::ZipArchive::new(zip).unwrap();
+        let mut content = String::new();
+        archive
+            .by_name("vcp/skins/main.vcp")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    };
+
+    let target = format!("{}=main.vcp", skin);
+    let (code, report) = run(&["export", &target, "--target", &out_path]);
+    assert_eq!(code, 0, "{}", report);
+    assert!(Path::new(report["zip"].as_str().unwrap()).starts_with(out.path()));
+    assert!(exported_skin(&report).contains("<path>C:/cncm/resources/vcp/images/logo.svg</path>"));
+
+    let (code, report) = run(&[
+        "export",
+        &target,
+        "--target",
+        &out_path,
+        "--cnc-base",
+        r"D:\cnc\vcp",
+    ]);
+    assert_eq!(code, 0, "{}", report);
+    assert!(exported_skin(&report).contains("<path>D:/cnc/vcp/images/logo.svg</path>"));
+}
@@ -1,4 +1,7 @@
 // Test utilities for VCP Editor integration tests
+// Not every test binary uses every helper
+#![allow(dead_code)]
+
 use std::fs;
 use std::path::{Path, PathBuf};
 use tempfile::TempDir;
@@ -0,0 +1,180 @@
+// Integration tests for deploying exports to a CNC's vcp folder and rolling back
+mod common;
+
+use common::*;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tempfile::TempDir;
+use vcp_editor_lib::backend::deploy::{
+    deploy_export, deploy_project, list_backups, rollback_deploy,
+};
+use vcp_editor_lib::backend::export::{export_project, ExportEntry};
+use vcp_editor_lib::backend::manifest::verify_export;
+use vcp_editor_lib::backend::models::SkinTarget;
+use vcp_editor_lib::backend::parser::parse_vcp;
+
+const SKIN: &str =
+    "<vcp_skin>\n    <column_count>4</column_count>\n    <row_count>3</row_count>\n</vcp_skin>\n";
+
+fn entries() -> [ExportEntry; 1] {
+    [ExportEntry {
+        document: parse_vcp(SKIN).unwrap(),
+        target: Some(SkinTarget::File("panel.vcp".to_string())),
+        source: None,
+    }]
+}
+
+/// Names of the backups of `target`, newest first.
+fn backup_names(target: &Path) -> Vec<String> {
+    list_backups(target)
+        .unwrap()
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+        .collect()
+}
+
+#[test]
+fn test_deploy_backs_up_the_old_folder_and_rolls_back() {
+    let project = setup_test_vcp_folder();
+    create_mock_image(&project.path().join("images"), "logo.svg");
+    let resources = TempDir::new().expect("Failed to create temp directory");
+    let target = resources.path().join("vcp");
+    fs::create_dir_all(target.join("skins")).unwrap();
+    fs::write(target.join("skins/old.vcp"), SKIN).unwrap();
+
+    let report = deploy_project(
+        project.path(),
+        "C:/cncm/resources/vcp",
+        &target,
+        &entries(),
+        None,
+    )
+    .unwrap();
+
+    assert!(verify_export(&target).unwrap().issues.is_empty());
+    assert_file_exists(&target.join("skins/panel.vcp"));
+    assert_file_exists(&target.join("images/logo.svg"));
+    assert!(!target.join("skins/old.vcp").exists());
+    let backup = Path::new(report.backup.as_deref().unwrap()).to_path_buf();
+    assert_file_exists(&backup.join("skins/old.vcp"));
+    assert_eq!(backup_names(&target).len(), 1);
+    assert!(backup_names(&target)[0].starts_with("vcp_backup_"));
+    // Nothing is left behind but the two folders
+    assert_eq!(fs::read_dir(resources.path()).unwrap().count(), 2);
+
+    // Rolling back restores the old folder and keeps the deployed one
+    let report = rollback_deploy(&target, None).unwrap();
+    assert_eq!(report.restored.as_deref(), Some(backup.to_str().unwrap()));
+    assert_file_exists(&target.join("skins/old.vcp"));
+    let deployed = Path::new(report.backup.as_deref().unwrap()).to_path_buf();
+    assert_file_exists(&deployed.join("skins/panel.vcp"));
+    assert_eq!(backup_names(&target).len(), 1);
+
+    // Any backup can be named
+    let name = deployed.file_name().unwrap().to_str().unwrap();
+    rollback_deploy(&target, Some(name)).unwrap();
+    assert_file_exists(&target.join("skins/panel.vcp"));
+    assert!(rollback_deploy(&target, Some("vcp_backup_19700101_000000")).is_err());
+}
+
+#[test]
+fn test_broken_exports_are_not_deployed() {
+    let project = setup_test_vcp_folder();
+    let resources = TempDir::new().expect("Failed to create temp directory");
+    let target = resources.path().join("vcp");
+
+    // A first deploy has nothing to back up
+    let zip_path = export_project(
+        project.path(),
+        &resources.path().to_string_lossy(),
+        &entries(),
+        None,
+    )
+    .unwrap()
+    .zip;
+    let report = deploy_export(Path::new(&zip_path), &target).unwrap();
+    assert_eq!(report.backup, None);
+    assert!(backup_names(&target).is_empty());
+
+    // A zip whose files differ from its manifest leaves the folder alone
+    let mut archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+    let tampered = resources.path().join("tampered.zip");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&tampered).unwrap());
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).unwrap();
+        let name = entry.name().to_string();
+        let mut content = Vec::new();
+        std::io::copy(&mut entry, &mut content).unwrap();
+        if name == "vcp/skins/panel.vcp" {
+            content = b"<vcp_skin/>".to_vec();
+        }
+        zip.start_file(name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(&content).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let error = deploy_export(&tampered, &target).unwrap_err();
+    assert!(error.contains("skins/panel.vcp"), "{}", error);
+    assert!(verify_export(&target).unwrap().issues.is_empty());
+    assert!(backup_names(&target).is_empty());
+
+    let error = rollback_deploy(&target, None).unwrap_err();
+    assert!(error.contains("No backups"), "{}", error);
+    assert!(deploy_project(project.path(), "C:/cncm", project.path(), &entries(), None).is_err());
+}
+
+#[test]
+fn test_files_vcpignore_brings_back_are_deployed() {
+    let project = setup_test_vcp_folder();
+    fs::write(project.path().join(".vcpignore"), "!.keep\n").unwrap();
+    fs::write(project.path().join("images/.keep"), "").unwrap();
+    let resources = TempDir::new().expect("Failed to create temp directory");
+    let target = resources.path().join("vcp");
+
+    deploy_project(
+        project.path(),
+        "C:/cncm/resources/vcp",
+        &target,
+        &entries(),
+        None,
+    )
+    .unwrap();
+
+    assert_file_exists(&target.join("images/.keep"));
+    assert!(verify_export(&target).unwrap().issues.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_deployed_folder_is_readable_by_the_cnc() {
+    use std::os::unix::fs::PermissionsExt;
+    let project = setup_test_vcp_folder();
+    let resources = TempDir::new().expect("Failed to create temp directory");
+    let target = resources.path().join("vcp");
+    let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+    // A new folder gets the usual permissions rather than the staging folder's
+    deploy_project(
+        project.path(),
+        "C:/cncm/resources/vcp",
+        &target,
+        &entries(),
+        None,
+    )
+    .unwrap();
+    assert_eq!(mode(&target), 0o755);
+
+    // A replaced folder keeps the permissions it had
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o775)).unwrap();
+    deploy_project(
+        project.path(),
+        "C:/cncm/resources/vcp",
+        &target,
+        &entries(),
+        None,
+    )
+    .unwrap();
+    assert_eq!(mode(&target), 0o775);
+}
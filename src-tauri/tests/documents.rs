@@ -0,0 +1,92 @@
+// Integration tests for the registry of open documents
+use vcp_editor_lib::backend::documents::DocumentRegistry;
+use vcp_editor_lib::backend::models::{DocumentInfo, EditCommand, VcpDocument};
+
+fn resize(rows: i32) -> Vec<EditCommand> {
+    vec![EditCommand::ResizeGrid {
+        row_count: rows,
+        column_count: 6,
+    }]
+}
+
+#[test]
+fn test_documents_keep_their_own_history() {
+    let mut registry = DocumentRegistry::default();
+    let first = registry.insert(
+        VcpDocument::default(),
+        Some("a.vcp".to_string()),
+        Some("main".to_string()),
+    );
+    let second = registry.insert(VcpDocument::default(), None, Some("editor-2".to_string()));
+    assert_ne!(first, second);
+
+    let entry = registry.get_mut(first).unwrap();
+    entry
+        .history
+        .apply(&mut entry.document, resize(30), "Resize grid".to_string())
+        .unwrap();
+
+    assert_eq!(registry.get(first).unwrap().document.row_count, 30);
+    assert!(registry.get(first).unwrap().is_dirty());
+    assert_eq!(
+        registry.get(second).unwrap().document.row_count,
+        VcpDocument::default().row_count
+    );
+    assert!(!registry.get(second).unwrap().is_dirty());
+
+    assert_eq!(
+        registry.list(),
+        vec![
+            DocumentInfo {
+                id: first,
+                path: Some("a.vcp".to_string()),
+                dirty: true,
+                window: Some("main".to_string()),
+            },
+            DocumentInfo {
+                id: second,
+                path: None,
+                dirty: false,
+                window: Some("editor-2".to_string()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_replacing_a_document_starts_a_clean_history() {
+    let mut registry = DocumentRegistry::default();
+    let id = registry.insert(VcpDocument::default(), None, None);
+    let entry = registry.get_mut(id).unwrap();
+    entry
+        .history
+        .apply(&mut entry.document, resize(12), "Resize grid".to_string())
+        .unwrap();
+
+    registry
+        .replace(id, VcpDocument::default(), Some("b.vcp".to_string()))
+        .unwrap();
+
+    let info = registry.info(id).unwrap();
+    assert_eq!(info.path.as_deref(), Some("b.vcp"));
+    assert!(!info.dirty);
+    assert!(registry.get(id).unwrap().history.list().entries.is_empty());
+}
+
+#[test]
+fn test_closed_ids_are_not_reused() {
+    let mut registry = DocumentRegistry::default();
+    let kept = registry.insert(VcpDocument::default(), None, Some("main".to_string()));
+    let closed = registry.insert(VcpDocument::default(), None, Some("editor-2".to_string()));
+
+    assert_eq!(registry.close_window("editor-2"), vec![closed]);
+    assert!(registry.get(closed).is_err());
+    assert!(registry
+        .replace(closed, VcpDocument::default(), None)
+        .is_err());
+
+    let next = registry.insert(VcpDocument::default(), None, None);
+    assert!(next != closed && next != kept);
+    assert!(registry.close(kept).is_some());
+    assert!(registry.close(kept).is_none());
+}
@@ -0,0 +1,130 @@
+// Integration tests for skin and button encoding detection
+mod common;
+
+use common::*;
+use std::fs;
+use std::path::Path;
+use vcp_editor_lib::backend::button::{load_button, save_button};
+use vcp_editor_lib::backend::encoding::{decode_text, encode_text};
+use vcp_editor_lib::backend::models::{Button, LineEnding, SerializeOptions, TextEncoding};
+use vcp_editor_lib::backend::parser::{load_file, save_file};
+
+const SKIN: &str = "<vcp_skin>\r\n    <background>#000000</background>\r\n    <button row=\"1\" column=\"1\">Broche_été</button>\r\n</vcp_skin>\r\n";
+
+fn utf16le(text: &str, bom: bool) -> Vec<u8> {
+    let mut bytes = if bom { vec![0xFF, 0xFE] } else { Vec::new() };
+    bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    bytes
+}
+
+fn write_skin(base: &Path, bytes: &[u8]) -> String {
+    let path = base.join("skins").join("test.vcp");
+    fs::write(&path, bytes).expect("Failed to write skin");
+    path.to_string_lossy().to_string()
+}
+
+fn new_button(row: i32, column: i32, name: &str) -> Button {
+    Button {
+        row,
+        column,
+        row_span: None,
+        column_span: None,
+        name: name.to_string(),
+        file: None,
+        default_image: None,
+        definition: None,
+        source: None,
+    }
+}
+
+#[test]
+fn test_detects_encodings() {
+    let mut utf8_bom = vec![0xEF, 0xBB, 0xBF];
+    utf8_bom.extend_from_slice(SKIN.as_bytes());
+    let mut utf16be = Vec::new();
+    utf16be.extend(SKIN.encode_utf16().flat_map(u16::to_be_bytes));
+    let ansi: Vec<u8> = SKIN
+        .chars()
+        .map(|c| if c == 'é' { 0xE9 } else { c as u8 })
+        .collect();
+
+    let cases: Vec<(Vec<u8>, TextEncoding, bool)> = vec![
+        (SKIN.as_bytes().to_vec(), TextEncoding::Utf8, false),
+        (utf8_bom, TextEncoding::Utf8, true),
+        (utf16le(SKIN, true), TextEncoding::Utf16le, true),
+        (utf16le(SKIN, false), TextEncoding::Utf16le, false),
+        (utf16be, TextEncoding::Utf16be, false),
+        (ansi, TextEncoding::Windows1252, false),
+    ];
+
+    for (bytes, encoding, bom) in cases {
+        let (text, format) = decode_text(&bytes);
+        assert_eq!(text, SKIN, "{:?}", encoding);
+        assert_eq!(format.encoding, encoding);
+        assert_eq!(format.bom, bom);
+        assert_eq!(format.line_ending, LineEnding::Crlf);
+        assert_eq!(encode_text(&text, &format), bytes, "{:?}", encoding);
+    }
+}
+
+#[test]
+fn test_declared_codepage_is_kept_for_ascii_files() {
+    let xml = "<?xml version=\"1.0\" encoding=\"Windows-1252\"?>\n<vcp_skin>\n</vcp_skin>\n";
+
+    let (_, format) = decode_text(xml.as_bytes());
+
+    assert_eq!(format.encoding, TextEncoding::Windows1252);
+    assert_eq!(format.line_ending, LineEnding::Lf);
+    assert_eq!(
+        encode_text("<a>é→</a>", &format),
+        b"<a>\xE9&#8594;</a>".to_vec()
+    );
+}
+
+#[test]
+fn test_utf16_skin_saves_back_unchanged() {
+    let temp_base = setup_test_vcp_folder();
+    let bytes = utf16le(SKIN, true);
+    let path = write_skin(temp_base.path(), &bytes);
+
+    let loaded = load_file(&path).expect("Failed to load UTF-16 skin");
+    assert_eq!(loaded.document.background, "#000000");
+    assert_eq!(loaded.document.buttons[0].name, "Broche_été");
+    save_file(&path, &loaded.document, &SerializeOptions::default()).expect("Failed to save skin");
+
+    assert_eq!(fs::read(&path).unwrap(), bytes);
+}
+
+#[test]
+fn test_edited_bom_skin_keeps_encoding_and_line_endings() {
+    let temp_base = setup_test_vcp_folder();
+    let mut bytes = vec![0xEF, 0xBB, 0xBF];
+    bytes.extend_from_slice(SKIN.as_bytes());
+    let path = write_skin(temp_base.path(), &bytes);
+
+    let mut doc = load_file(&path).expect("Failed to load skin").document;
+    doc.buttons.push(new_button(2, 1, "feed_hold"));
+    save_file(&path, &doc, &SerializeOptions::default()).expect("Failed to save skin");
+
+    let saved = fs::read(&path).unwrap();
+    assert!(saved.starts_with(&[0xEF, 0xBB, 0xBF]));
+    let text = String::from_utf8(saved[3..].to_vec()).unwrap();
+    assert!(text.contains("<button row=\"2\" column=\"1\">feed_hold</button>\r\n"));
+    assert_eq!(text.matches('\n').count(), text.matches("\r\n").count());
+}
+
+#[test]
+fn test_button_xml_keeps_its_encoding() {
+    let temp_base = setup_test_vcp_folder();
+    let button_dir = create_mock_button(temp_base.path(), "coolant");
+    let xml = "<vcp_button>\r\n  <skin_event_num>12</skin_event_num>\r\n</vcp_button>";
+    fs::write(button_dir.join("coolant.xml"), utf16le(xml, true)).unwrap();
+
+    let mut button = load_button(temp_base.path(), "coolant").expect("Failed to load button");
+    assert_eq!(button.skin_event_num, Some(12));
+    button.skin_event_num = Some(13);
+    save_button(&button_dir, &button).expect("Failed to save button");
+
+    let expected = utf16le(&xml.replace("12", "13"), true);
+    assert_eq!(fs::read(button_dir.join("coolant.xml")).unwrap(), expected);
+}
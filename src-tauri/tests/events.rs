@@ -0,0 +1,115 @@
+// Integration tests for skin event analysis
+mod common;
+
+use common::*;
+use std::fs;
+use vcp_editor_lib::backend::button::save_button;
+use vcp_editor_lib::backend::events::{
+    analyze_buttons, analyze_skin_events, BUILTIN_SKIN_EVENTS, RESERVED_SKIN_EVENTS,
+};
+use vcp_editor_lib::backend::models::{EventIssue, Severity, VcpButton};
+
+fn bound(name: &str, event: i32) -> VcpButton {
+    VcpButton {
+        name: name.to_string(),
+        skin_event_num: Some(event),
+        ..Default::default()
+    }
+}
+
+fn problems(issues: &[EventIssue]) -> Vec<&EventIssue> {
+    issues
+        .iter()
+        .filter(|issue| issue.rule != "unbound-event")
+        .collect()
+}
+
+#[test]
+fn test_distinct_user_events_have_no_problems() {
+    let buttons = vec![
+        bound("spin_cw", 7),
+        bound("spin_ccw", 12),
+        bound("lube", 200),
+    ];
+
+    assert!(problems(&analyze_buttons(&buttons)).is_empty());
+}
+
+#[test]
+fn test_duplicate_event_lists_every_button() {
+    let buttons = vec![
+        bound("flood_b", 22),
+        bound("flood_a", 22),
+        bound("mist", 23),
+    ];
+
+    let issues = analyze_buttons(&buttons);
+    let problems = problems(&issues);
+
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].rule, "duplicate-event");
+    assert_eq!(problems[0].severity, Severity::Error);
+    assert_eq!(problems[0].event, Some(22));
+    assert_eq!(problems[0].buttons, vec!["flood_a", "flood_b"]);
+    assert!(problems[0].message.contains("SkinCoolFlood_M"));
+}
+
+#[test]
+fn test_out_of_range_and_reserved_events() {
+    let buttons = vec![bound("too_high", 256), bound("zero", 0), bound("estop", 93)];
+
+    let issues = analyze_buttons(&buttons);
+    let rules: Vec<(&str, Option<i32>)> = problems(&issues)
+        .iter()
+        .map(|issue| (issue.rule.as_str(), issue.event))
+        .collect();
+
+    assert_eq!(
+        rules,
+        vec![
+            ("out-of-range", Some(0)),
+            ("reserved-event", Some(93)),
+            ("out-of-range", Some(256)),
+        ]
+    );
+}
+
+#[test]
+fn test_unbound_user_events_are_info() {
+    let buttons = vec![bound("spin_cw", 7)];
+
+    let unbound: Vec<EventIssue> = analyze_buttons(&buttons)
+        .into_iter()
+        .filter(|issue| issue.rule == "unbound-event")
+        .collect();
+
+    assert_eq!(
+        unbound.len(),
+        BUILTIN_SKIN_EVENTS.len() - RESERVED_SKIN_EVENTS.len() - 1
+    );
+    assert!(unbound.iter().all(|issue| issue.severity == Severity::Info));
+    assert!(!unbound.iter().any(|issue| issue.event == Some(7)));
+    assert!(!unbound.iter().any(|issue| issue.event == Some(55)));
+}
+
+#[test]
+fn test_analyze_reads_every_button_folder() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    for (name, event) in [("cycle_start", 50), ("start_copy", 50)] {
+        let folder = root.join("Buttons").join(name);
+        fs::create_dir_all(&folder).unwrap();
+        save_button(&folder, &bound(name, event)).expect("Failed to save button");
+    }
+    fs::create_dir_all(root.join("Buttons/broken")).unwrap();
+    fs::write(root.join("Buttons/broken/broken.xml"), "not xml").unwrap();
+
+    let issues = analyze_skin_events(root).expect("Failed to analyze project");
+    let problems = problems(&issues);
+
+    assert_eq!(problems.len(), 2);
+    assert_eq!(problems[0].rule, "unreadable-button");
+    assert_eq!(problems[0].buttons, vec!["broken"]);
+    assert_eq!(problems[1].rule, "duplicate-event");
+    assert_eq!(problems[1].buttons, vec!["cycle_start", "start_copy"]);
+}
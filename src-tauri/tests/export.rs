@@ -0,0 +1,430 @@
+// Integration tests for exporting skins to the CNC
+mod common;
+
+use common::*;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::thread;
+use tempfile::TempDir;
+use vcp_editor_lib::backend::export::{export_project, read_export_skins, ExportEntry};
+use vcp_editor_lib::backend::ignore::IgnoreRules;
+use vcp_editor_lib::backend::manifest::verify_export;
+use vcp_editor_lib::backend::models::{
+    AssetIssueKind, Controller, ExportSkin, LineEnding, MachineProfile, MachineType,
+    ProjectSettings, SerializeOptions, SkinTarget, ValidationSettings, VerifyIssue,
+    VerifyIssueKind,
+};
+use vcp_editor_lib::backend::parser::parse_vcp;
+use vcp_editor_lib::backend::settings::save_project_settings;
+
+const SKIN: &str =
+    "<vcp_skin>\n    <column_count>4</column_count>\n    <row_count>3</row_count>\n</vcp_skin>\n";
+
+fn zip_names(zip_path: impl AsRef<Path>) -> Vec<String> {
+    let file = fs::File::open(zip_path).expect("Failed to open zip");
+    let archive = zip::ZipArchive::new(file).expect("Failed to read zip");
+    let mut names: Vec<String> = archive
+        .file_names()
+        .map(|name| name.replace('\\', "/"))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_machine_profiles_name_the_skin_file() {
+    let profile = MachineProfile::parse("Oak-Lathe").unwrap();
+    assert_eq!(
+        profile,
+        MachineProfile {
+            controller: Controller::Oak,
+            machine: MachineType::Lathe,
+        }
+    );
+    assert_eq!(profile.skin_file(), "oak_lathe_vcp_skin.vcp");
+    assert_eq!(
+        MachineProfile::default().skin_file(),
+        "acorn_mill_vcp_skin.vcp"
+    );
+    assert!(MachineProfile::parse("acorn-laser").is_err());
+    assert!(MachineProfile::parse("acornsix").is_err());
+
+    assert!(SkinTarget::File("../evil.vcp".to_string())
+        .file_name()
+        .is_err());
+    assert!(SkinTarget::File("panel.xml".to_string())
+        .file_name()
+        .is_err());
+}
+
+#[test]
+fn test_several_skins_are_exported_under_their_targets() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    fs::write(root.join("skins/router.vcp"), SKIN).expect("Failed to write skin");
+    save_project_settings(
+        root,
+        &ProjectSettings {
+            machine: MachineProfile::parse("hickory-plasma").unwrap(),
+            ..ProjectSettings::default()
+        },
+    )
+    .expect("Failed to save settings");
+    let cnc = TempDir::new().expect("Failed to create temp directory");
+
+    let mut entries = vec![ExportEntry {
+        document: parse_vcp(SKIN).unwrap(),
+        target: None,
+        source: None,
+    }];
+    let skins = [ExportSkin {
+        source: "skins/router.vcp".to_string(),
+        target: Some(SkinTarget::Machine(
+            MachineProfile::parse("acornsix-router").unwrap(),
+        )),
+    }];
+    entries.extend(read_export_skins(root, &skins).unwrap());
+    let zip_path = export_project(root, &cnc.path().to_string_lossy(), &entries, None)
+        .unwrap()
+        .zip;
+
+    let names = zip_names(&zip_path);
+    assert!(
+        names.contains(&"vcp/skins/hickory_plasma_vcp_skin.vcp".to_string()),
+        "{:?}",
+        names
+    );
+    assert!(
+        names.contains(&"vcp/skins/acornsix_router_vcp_skin.vcp".to_string()),
+        "{:?}",
+        names
+    );
+    assert!(!names.contains(&"vcp/skins/acorn_mill_vcp_skin.vcp".to_string()));
+}
+
+#[test]
+fn test_skins_exported_under_one_name_are_refused() {
+    let temp_base = setup_test_vcp_folder();
+    let cnc = TempDir::new().expect("Failed to create temp directory");
+    let entries = vec![
+        ExportEntry {
+            document: parse_vcp(SKIN).unwrap(),
+            target: None,
+            source: None,
+        },
+        ExportEntry {
+            document: parse_vcp(SKIN).unwrap(),
+            target: Some(SkinTarget::File("Acorn_Mill_VCP_Skin.vcp".to_string())),
+            source: None,
+        },
+    ];
+
+    let error = export_project(
+        temp_base.path(),
+        &cnc.path().to_string_lossy(),
+        &entries,
+        None,
+    )
+    .unwrap_err();
+    assert!(error.starts_with("More than one skin"), "{}", error);
+    assert_eq!(fs::read_dir(cnc.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_missing_assets_block_export_or_are_reported() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    let cnc = TempDir::new().expect("Failed to create temp directory");
+    let skin = SKIN.replace(
+        "</vcp_skin>",
+        "    <image>\n        <row_start>1</row_start>\n        <column_start>1</column_start>\n        <row_span>1</row_span>\n        <column_span>1</column_span>\n        <path>images/missing.svg</path>\n    </image>\n</vcp_skin>",
+    );
+    let entries = [ExportEntry {
+        document: parse_vcp(&skin).unwrap(),
+        target: Some(SkinTarget::File("panel.vcp".to_string())),
+        source: None,
+    }];
+    let cnc_path = cnc.path().to_string_lossy().to_string();
+
+    let error = export_project(root, &cnc_path, &entries, None).unwrap_err();
+    assert!(
+        error.starts_with("Project has 1 missing or unusable asset(s)"),
+        "{}",
+        error
+    );
+    assert_eq!(fs::read_dir(cnc.path()).unwrap().count(), 0);
+
+    save_project_settings(
+        root,
+        &ProjectSettings {
+            validation: ValidationSettings {
+                block_export: false,
+                ..ValidationSettings::default()
+            },
+            ..ProjectSettings::default()
+        },
+    )
+    .expect("Failed to save settings");
+    let report = export_project(root, &cnc_path, &entries, None).unwrap();
+    assert!(Path::new(&report.zip).is_file());
+    assert_eq!(report.asset_issues.len(), 1);
+    assert_eq!(report.asset_issues[0].kind, AssetIssueKind::Missing);
+    assert_eq!(report.asset_issues[0].path, "images/missing.svg");
+}
+
+#[test]
+fn test_ignore_rules() {
+    let rules = IgnoreRules::parse("# drafts\n*.psd\n/skins/old/\nimages/**/raw_*\n!.keep\n");
+
+    for path in [
+        ".DS_Store",
+        "images/Thumbs.db",
+        "skins/main.vcp~",
+        "skins/main.vcp.BAK",
+        "art.psd",
+        ".vcp-editor.json",
+    ] {
+        assert!(rules.is_ignored(path, false), "{}", path);
+    }
+    assert!(rules.is_ignored("skins/old", true));
+    assert!(!rules.is_ignored("skins/old", false));
+    assert!(!rules.is_ignored("Buttons/skins/old", true));
+    assert!(rules.is_ignored("images/raw_logo.svg", false));
+    assert!(rules.is_ignored("images/a/b/raw_logo.svg", false));
+    assert!(!rules.is_ignored("images/logo.svg", false));
+    assert!(!rules.is_ignored("images/.keep", false));
+    assert!(!rules.is_ignored("skins/main.vcp", false));
+}
+
+#[test]
+fn test_export_skips_ignored_files_and_runs_concurrently() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    create_mock_image(&root.join("images"), "logo.svg");
+    create_mock_image(&root.join("images"), "draft.svg");
+    for litter in [".DS_Store", "Thumbs.db", "logo.svg~", "logo.svg.bak"] {
+        fs::write(root.join("images").join(litter), "x").expect("Failed to write file");
+    }
+    fs::create_dir_all(root.join(".git")).expect("Failed to create folder");
+    fs::write(root.join(".git/HEAD"), "ref").expect("Failed to write file");
+    fs::write(root.join(".vcpignore"), "images/draft.svg\n").expect("Failed to write ignore file");
+    let cnc = TempDir::new().expect("Failed to create temp directory");
+    let cnc_path = cnc.path().to_string_lossy().to_string();
+
+    let exports: Vec<_> = (0..4)
+        .map(|_| {
+            let (root, cnc_path) = (root.to_path_buf(), cnc_path.clone());
+            thread::spawn(move || {
+                let entries = [ExportEntry {
+                    document: parse_vcp(SKIN).unwrap(),
+                    target: None,
+                    source: None,
+                }];
+                export_project(&root, &cnc_path, &entries, None).map(|report| report.zip)
+            })
+        })
+        .collect();
+    let mut zips: Vec<_> = exports
+        .into_iter()
+        .map(|export| export.join().unwrap().unwrap())
+        .collect();
+    zips.sort();
+    zips.dedup();
+    assert_eq!(zips.len(), 4);
+
+    for zip_path in &zips {
+        assert_eq!(
+            zip_names(zip_path),
+            [
+                "vcp/.vcp-manifest.json",
+                "vcp/images/logo.svg",
+                "vcp/skins/acorn_mill_vcp_skin.vcp"
+            ]
+        );
+    }
+    // Nothing but the zips is left in the CNC folder
+    assert_eq!(fs::read_dir(cnc.path()).unwrap().count(), 4);
+}
+
+#[test]
+fn test_manifest_lists_files_and_verifies_deployed_folder() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    create_mock_image(&root.join("images"), "logo.svg");
+    let cnc = TempDir::new().expect("Failed to create temp directory");
+    let entries = [ExportEntry {
+        document: parse_vcp(SKIN).unwrap(),
+        target: Some(SkinTarget::File("panel.vcp".to_string())),
+        source: Some("skins/panel.vcp".to_string()),
+    }];
+    let zip_path = export_project(root, &cnc.path().to_string_lossy(), &entries, None)
+        .unwrap()
+        .zip;
+
+    let report = verify_export(Path::new(&zip_path)).unwrap();
+    assert!(report.issues.is_empty(), "{:?}", report.issues);
+    let manifest = report.manifest;
+    assert_eq!(manifest.editor_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(manifest.skins[0].file, "panel.vcp");
+    assert_eq!(manifest.skins[0].source.as_deref(), Some("skins/panel.vcp"));
+    let paths: Vec<&str> = manifest
+        .files
+        .iter()
+        .map(|file| file.path.as_str())
+        .collect();
+    assert_eq!(paths, ["images/logo.svg", "skins/panel.vcp"]);
+    assert_eq!(manifest.files[0].sha256.len(), 64);
+
+    // Unpack as CNC12 would find it, then tamper with it
+    let deployed = TempDir::new().expect("Failed to create temp directory");
+    let mut archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+    archive
+        .extract(deployed.path())
+        .expect("Failed to extract zip");
+    let vcp = deployed.path().join("vcp");
+    assert!(verify_export(deployed.path()).unwrap().issues.is_empty());
+
+    fs::write(vcp.join("skins/panel.vcp"), "<vcp_skin/>").unwrap();
+    fs::remove_file(vcp.join("images/logo.svg")).unwrap();
+    fs::write(vcp.join("images/extra.svg"), "<svg/>").unwrap();
+    fs::write(vcp.join("images/Thumbs.db"), "x").unwrap();
+    let issues = verify_export(&vcp).unwrap().issues;
+    assert_eq!(
+        issues,
+        [
+            VerifyIssue {
+                kind: VerifyIssueKind::Missing,
+                path: "images/logo.svg".to_string(),
+            },
+            VerifyIssue {
+                kind: VerifyIssueKind::Modified,
+                path: "skins/panel.vcp".to_string(),
+            },
+            VerifyIssue {
+                kind: VerifyIssueKind::Unexpected,
+                path: "images/extra.svg".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_exported_skins_follow_output_options() {
+    let temp_base = setup_test_vcp_folder();
+    let cnc = TempDir::new().expect("Failed to create temp directory");
+    // A hand-edited skin with CRLF line endings, exported with LF and a
+    // declaration asked for
+    let document = parse_vcp(&SKIN.replace('\n', "\r\n")).unwrap();
+    assert!(document.layout.is_some());
+    let options = SerializeOptions {
+        line_ending: Some(LineEnding::Lf),
+        declaration: true,
+        ..Default::default()
+    };
+    let entries = [ExportEntry {
+        document,
+        target: Some(SkinTarget::File("panel.vcp".to_string())),
+        source: None,
+    }];
+    let zip_path = export_project(
+        temp_base.path(),
+        &cnc.path().to_string_lossy(),
+        &entries,
+        Some(options),
+    )
+    .unwrap()
+    .zip;
+
+    let mut archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+    let mut exported = String::new();
+    archive
+        .by_name("vcp/skins/panel.vcp")
+        .unwrap()
+        .read_to_string(&mut exported)
+        .unwrap();
+    assert!(
+        exported.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<vcp_skin>\n"),
+        "{}",
+        exported
+    );
+    assert!(!exported.contains('\r'), "{:?}", exported);
+}
+
+#[test]
+fn test_verify_keeps_files_vcpignore_brings_back() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    fs::write(root.join(".vcpignore"), "!.keep\n!.config/\n").unwrap();
+    fs::write(root.join("images/.keep"), "").unwrap();
+    fs::create_dir_all(root.join(".config")).unwrap();
+    fs::write(root.join(".config/panel.ini"), "x").unwrap();
+    let cnc = TempDir::new().expect("Failed to create temp directory");
+    let entries = [ExportEntry {
+        document: parse_vcp(SKIN).unwrap(),
+        target: Some(SkinTarget::File("panel.vcp".to_string())),
+        source: None,
+    }];
+    let zip_path = export_project(root, &cnc.path().to_string_lossy(), &entries, None)
+        .unwrap()
+        .zip;
+    let names = zip_names(&zip_path);
+    assert!(names.contains(&"vcp/images/.keep".to_string()));
+    assert!(names.contains(&"vcp/.config/panel.ini".to_string()));
+
+    let deployed = TempDir::new().expect("Failed to create temp directory");
+    let mut archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+    archive
+        .extract(deployed.path())
+        .expect("Failed to extract zip");
+    let vcp = deployed.path().join("vcp");
+    // Litter the OS adds later is still left out
+    fs::write(vcp.join(".DS_Store"), "x").unwrap();
+    fs::write(vcp.join(".config/.DS_Store"), "x").unwrap();
+    assert_eq!(verify_export(&vcp).unwrap().issues, []);
+
+    fs::remove_file(vcp.join("images/.keep")).unwrap();
+    assert_eq!(
+        verify_export(&vcp).unwrap().issues,
+        [VerifyIssue {
+            kind: VerifyIssueKind::Missing,
+            path: "images/.keep".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn test_cnc_image_paths_are_escaped_and_comments_left_alone() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    create_mock_image(&root.join("images"), "logo.svg");
+    let cnc = TempDir::new().expect("Failed to create temp directory");
+    let skin = "<vcp_skin>\n    <!-- <path>images/old.svg</path> -->\n    <image>\n        <row_start>1</row_start>\n        <column_start>1</column_start>\n        <row_span>1</row_span>\n        <column_span>1</column_span>\n        <path>images/logo.svg</path>\n    </image>\n</vcp_skin>\n";
+    // The zip is written into the CNC folder, so give one that exists
+    let cnc_dir = cnc.path().join("R&D <vcp>");
+    fs::create_dir_all(&cnc_dir).unwrap();
+    let cnc_base_path = cnc_dir.to_string_lossy().to_string();
+    let entries = [ExportEntry {
+        document: parse_vcp(skin).unwrap(),
+        target: Some(SkinTarget::File("panel.vcp".to_string())),
+        source: None,
+    }];
+    let zip_path = export_project(root, &cnc_base_path, &entries, None)
+        .unwrap()
+        .zip;
+
+    let mut archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+    let mut exported = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name("vcp/skins/panel.vcp").unwrap(),
+        &mut exported,
+    )
+    .unwrap();
+    assert!(exported.contains("<!-- <path>images/old.svg</path> -->"));
+    assert!(exported.contains("R&amp;D &lt;vcp&gt;/images/logo.svg"));
+    let reparsed = parse_vcp(&exported).unwrap();
+    assert_eq!(
+        reparsed.images[0].path,
+        format!("{}/images/logo.svg", cnc_base_path.replace('\\', "/"))
+    );
+}
@@ -185,11 +185,9 @@ fn test_folder_structure_integrity() {
     // Verify all structure is intact
     let entries = fs::read_dir(base_path).expect("Failed to read directory");
     let mut dir_count = 0;
-    for entry in entries {
-        if let Ok(entry) = entry {
-            if entry.path().is_dir() {
-                dir_count += 1;
-            }
+    for entry in entries.flatten() {
+        if entry.path().is_dir() {
+            dir_count += 1;
         }
     }
     
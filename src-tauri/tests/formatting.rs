@@ -0,0 +1,182 @@
+// Integration tests for configurable output formatting
+mod common;
+
+use common::*;
+use std::fs;
+use std::path::PathBuf;
+use vcp_editor_lib::backend::models::{
+    AttributeQuote, Button, ElementOrder, LineEnding, ProjectSettings, SerializeOptions,
+    VcpDocument,
+};
+use vcp_editor_lib::backend::parser::{load_file, parse_vcp, save_file, serialize_vcp_with};
+use vcp_editor_lib::backend::settings::{
+    load_project_settings, project_settings_path, save_project_settings,
+};
+
+fn small_document() -> VcpDocument {
+    VcpDocument {
+        on_click: None,
+        on_hover: None,
+        buttons: vec![Button {
+            row: 1,
+            column: 2,
+            row_span: Some(2),
+            column_span: None,
+            name: "spindle".to_string(),
+            file: None,
+            default_image: None,
+            definition: None,
+            source: None,
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_default_options_match_cnc12() {
+    let xml = serialize_vcp_with(&small_document(), &SerializeOptions::default());
+
+    assert_eq!(
+        xml,
+        "<vcp_skin>\r\n    <background>#E9E0B7</background>\r\n    <column_count>6</column_count>\r\n    <row_count>14</row_count>\r\n    <button row=\"1\" column=\"2\" row_span=\"2\">spindle</button>\r\n</vcp_skin>\r\n"
+    );
+}
+
+#[test]
+fn test_custom_indent_quotes_and_declaration() {
+    let mut doc = small_document();
+    doc.on_hover = VcpDocument::default().on_hover;
+    let options = SerializeOptions {
+        line_ending: Some(LineEnding::Lf),
+        indent: "\t".to_string(),
+        declaration: true,
+        attribute_quote: AttributeQuote::Single,
+        element_order: ElementOrder::Original,
+    };
+
+    let xml = serialize_vcp_with(&doc, &options);
+
+    assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<vcp_skin>\n"));
+    assert!(xml.contains("\n\t<on_hover>\n\t\t<opacity>100</opacity>\n"));
+    assert!(xml.contains("\n\t<button row='1' column='2' row_span='2'>spindle</button>\n"));
+    assert!(!xml.contains('\r'));
+    let reparsed = parse_vcp(&xml).expect("Failed to reparse skin");
+    assert_eq!(reparsed.buttons[0].row_span, Some(2));
+}
+
+#[test]
+fn test_options_only_shape_new_markup_in_loaded_skins() {
+    let content = "<vcp_skin>\n  <background>#000000</background>\n  <button row=\"1\" column=\"1\">a</button>\n</vcp_skin>\n";
+    let mut doc = parse_vcp(content).expect("Failed to parse skin");
+    let mut added = doc.buttons[0].clone();
+    added.source = None;
+    added.column = 2;
+    added.name = "b".to_string();
+    doc.buttons.push(added);
+    let options = SerializeOptions {
+        attribute_quote: AttributeQuote::Single,
+        ..Default::default()
+    };
+
+    let xml = serialize_vcp_with(&doc, &options);
+
+    assert_eq!(
+        xml,
+        "<vcp_skin>\r\n  <background>#000000</background>\r\n  <button row=\"1\" column=\"1\">a</button>\r\n  <button row='1' column='2'>b</button>\r\n</vcp_skin>\r\n"
+    );
+}
+
+#[test]
+fn test_save_file_keeps_line_endings_of_loaded_skin() {
+    let temp_base = setup_test_vcp_folder();
+    let path = temp_base.path().join("skins/hand_edited_skin.vcp");
+    let specimen =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../specimens/skins/hand_edited_skin.vcp");
+    fs::copy(&specimen, &path).expect("Failed to copy specimen");
+    let path = path.to_str().unwrap();
+
+    let loaded = load_file(path).expect("Failed to load skin");
+    save_file(path, &loaded.document, &SerializeOptions::default()).expect("Failed to save");
+
+    assert_eq!(fs::read(path).unwrap(), fs::read(&specimen).unwrap());
+}
+
+#[test]
+fn test_save_file_applies_options_to_new_skin() {
+    let temp_base = setup_test_vcp_folder();
+    let path = temp_base.path().join("skins/new.vcp");
+    let path = path.to_str().unwrap();
+    let options = SerializeOptions {
+        declaration: true,
+        ..Default::default()
+    };
+
+    save_file(path, &small_document(), &options).expect("Failed to save");
+
+    let saved = fs::read_to_string(path).unwrap();
+    assert!(saved.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n<vcp_skin>\r\n"));
+    assert_eq!(saved.matches('\n').count(), saved.matches("\r\n").count());
+}
+
+#[test]
+fn test_sorted_order_is_row_then_column() {
+    let mut doc = small_document();
+    for (row, column, name) in [(3, 1, "c"), (1, 4, "b"), (1, 1, "a")] {
+        let mut button = doc.buttons[0].clone();
+        button.row = row;
+        button.column = column;
+        button.row_span = None;
+        button.name = name.to_string();
+        doc.buttons.push(button);
+    }
+    let options = SerializeOptions {
+        element_order: ElementOrder::Sorted,
+        ..Default::default()
+    };
+
+    let reparsed = parse_vcp(&serialize_vcp_with(&doc, &options)).expect("Failed to reparse");
+    let names: Vec<&str> = reparsed.buttons.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "spindle", "b", "c"]);
+
+    let unsorted = parse_vcp(&serialize_vcp_with(&doc, &SerializeOptions::default()))
+        .expect("Failed to reparse");
+    assert_eq!(unsorted.buttons[0].name, "spindle");
+}
+
+#[test]
+fn test_sorting_loaded_skin_keeps_comments_in_place() {
+    let content = "<vcp_skin>\n  <!-- Bottom -->\n  <button row=\"6\" column=\"1\">estop</button>\n  <!-- Top -->\n  <button row=\"1\" column=\"1\">start</button>\n</vcp_skin>\n";
+    let doc = parse_vcp(content).expect("Failed to parse skin");
+    let options = SerializeOptions {
+        line_ending: None,
+        element_order: ElementOrder::Sorted,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        serialize_vcp_with(&doc, &options),
+        "<vcp_skin>\n  <!-- Bottom -->\n  <button row=\"1\" column=\"1\">start</button>\n  <!-- Top -->\n  <button row=\"6\" column=\"1\">estop</button>\n</vcp_skin>\n"
+    );
+}
+
+#[test]
+fn test_project_settings_round_trip() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    assert_eq!(
+        load_project_settings(root).expect("Failed to load defaults"),
+        ProjectSettings::default()
+    );
+
+    let mut settings = ProjectSettings::default();
+    settings.output.indent = "  ".to_string();
+    settings.output.line_ending = Some(LineEnding::Lf);
+    settings.output.element_order = ElementOrder::Sorted;
+    save_project_settings(root, &settings).expect("Failed to save settings");
+
+    assert!(project_settings_path(root).exists());
+    assert_eq!(
+        load_project_settings(root).expect("Failed to load settings"),
+        settings
+    );
+}
@@ -0,0 +1,176 @@
+// Integration tests for edit commands and the undo history
+use serde_json::{json, Value};
+use vcp_editor_lib::backend::history::EditHistory;
+use vcp_editor_lib::backend::models::{
+    EditCommand, Element, ElementKind, ElementRef, Image, VcpDocument,
+};
+use vcp_editor_lib::backend::parser::parse_vcp;
+
+const SKIN: &str = "<vcp_skin>\n    <background>#000000</background>\n    <column_count>4</column_count>\n    <row_count>4</row_count>\n    <border>\n        <row_start>1</row_start>\n        <column_start>1</column_start>\n        <row_span>2</row_span>\n        <column_span>2</column_span>\n        <fill>Transparent</fill>\n        <outline_color>#FFFFFF</outline_color>\n        <outline_thickness>1</outline_thickness>\n    </border>\n    <button row=\"1\" column=\"1\">spin_cw</button>\n    <button row=\"2\" column=\"1\" column_span=\"2\">cycle_start</button>\n</vcp_skin>\n";
+
+fn snapshot(doc: &VcpDocument) -> Value {
+    serde_json::to_value(doc).unwrap()
+}
+
+fn button(index: usize) -> ElementRef {
+    ElementRef {
+        kind: ElementKind::Button,
+        index,
+    }
+}
+
+#[test]
+fn test_every_edit_is_reverted_by_its_inverse() {
+    let mut doc = parse_vcp(SKIN).unwrap();
+    let image = Image {
+        row_start: 3,
+        column_start: 1,
+        row_span: 1,
+        column_span: 2,
+        path: "images/logo.svg".to_string(),
+        source: None,
+    };
+    let edits = vec![
+        EditCommand::AddElement {
+            element: Box::new(Element::Image(image)),
+            index: None,
+        },
+        EditCommand::DeleteElement {
+            kind: ElementKind::Button,
+            index: 0,
+        },
+        EditCommand::MoveElement {
+            kind: ElementKind::Border,
+            index: 0,
+            row: 3,
+            column: 3,
+        },
+        EditCommand::ResizeElement {
+            kind: ElementKind::Button,
+            index: 1,
+            row_span: Some(2),
+            column_span: None,
+        },
+        EditCommand::SetProperty {
+            element: Some(button(1)),
+            name: "name".to_string(),
+            value: json!("spindle_stop"),
+        },
+        EditCommand::SetProperty {
+            element: None,
+            name: "background".to_string(),
+            value: json!("#E9E0B7"),
+        },
+        EditCommand::ResizeGrid {
+            row_count: 8,
+            column_count: 6,
+        },
+    ];
+
+    for edit in edits {
+        let before = snapshot(&doc);
+        let inverse = edit.apply(&mut doc).expect("Edit failed");
+        assert_ne!(snapshot(&doc), before, "{:?} changed nothing", edit);
+        inverse.apply(&mut doc).expect("Inverse failed");
+        assert_eq!(snapshot(&doc), before, "{:?} was not reverted", edit);
+    }
+}
+
+#[test]
+fn test_invalid_edits_leave_the_document_alone() {
+    let mut doc = parse_vcp(SKIN).unwrap();
+    let before = snapshot(&doc);
+    let invalid = [
+        EditCommand::DeleteElement {
+            kind: ElementKind::Image,
+            index: 0,
+        },
+        EditCommand::ResizeElement {
+            kind: ElementKind::Border,
+            index: 0,
+            row_span: None,
+            column_span: Some(1),
+        },
+        EditCommand::SetProperty {
+            element: Some(button(0)),
+            name: "colour".to_string(),
+            value: json!("red"),
+        },
+        EditCommand::SetProperty {
+            element: Some(button(0)),
+            name: "row".to_string(),
+            value: json!("first"),
+        },
+        EditCommand::SetProperty {
+            element: None,
+            name: "row_count".to_string(),
+            value: json!(5),
+        },
+    ];
+    for edit in invalid {
+        assert!(edit.apply(&mut doc).is_err(), "{:?} was accepted", edit);
+        assert_eq!(snapshot(&doc), before);
+    }
+
+    // A failing edit takes the rest of its step with it
+    let mut history = EditHistory::default();
+    let step = vec![
+        EditCommand::ResizeGrid {
+            row_count: 8,
+            column_count: 8,
+        },
+        EditCommand::DeleteElement {
+            kind: ElementKind::Button,
+            index: 5,
+        },
+    ];
+    assert!(history.apply(&mut doc, step, "Resize".to_string()).is_err());
+    assert_eq!(snapshot(&doc), before);
+    assert_eq!(history.list().position, 0);
+}
+
+#[test]
+fn test_history_undo_redo_and_saved_position() {
+    let mut doc = parse_vcp(SKIN).unwrap();
+    let original = snapshot(&doc);
+    let mut history = EditHistory::default();
+    assert!(!history.is_dirty());
+
+    // Far more steps than the old frontend history kept
+    for column in 0..200 {
+        let step = vec![EditCommand::MoveElement {
+            kind: ElementKind::Button,
+            index: 0,
+            row: 4,
+            column: column % 4 + 1,
+        }];
+        history
+            .apply(&mut doc, step, format!("Move {}", column))
+            .unwrap();
+    }
+    let moved = snapshot(&doc);
+    history.mark_saved();
+    assert_eq!(history.list().entries.len(), 200);
+    assert_eq!(history.list().saved_position, Some(200));
+
+    while history.undo(&mut doc).unwrap() {}
+    assert_eq!(snapshot(&doc), original);
+    assert!(history.is_dirty());
+    assert!(history.redo(&mut doc).unwrap());
+    assert_eq!(history.list().position, 1);
+
+    // A new step drops the undone ones, including the saved state
+    let step = vec![EditCommand::ResizeGrid {
+        row_count: 5,
+        column_count: 5,
+    }];
+    history
+        .apply(&mut doc, step, "Resize grid".to_string())
+        .unwrap();
+    let list = history.list();
+    assert_eq!(list.entries, vec!["Move 0", "Resize grid"]);
+    assert_eq!(list.position, 2);
+    assert_eq!(list.saved_position, None);
+    assert!(!history.redo(&mut doc).unwrap());
+    assert_ne!(snapshot(&doc), moved);
+}
@@ -0,0 +1,260 @@
+// Integration tests for importing exports and deployed folders back into a project
+mod common;
+
+use common::*;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use vcp_editor_lib::backend::export::{export_project, ExportEntry};
+use vcp_editor_lib::backend::import::{import_cnc, relative_image_path};
+use vcp_editor_lib::backend::manifest::MANIFEST_FILE;
+use vcp_editor_lib::backend::models::SkinTarget;
+use vcp_editor_lib::backend::parser::parse_vcp;
+
+const SKIN: &str = "<vcp_skin>\n    <column_count>4</column_count>\n    <row_count>3</row_count>\n    <image>\n        <row_start>1</row_start>\n        <column_start>1</column_start>\n        <row_span>1</row_span>\n        <column_span>2</column_span>\n        <path>images/logo.svg</path>\n    </image>\n</vcp_skin>\n";
+
+/// Writes a zip holding `files`, as a CNC12 backup or a hand-made export might.
+fn write_zip(path: &Path, files: &[(&str, &str)]) {
+    let mut zip = zip::ZipWriter::new(fs::File::create(path).expect("Failed to create zip"));
+    for (name, content) in files {
+        zip.start_file(*name, zip::write::SimpleFileOptions::default())
+            .expect("Failed to add file");
+        zip.write_all(content.as_bytes())
+            .expect("Failed to write file");
+    }
+    zip.finish().expect("Failed to finish zip");
+}
+
+#[test]
+fn test_relative_image_paths() {
+    let cases = [
+        (r"C:\cncm\resources\vcp/images/a.svg", None, "images/a.svg"),
+        (
+            r"C:\cncm\resources\vcp\images\sub\b.png",
+            None,
+            "images/sub/b.png",
+        ),
+        ("/home/cnc/resources/vcp/images/c.svg", None, "images/c.svg"),
+        ("D:/panels/images/d.svg", None, "images/d.svg"),
+        (r"images\e.svg", None, "images/e.svg"),
+        ("images/f.svg", None, "images/f.svg"),
+        (
+            "E:/custom/base/images/g.svg",
+            Some(r"E:\custom\base\"),
+            "images/g.svg",
+        ),
+        (
+            "c:/CNCM/Resources/VCP/images/h.svg",
+            Some(r"C:\cncm\resources\vcp"),
+            "images/h.svg",
+        ),
+    ];
+    for (path, base, expected) in cases {
+        assert_eq!(relative_image_path(path, base), expected, "{}", path);
+    }
+}
+
+#[test]
+fn test_export_imports_back_into_a_clean_project() {
+    let temp_base = setup_test_vcp_folder();
+    let root = temp_base.path();
+    create_mock_image(&root.join("images"), "logo.svg");
+    let cnc = TempDir::new().expect("Failed to create temp directory");
+    let entries = [ExportEntry {
+        document: parse_vcp(SKIN).unwrap(),
+        target: Some(SkinTarget::File("panel.vcp".to_string())),
+        source: None,
+    }];
+    let zip_path = PathBuf::from(
+        export_project(root, &cnc.path().to_string_lossy(), &entries, None)
+            .unwrap()
+            .zip,
+    );
+
+    let wip = TempDir::new().expect("Failed to create temp directory");
+    let report = import_cnc(&zip_path, wip.path(), false).unwrap();
+
+    assert_eq!(report.files, ["images/logo.svg", "skins/panel.vcp"]);
+    assert_eq!(report.rewritten.len(), 1);
+    assert_eq!(report.rewritten[0].skin, "skins/panel.vcp");
+    assert_eq!(report.rewritten[0].to, "images/logo.svg");
+    assert_file_contains(
+        &wip.path().join("skins/panel.vcp"),
+        "<path>images/logo.svg</path>",
+    );
+    assert_file_exists(&wip.path().join("images/logo.svg"));
+    assert_dir_exists(&wip.path().join("Buttons"));
+    assert!(!wip.path().join(MANIFEST_FILE).exists());
+
+    // Importing again would replace the files
+    let error = import_cnc(&zip_path, wip.path(), false).unwrap_err();
+    assert!(error.contains("already exists"), "{}", error);
+    assert!(import_cnc(&zip_path, wip.path(), true).is_ok());
+}
+
+#[test]
+fn test_deployed_folder_and_foreign_zips_import() {
+    let skin = SKIN.replace("images/logo.svg", r"C:\cncm\resources\vcp\images\logo.svg");
+
+    // A vcp folder copied off the CNC, with the OS's litter in it
+    let deployed = TempDir::new().expect("Failed to create temp directory");
+    let vcp = deployed.path().join("vcp");
+    fs::create_dir_all(vcp.join("skins")).unwrap();
+    fs::create_dir_all(vcp.join("images")).unwrap();
+    fs::write(vcp.join("skins/acorn_mill_vcp_skin.vcp"), &skin).unwrap();
+    fs::write(vcp.join("images/logo.svg"), "<svg/>").unwrap();
+    fs::write(vcp.join("images/Thumbs.db"), "x").unwrap();
+    let wip = TempDir::new().expect("Failed to create temp directory");
+    let report = import_cnc(deployed.path(), wip.path(), false).unwrap();
+    assert_eq!(
+        report.files,
+        ["images/logo.svg", "skins/acorn_mill_vcp_skin.vcp"]
+    );
+    assert_file_contains(
+        &wip.path().join("skins/acorn_mill_vcp_skin.vcp"),
+        "<path>images/logo.svg</path>",
+    );
+    assert_dir_exists(&wip.path().join("Buttons"));
+
+    // A zip of the folder's contents, with Windows separators
+    let zips = TempDir::new().expect("Failed to create temp directory");
+    let flat = zips.path().join("backup.zip");
+    write_zip(
+        &flat,
+        &[(r"skins\panel.vcp", &skin), ("images/logo.svg", "<svg/>")],
+    );
+    let wip = TempDir::new().expect("Failed to create temp directory");
+    let report = import_cnc(&flat, wip.path(), false).unwrap();
+    assert_eq!(report.files, ["skins/panel.vcp", "images/logo.svg"]);
+
+    // Entries must stay inside the project, and there must be a skin
+    let evil = zips.path().join("evil.zip");
+    write_zip(&evil, &[("skins/panel.vcp", &skin), ("../escape.txt", "x")]);
+    assert!(import_cnc(&evil, wip.path(), true).is_err());
+    assert!(!wip.path().parent().unwrap().join("escape.txt").exists());
+    let empty = zips.path().join("empty.zip");
+    write_zip(&empty, &[("images/logo.svg", "<svg/>")]);
+    let error = import_cnc(&empty, wip.path(), true).unwrap_err();
+    assert!(error.contains("No skins"), "{}", error);
+}
+
+#[test]
+fn test_only_escaped_path_elements_are_rewritten() {
+    let image = |path: &str| {
+        format!(
+            "    <image>\n        <row_start>1</row_start>\n        <column_start>1</column_start>\n        <row_span>1</row_span>\n        <column_span>1</column_span>\n        <path>{}</path>\n    </image>\n",
+            path
+        )
+    };
+    let skin = format!(
+        "<vcp_skin>\n    <!-- was <path>C:\\old\\images\\x.svg</path> -->\n{}{}</vcp_skin>\n",
+        image(r"C:\cncm\resources\vcp\images\R&amp;D.svg"),
+        image(r"<![CDATA[C:\cncm\resources\vcp\images\<b>.svg]]>"),
+    );
+    let zips = TempDir::new().expect("Failed to create temp directory");
+    let zip_path = zips.path().join("backup.zip");
+    write_zip(&zip_path, &[("skins/panel.vcp", &skin)]);
+
+    let wip = TempDir::new().expect("Failed to create temp directory");
+    let report = import_cnc(&zip_path, wip.path(), false).unwrap();
+
+    let rewritten: Vec<&str> = report
+        .rewritten
+        .iter()
+        .map(|path| path.to.as_str())
+        .collect();
+    assert_eq!(rewritten, ["images/R&D.svg", "images/<b>.svg"]);
+    let content = fs::read_to_string(wip.path().join("skins/panel.vcp")).unwrap();
+    assert!(content.contains(r"<!-- was <path>C:\old\images\x.svg</path> -->"));
+    assert!(content.contains("<path>images/R&amp;D.svg</path>"));
+    assert!(content.contains("<path>images/&lt;b&gt;.svg</path>"));
+    parse_vcp(&content).unwrap();
+}
+
+#[test]
+fn test_failed_import_leaves_destination_alone() {
+    // The image's data no longer matches its checksum, so reading it fails
+    // after the skin has been written
+    let zips = TempDir::new().expect("Failed to create temp directory");
+    let zip_path = zips.path().join("broken.zip");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+    let stored =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for (name, content) in [
+        ("skins/panel.vcp", SKIN),
+        ("images/logo.svg", "<svg>good</svg>"),
+    ] {
+        zip.start_file(name, stored).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+    let mut bytes = fs::read(&zip_path).unwrap();
+    let start = bytes
+        .windows(4)
+        .position(|window| window == b"good")
+        .unwrap();
+    bytes[start..start + 4].copy_from_slice(b"evil");
+    fs::write(&zip_path, bytes).unwrap();
+
+    let parent = TempDir::new().expect("Failed to create temp directory");
+    let fresh = parent.path().join("fresh");
+    let error = import_cnc(&zip_path, &fresh, false).unwrap_err();
+    assert!(error.contains("images/logo.svg"), "{}", error);
+    assert!(!fresh.exists());
+
+    let existing = parent.path().join("existing");
+    fs::create_dir_all(existing.join("skins")).unwrap();
+    fs::write(existing.join("skins/panel.vcp"), "old").unwrap();
+    assert!(import_cnc(&zip_path, &existing, true).is_err());
+    assert_eq!(
+        fs::read_to_string(existing.join("skins/panel.vcp")).unwrap(),
+        "old"
+    );
+    assert!(!existing.join("images").exists());
+
+    // No staging folders are left behind
+    let mut names: Vec<String> = fs::read_dir(parent.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["existing"]);
+}
+
+#[test]
+fn test_overwrite_swaps_in_the_whole_folder() {
+    let zips = TempDir::new().expect("Failed to create temp directory");
+    let zip_path = zips.path().join("backup.zip");
+    write_zip(
+        &zip_path,
+        &[("skins/panel.vcp", SKIN), ("images/logo.svg", "<svg/>")],
+    );
+
+    let parent = TempDir::new().expect("Failed to create temp directory");
+    let existing = parent.path().join("existing");
+    fs::create_dir_all(existing.join("skins")).unwrap();
+    fs::write(existing.join("skins/panel.vcp"), "old").unwrap();
+    fs::write(existing.join("notes.txt"), "keep me").unwrap();
+    import_cnc(&zip_path, &existing, true).unwrap();
+    assert_file_contains(&existing.join("skins/panel.vcp"), "<vcp_skin>");
+    assert_file_contains(&existing.join("images/logo.svg"), "<svg/>");
+    assert_file_contains(&existing.join("notes.txt"), "keep me");
+
+    let fresh = parent.path().join("fresh");
+    import_cnc(&zip_path, &fresh, false).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&fresh).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    // Neither the staging folder nor the replaced one is left behind
+    let mut names: Vec<String> = fs::read_dir(parent.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["existing", "fresh"]);
+}
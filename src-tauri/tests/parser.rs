@@ -0,0 +1,215 @@
+// Integration tests for VCP skin parsing
+use vcp_editor_lib::backend::models::Severity;
+use vcp_editor_lib::backend::parser::{parse_vcp, parse_vcp_with_diagnostics, serialize_vcp};
+
+#[test]
+fn test_parse_canonical_layout() {
+    let xml = r#"<vcp_skin>
+    <background>#112233</background>
+    <column_count>8</column_count>
+    <row_count>12</row_count>
+    <border>
+        <column_span>2</column_span>
+        <column_start>3</column_start>
+        <fill>Transparent</fill>
+        <row_span>4</row_span>
+        <row_start>5</row_start>
+        <outline_color>#ff0000</outline_color>
+        <outline_thickness>2</outline_thickness>
+    </border>
+    <image>
+        <column_span>1</column_span>
+        <column_start>1</column_start>
+        <row_span>1</row_span>
+        <row_start>1</row_start>
+        <path>images/logo.svg</path>
+    </image>
+    <button row="2" column="3">cycle_start</button>
+</vcp_skin>
+"#;
+
+    let doc = parse_vcp(xml).expect("Failed to parse skin");
+
+    assert_eq!(doc.background, "#112233");
+    assert_eq!(doc.column_count, 8);
+    assert_eq!(doc.row_count, 12);
+    assert_eq!(doc.borders.len(), 1);
+    assert_eq!(doc.borders[0].row_start, 5);
+    assert_eq!(doc.borders[0].column_span, 2);
+    assert_eq!(doc.borders[0].outline_thickness, 2);
+    assert_eq!(doc.images[0].path, "images/logo.svg");
+    assert_eq!(doc.buttons[0].name, "cycle_start");
+    assert_eq!(doc.buttons[0].row, 2);
+    assert_eq!(doc.buttons[0].column, 3);
+}
+
+#[test]
+fn test_parse_reformatted_skin() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Reformatted by a text editor -->
+<vcp_skin><background>#000000</background><column_count>
+    4
+</column_count>
+<row_count>3</row_count>
+<button
+    column_span="2"
+    column="4"
+    row="1">feed_hold</button>
+<border><row_start>2</row_start><column_start>1</column_start>
+<plc_word><number>7</number><percentage>true</percentage></plc_word></border>
+</vcp_skin>"#;
+
+    let doc = parse_vcp(xml).expect("Failed to parse skin");
+
+    assert_eq!(doc.column_count, 4);
+    assert_eq!(doc.row_count, 3);
+    assert_eq!(doc.buttons.len(), 1);
+    assert_eq!(doc.buttons[0].row, 1);
+    assert_eq!(doc.buttons[0].column, 4);
+    assert_eq!(doc.buttons[0].column_span, Some(2));
+    assert_eq!(doc.buttons[0].row_span, None);
+    assert_eq!(doc.buttons[0].name, "feed_hold");
+
+    let plc = doc.borders[0].plc_word.as_ref().expect("Missing plc_word");
+    assert_eq!(plc.number, 7);
+    assert!(plc.percentage);
+}
+
+#[test]
+fn test_parse_entities_cdata_and_comments() {
+    let xml = r#"<vcp_skin>
+    <!-- <button row="9" column="9">commented_out</button> -->
+    <image>
+        <path><![CDATA[images/a&b.svg]]></path>
+    </image>
+    <button row="1" column="1">Jog&amp;Step</button>
+</vcp_skin>"#;
+
+    let doc = parse_vcp(xml).expect("Failed to parse skin");
+
+    assert_eq!(doc.images[0].path, "images/a&b.svg");
+    assert_eq!(doc.buttons.len(), 1);
+    assert_eq!(doc.buttons[0].name, "Jog&Step");
+}
+
+#[test]
+fn test_parse_malformed_xml_reports_position() {
+    let xml = "<vcp_skin>\n    <background>#000000</row_count>\n</vcp_skin>";
+
+    let err = parse_vcp(xml).expect_err("Mismatched tags should fail");
+
+    assert!(err.contains("line 2"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_serialized_output_parses_back() {
+    let xml = r#"<vcp_skin><column_count>5</column_count><row_count>7</row_count>
+<button row="3" column="2" row_span="2">spindle</button></vcp_skin>"#;
+
+    let doc = parse_vcp(xml).expect("Failed to parse skin");
+    let reparsed = parse_vcp(&serialize_vcp(&doc)).expect("Failed to reparse skin");
+
+    assert_eq!(reparsed.column_count, 5);
+    assert_eq!(reparsed.row_count, 7);
+    assert_eq!(reparsed.buttons[0].row_span, Some(2));
+    assert_eq!(reparsed.buttons[0].name, "spindle");
+}
+
+#[test]
+fn test_diagnostics_report_malformed_number_position() {
+    let xml = "<vcp_skin>\n    <background>#000000</background>\n    <column_count>6</column_count>\n    <row_count>14</row_count>\n    <image>\n        <row_start>1O</row_start>\n        <column_start>2</column_start>\n        <row_span>1</row_span>\n        <column_span>1</column_span>\n        <path>images/a.svg</path>\n    </image>\n</vcp_skin>";
+
+    let loaded = parse_vcp_with_diagnostics(xml).expect("Failed to parse skin");
+
+    assert_eq!(loaded.document.images[0].row_start, 1);
+    assert_eq!(loaded.diagnostics.len(), 1);
+    let diagnostic = &loaded.diagnostics[0];
+    assert_eq!(diagnostic.severity, Severity::Warning);
+    assert_eq!((diagnostic.line, diagnostic.column), (6, 9));
+    assert!(
+        diagnostic.message.contains("'1O'"),
+        "Unexpected message: {}",
+        diagnostic.message
+    );
+}
+
+#[test]
+fn test_diagnostics_report_unknown_and_defaulted_values() {
+    let xml = r#"<vcp_skin>
+    <background>#000000</background>
+    <column_count>6</column_count>
+    <row_count>14</row_count>
+    <future_tag>1</future_tag>
+    <button row="2" colum="3">typo</button>
+</vcp_skin>"#;
+
+    let loaded = parse_vcp_with_diagnostics(xml).expect("Failed to parse skin");
+    let messages: Vec<&str> = loaded
+        .diagnostics
+        .iter()
+        .map(|d| d.message.as_str())
+        .collect();
+
+    assert!(messages
+        .iter()
+        .any(|m| m.contains("Unknown element <future_tag>")));
+    assert!(messages
+        .iter()
+        .any(|m| m.contains("Unknown attribute colum")));
+    assert!(messages
+        .iter()
+        .any(|m| m.contains("Missing attribute column")));
+    assert_eq!(loaded.document.buttons[0].column, 1);
+}
+
+#[test]
+fn test_diagnostics_empty_for_clean_skin() {
+    let doc = parse_vcp(&serialize_vcp(&Default::default())).expect("Failed to parse skin");
+    let loaded = parse_vcp_with_diagnostics(&serialize_vcp(&doc)).expect("Failed to parse skin");
+
+    assert!(loaded.diagnostics.is_empty(), "{:?}", loaded.diagnostics);
+}
+
+#[test]
+fn test_serialize_escapes_metacharacters() {
+    let mut doc = parse_vcp(&serialize_vcp(&Default::default())).expect("Failed to parse skin");
+    doc.background = "#000000".to_string();
+    doc.images = parse_vcp("<vcp_skin><image><path>x</path></image></vcp_skin>")
+        .expect("Failed to parse skin")
+        .images;
+    doc.images[0].path = r#"images/<a> & "b".svg"#.to_string();
+    doc.buttons = parse_vcp(r#"<vcp_skin><button row="1" column="1">x</button></vcp_skin>"#)
+        .expect("Failed to parse skin")
+        .buttons;
+    doc.buttons[0].name = "Jog&Step".to_string();
+
+    let xml = serialize_vcp(&doc);
+
+    assert!(xml.contains(">Jog&amp;Step</button>"), "{}", xml);
+    assert!(
+        xml.contains("<path>images/&lt;a&gt; &amp; \"b\".svg</path>"),
+        "{}",
+        xml
+    );
+    let reparsed = parse_vcp(&xml).expect("Serialized skin should be valid XML");
+    assert_eq!(reparsed.buttons[0].name, "Jog&Step");
+    assert_eq!(reparsed.images[0].path, r#"images/<a> & "b".svg"#);
+}
+
+#[test]
+fn test_non_ascii_text_round_trips() {
+    let xml = "<vcp_skin>\n    <border>\n        <plc_word>\n            <number>3</number>\n            <font>Schriftart Ü</font>\n        </plc_word>\n    </border>\n    <button row=\"1\" column=\"1\">Broche_été</button>\n</vcp_skin>";
+
+    let mut doc = parse_vcp(xml).expect("Failed to parse skin");
+    assert_eq!(doc.buttons[0].name, "Broche_été");
+    doc.buttons[0].name = "主轴 &lt;1&gt;".to_string();
+
+    let reparsed = parse_vcp(&serialize_vcp(&doc)).expect("Failed to reparse skin");
+
+    assert_eq!(reparsed.buttons[0].name, "主轴 &lt;1&gt;");
+    let plc = reparsed.borders[0]
+        .plc_word
+        .as_ref()
+        .expect("Missing plc_word");
+    assert_eq!(plc.font, "Schriftart Ü");
+}
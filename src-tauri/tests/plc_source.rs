@@ -0,0 +1,213 @@
+// Integration tests for PLC source parsing
+use std::path::PathBuf;
+use vcp_editor_lib::backend::models::{
+    Button, ButtonBinding, PlcInput, PlcOutput, PlcSymbolKind, Severity, VcpButton, VcpDocument,
+};
+use vcp_editor_lib::backend::plc::{cross_check, load_plc_source, parse_plc_source};
+
+fn button(name: &str, definition: Option<VcpButton>) -> Button {
+    Button {
+        row: 1,
+        column: 1,
+        row_span: None,
+        column_span: None,
+        name: name.to_string(),
+        file: None,
+        default_image: None,
+        definition,
+        source: None,
+    }
+}
+
+fn skin_events_list() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../specimens/SkinEventsList.txt")
+}
+
+#[test]
+fn test_skin_events_list_specimen() {
+    let definitions = load_plc_source(&skin_events_list()).expect("Failed to read specimen");
+
+    assert_eq!(definitions.skin_events.len(), 97);
+    let first = &definitions.skin_events[0];
+    assert_eq!(first.name, "SkinSpinOverPlus_M");
+    assert_eq!(
+        (first.number, first.row, first.column),
+        (1, Some(1), Some(1))
+    );
+    let cycle_start = definitions
+        .skin_events
+        .iter()
+        .find(|event| event.number == 50)
+        .unwrap();
+    assert_eq!((cycle_start.row, cycle_start.column), (None, None));
+    assert!(definitions.skin_events.iter().all(|event| !event.commented));
+}
+
+#[test]
+fn test_tab_aligned_and_miscased_events() {
+    let definitions = load_plc_source(&skin_events_list()).expect("Failed to read specimen");
+
+    let turret = definitions
+        .skin_events
+        .iter()
+        .find(|event| event.name == "SkinTurretIndex_M")
+        .unwrap();
+    assert_eq!(turret.number, 85);
+    let tail_stock = definitions
+        .skin_events
+        .iter()
+        .find(|event| event.name == "SkinTailStock_M")
+        .unwrap();
+    assert_eq!(
+        (tail_stock.number, tail_stock.row, tail_stock.column),
+        (70, Some(3), Some(5))
+    );
+
+    let miscased: Vec<usize> = definitions
+        .diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Info)
+        .map(|d| d.line)
+        .collect();
+    assert_eq!(
+        miscased,
+        vec![turret.line, definitions.skin_events[96].line]
+    );
+}
+
+#[test]
+fn test_commented_memory_symbols() {
+    let definitions = load_plc_source(&skin_events_list()).expect("Failed to read specimen");
+
+    let names: Vec<&str> = definitions
+        .symbols
+        .iter()
+        .map(|symbol| symbol.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["SetMem_M", "RstMem_M", "SetOut_M", "RstOut_M"]);
+    let set_mem = &definitions.symbols[0];
+    assert_eq!((set_mem.kind, set_mem.number), (PlcSymbolKind::Mem, 795));
+    assert!(set_mem.commented);
+    assert_eq!(set_mem.comment.as_deref(), Some("Used for skinning"));
+}
+
+#[test]
+fn test_plc_program_symbols() {
+    let source = "; Acorn PLC program\n\
+        SpinOverPlusKey   IS INP1057 ; Key 1\n\
+        SpinOverPlusLED\tis\tout1057\n\
+        Flood_O           IS OUT3\n\
+        ;Mist_O           IS OUT4 ; not wired\n\
+        SkinFoo_M         IS SV_SKIN_EVENT_X\n\
+        Timer1            IS T1\n\
+        IF SkinCoolFlood_M THEN (Flood_O)\n";
+
+    let definitions = parse_plc_source(source);
+
+    let symbols: Vec<(&str, PlcSymbolKind, i32, bool)> = definitions
+        .symbols
+        .iter()
+        .map(|s| (s.name.as_str(), s.kind, s.number, s.commented))
+        .collect();
+    assert_eq!(
+        symbols,
+        vec![
+            ("SpinOverPlusKey", PlcSymbolKind::Inp, 1057, false),
+            ("SpinOverPlusLED", PlcSymbolKind::Out, 1057, false),
+            ("Flood_O", PlcSymbolKind::Out, 3, false),
+            ("Mist_O", PlcSymbolKind::Out, 4, true),
+        ]
+    );
+    assert!(definitions.skin_events.is_empty());
+    assert_eq!(definitions.diagnostics.len(), 1);
+    assert_eq!(definitions.diagnostics[0].severity, Severity::Warning);
+    assert_eq!(
+        (
+            definitions.diagnostics[0].line,
+            definitions.diagnostics[0].column
+        ),
+        (6, 22)
+    );
+}
+
+#[test]
+fn test_diagnostic_column_points_at_the_target() {
+    // The target's text also appears in the name, and after a tab
+    let source =
+        "SV_Skin_EVENT_85_M IS\tSV_Skin_EVENT_85\n\u{e9}SV_SKIN_EVENT_x IS SV_SKIN_EVENT_x\n";
+    let definitions = parse_plc_source(source);
+
+    let positions: Vec<(usize, usize, Severity)> = definitions
+        .diagnostics
+        .iter()
+        .map(|d| (d.line, d.column, d.severity))
+        .collect();
+    assert_eq!(
+        positions,
+        vec![(1, 23, Severity::Info), (2, 21, Severity::Warning)]
+    );
+}
+
+#[test]
+fn test_cross_check_against_plc_program() {
+    let source = "SkinCoolFlood_M IS SV_SKIN_EVENT_22\n\
+        SkinCoolMist_M IS SV_SKIN_EVENT_23\n\
+        ;SkinOld_M IS SV_SKIN_EVENT_24\n\
+        KeepAlive_M IS SV_SKIN_EVENT_55\n\
+        FloodLED IS OUT1078\n\
+        FloodKey IS INP1078\n";
+    let definitions = parse_plc_source(source);
+    let flood = VcpButton {
+        skin_event_num: Some(22),
+        plc_output: Some(PlcOutput {
+            number: 1078,
+            color_on: "#EC1C24".to_string(),
+            color_off: "#81151C".to_string(),
+            image_on: None,
+            image_off: None,
+        }),
+        ..Default::default()
+    };
+    let old = VcpButton {
+        skin_event_num: Some(24),
+        plc_output: Some(PlcOutput {
+            number: 1079,
+            color_on: "#EC1C24".to_string(),
+            color_off: "#81151C".to_string(),
+            image_on: None,
+            image_off: None,
+        }),
+        plc_input: Some(PlcInput {
+            number: 1080,
+            image_active: None,
+            image_inactive: None,
+        }),
+        ..Default::default()
+    };
+    let doc = VcpDocument {
+        buttons: vec![
+            button("flood", Some(flood.clone())),
+            button("old", Some(old)),
+            button("flood", Some(flood)),
+            button("logo", None),
+        ],
+        ..Default::default()
+    };
+
+    let report = cross_check(&doc, &definitions);
+
+    let binding = |button: &str, number| ButtonBinding {
+        button: button.to_string(),
+        number,
+    };
+    assert_eq!(report.undefined_events, vec![binding("old", 24)]);
+    assert_eq!(report.undefined_outputs, vec![binding("old", 1079)]);
+    assert_eq!(report.undeclared_inputs, vec![binding("old", 1080)]);
+    let untriggered: Vec<&str> = report
+        .untriggered_events
+        .iter()
+        .map(|event| event.name.as_str())
+        .collect();
+    assert_eq!(untriggered, vec!["SkinCoolMist_M"]);
+    assert_eq!(report.unresolved_buttons, vec!["logo"]);
+}
@@ -0,0 +1,132 @@
+// Integration tests for PLC source stub generation
+use vcp_editor_lib::backend::catalog::{builtin_catalog, catalog_from_plc};
+use vcp_editor_lib::backend::models::{Button, PlcOutput, VcpButton, VcpDocument};
+use vcp_editor_lib::backend::plc::parse_plc_source;
+use vcp_editor_lib::backend::plc_stub::generate_plc_stub;
+
+fn button(name: &str, event: i32, output: Option<i32>) -> Button {
+    Button {
+        row: 1,
+        column: 1,
+        row_span: None,
+        column_span: None,
+        name: name.to_string(),
+        file: None,
+        default_image: None,
+        definition: Some(VcpButton {
+            skin_event_num: Some(event),
+            plc_output: output.map(|number| PlcOutput {
+                number,
+                color_on: "#EC1C24".to_string(),
+                color_off: "#81151C".to_string(),
+                image_on: None,
+                image_off: None,
+            }),
+            ..Default::default()
+        }),
+        source: None,
+    }
+}
+
+#[test]
+fn test_stub_for_custom_events() {
+    let doc = VcpDocument {
+        buttons: vec![
+            button("vacuum_pump", 211, Some(12)),
+            button("spin_cw", 7, Some(1063)),
+            button("door-lock", 210, Some(1057)),
+            button("probe", 212, None),
+        ],
+        ..Default::default()
+    };
+
+    let stub = generate_plc_stub(&doc, &builtin_catalog());
+
+    assert_eq!(
+        stub,
+        ";--- Skin events added by VCP Editor\n\
+         SkinDoorLock_M                IS SV_SKIN_EVENT_210  ; Button door-lock\n\
+         SkinVacuumPump_M              IS SV_SKIN_EVENT_211  ; Button vacuum_pump\n\
+         SkinProbe_M                   IS SV_SKIN_EVENT_212  ; Button probe\n\
+         \n\
+         ;--- Button LED outputs\n\
+         VacuumPumpLED                 IS OUT12              ; Button vacuum_pump\n\
+         \n\
+         ;--- Toggle logic, to be placed in the program body\n\
+         ;--- door-lock (skin event 210)\n\
+         IF (SkinDoorLock_M && !SpinOverPlusLED) || (!SkinDoorLock_M && SpinOverPlusLED) THEN (SpinOverPlusLED)\n\
+         \n\
+         ;--- vacuum_pump (skin event 211)\n\
+         IF (SkinVacuumPump_M && !VacuumPumpLED) || (!SkinVacuumPump_M && VacuumPumpLED) THEN (VacuumPumpLED)\n\
+         \n\
+         ;--- probe (skin event 212)\n\
+         ;IF SkinProbe_M THEN (...)\n"
+    );
+}
+
+#[test]
+fn test_stub_is_empty_when_plc_defines_every_event() {
+    let doc = VcpDocument {
+        buttons: vec![button("vacuum_pump", 210, None)],
+        ..Default::default()
+    };
+    let plc = parse_plc_source("SkinVac_M IS SV_SKIN_EVENT_210\n");
+    let known = catalog_from_plc(&plc, &Default::default());
+
+    assert_eq!(generate_plc_stub(&doc, &known), "");
+    assert!(generate_plc_stub(&doc, &builtin_catalog()).contains("SV_SKIN_EVENT_210"));
+}
+
+#[test]
+fn test_stub_symbols_never_clash() {
+    let doc = VcpDocument {
+        buttons: vec![
+            button("vac pump", 210, Some(20)),
+            button("vac-pump", 211, Some(20)),
+            button("!!!", 212, Some(21)),
+            button("CoolFlood", 213, None),
+            button("3d probe", 214, Some(22)),
+        ],
+        ..Default::default()
+    };
+
+    let stub = generate_plc_stub(&doc, &builtin_catalog());
+
+    assert_eq!(
+        stub,
+        ";--- Skin events added by VCP Editor\n\
+         SkinVacPump_M                 IS SV_SKIN_EVENT_210  ; Button vac pump\n\
+         SkinVacPump211_M              IS SV_SKIN_EVENT_211  ; Button vac-pump\n\
+         SkinButton212_M               IS SV_SKIN_EVENT_212  ; Button !!!\n\
+         SkinCoolFlood213_M            IS SV_SKIN_EVENT_213  ; Button CoolFlood\n\
+         SkinButton3dProbe_M           IS SV_SKIN_EVENT_214  ; Button 3d probe\n\
+         \n\
+         ;--- Button LED outputs\n\
+         VacPumpLED                    IS OUT20              ; Button vac pump\n\
+         Button212LED                  IS OUT21              ; Button !!!\n\
+         Button3dProbeLED              IS OUT22              ; Button 3d probe\n\
+         \n\
+         ;--- Toggle logic, to be placed in the program body\n\
+         ;--- vac pump (skin event 210)\n\
+         IF (SkinVacPump_M && !VacPumpLED) || (!SkinVacPump_M && VacPumpLED) THEN (VacPumpLED)\n\
+         \n\
+         ;--- vac-pump (skin event 211)\n\
+         IF (SkinVacPump211_M && !VacPumpLED) || (!SkinVacPump211_M && VacPumpLED) THEN (VacPumpLED)\n\
+         \n\
+         ;--- !!! (skin event 212)\n\
+         IF (SkinButton212_M && !Button212LED) || (!SkinButton212_M && Button212LED) THEN (Button212LED)\n\
+         \n\
+         ;--- CoolFlood (skin event 213)\n\
+         ;IF SkinCoolFlood213_M THEN (...)\n\
+         \n\
+         ;--- 3d probe (skin event 214)\n\
+         IF (SkinButton3dProbe_M && !Button3dProbeLED) || (!SkinButton3dProbe_M && Button3dProbeLED) THEN (Button3dProbeLED)\n"
+    );
+
+    // Names the PLC program already uses are taken too, whatever their case
+    let plc = parse_plc_source("vacpumpled IS OUT99\n");
+    let known = catalog_from_plc(&plc, &Default::default());
+    let stub = generate_plc_stub(&doc, &known);
+    assert!(stub.contains("VacPump210LED                 IS OUT20"));
+    assert!(stub.contains("SkinCoolFlood_M "));
+}
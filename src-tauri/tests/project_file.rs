@@ -0,0 +1,137 @@
+// Integration tests for the JSON/YAML project interchange format
+mod common;
+
+use common::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use vcp_editor_lib::backend::button::{load_button, save_button};
+use vcp_editor_lib::backend::models::{ButtonRun, DataFormat, PlcOutput, VcpButton};
+use vcp_editor_lib::backend::project::{
+    from_data, load_project_file, read_project, save_project_file, to_data, write_project,
+};
+
+/// A project with both specimen skins and two button definitions.
+fn specimen_project(root: &Path) {
+    let specimens = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../specimens/skins");
+    for skin in ["acorn_mill_vcp_skin.vcp", "hand_edited_skin.vcp"] {
+        fs::copy(specimens.join(skin), root.join("skins").join(skin))
+            .expect("Failed to copy specimen skin");
+    }
+    let buttons = [
+        VcpButton {
+            name: "spin_cw".to_string(),
+            skin_event_num: Some(46),
+            default_image: Some("spin_cw.svg".to_string()),
+            plc_output: Some(PlcOutput {
+                number: 46,
+                color_on: "#00FF00".to_string(),
+                color_off: "#000000".to_string(),
+                image_on: None,
+                image_off: None,
+            }),
+            ..Default::default()
+        },
+        VcpButton {
+            name: "probe".to_string(),
+            run: Some(ButtonRun::Macro("probe.mac".to_string())),
+            ..Default::default()
+        },
+    ];
+    for button in buttons {
+        let folder = root.join("Buttons").join(&button.name);
+        fs::create_dir_all(&folder).expect("Failed to create button folder");
+        save_button(&folder, &button).expect("Failed to save button");
+    }
+}
+
+#[test]
+fn test_project_round_trips_through_yaml_byte_for_byte() {
+    let source = setup_test_vcp_folder();
+    specimen_project(source.path());
+    let target = setup_test_vcp_folder();
+
+    let yaml = to_data(
+        &read_project(source.path(), true).unwrap(),
+        DataFormat::Yaml,
+    )
+    .unwrap();
+    let written = write_project(target.path(), &from_data(&yaml, DataFormat::Yaml).unwrap())
+        .expect("Failed to write project");
+
+    assert_eq!(written.len(), 4);
+    for skin in ["acorn_mill_vcp_skin.vcp", "hand_edited_skin.vcp"] {
+        let skin = Path::new("skins").join(skin);
+        assert_eq!(
+            fs::read(target.path().join(&skin)).unwrap(),
+            fs::read(source.path().join(&skin)).unwrap()
+        );
+    }
+    for name in ["spin_cw", "probe"] {
+        assert_eq!(
+            load_button(target.path(), name).unwrap(),
+            load_button(source.path(), name).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_project_file_without_layout_keeps_content() {
+    let source = setup_test_vcp_folder();
+    specimen_project(source.path());
+    let target = setup_test_vcp_folder();
+    let file = source.path().join("project.json");
+
+    save_project_file(&file, &read_project(source.path(), false).unwrap()).unwrap();
+    let content = fs::read_to_string(&file).unwrap();
+    assert!(!content.contains("\"layout\""));
+    assert!(!content.contains("\"source\""));
+    write_project(target.path(), &load_project_file(&file).unwrap()).unwrap();
+
+    let before = serde_json::to_value(read_project(source.path(), false).unwrap()).unwrap();
+    let after = serde_json::to_value(read_project(target.path(), false).unwrap()).unwrap();
+    assert_eq!(before["buttons"], after["buttons"]);
+    for skin in 0..2 {
+        let (before, after) = (&before["skins"][skin], &after["skins"][skin]);
+        assert_eq!(before["file"], after["file"]);
+        for field in [
+            "background",
+            "column_count",
+            "row_count",
+            "borders",
+            "images",
+            "buttons",
+        ] {
+            assert_eq!(
+                before["document"][field], after["document"][field],
+                "{}",
+                field
+            );
+        }
+    }
+}
+
+#[test]
+fn test_project_file_is_checked_before_writing() {
+    let target = setup_test_vcp_folder();
+    let project = |body: &str| from_data(&format!("version: 1\n{}", body), DataFormat::Yaml);
+
+    let escaping = project("buttons:\n  - name: ../outside\n").unwrap();
+    assert!(write_project(target.path(), &escaping).is_err());
+    let not_a_skin = project(
+        "skins:\n  - file: main.xml\n    document: {background: '#000000', column_count: 1, row_count: 1}\n",
+    )
+    .unwrap();
+    assert!(write_project(target.path(), &not_a_skin).is_err());
+    assert!(fs::read_dir(target.path().join("Buttons"))
+        .unwrap()
+        .next()
+        .is_none());
+
+    let error = from_data("{\"version\": 2}", DataFormat::Json).unwrap_err();
+    assert!(error.contains("version 2"));
+    assert!(DataFormat::from_path(Path::new("project.txt")).is_err());
+    assert_eq!(
+        DataFormat::from_path(Path::new("Panel.YML")).unwrap(),
+        DataFormat::Yaml
+    );
+}
@@ -0,0 +1,142 @@
+// Integration tests for lossless load/save round trips
+mod common;
+
+use common::*;
+use std::fs;
+use std::path::PathBuf;
+use vcp_editor_lib::backend::models::{Border, Button, SerializeOptions, VcpDocument};
+use vcp_editor_lib::backend::parser::{load_file, parse_vcp, save_file, serialize_vcp};
+
+fn specimen(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../specimens/skins")
+        .join(name);
+    fs::read_to_string(&path).expect("Failed to read specimen skin")
+}
+
+fn specimens() -> Vec<String> {
+    vec![
+        specimen("acorn_mill_vcp_skin.vcp"),
+        specimen("hand_edited_skin.vcp"),
+    ]
+}
+
+fn new_button(row: i32, column: i32, name: &str) -> Button {
+    Button {
+        row,
+        column,
+        row_span: None,
+        column_span: None,
+        name: name.to_string(),
+        file: None,
+        default_image: None,
+        definition: None,
+        source: None,
+    }
+}
+
+#[test]
+fn test_untouched_specimens_are_byte_identical() {
+    for content in specimens() {
+        let doc = parse_vcp(&content).expect("Failed to parse specimen");
+        assert_eq!(serialize_vcp(&doc), content);
+    }
+}
+
+#[test]
+fn test_round_trip_survives_json_transport() {
+    // The frontend holds documents as JSON between load and save
+    for content in specimens() {
+        let doc = parse_vcp(&content).expect("Failed to parse specimen");
+        let json = serde_json::to_string(&doc).expect("Failed to serialize to JSON");
+        let doc: VcpDocument = serde_json::from_str(&json).expect("Failed to read JSON");
+        assert_eq!(serialize_vcp(&doc), content);
+    }
+}
+
+#[test]
+fn test_load_and_save_file_is_byte_identical() {
+    let temp_base = setup_test_vcp_folder();
+    let skin_path = temp_base.path().join("skins/acorn_mill_vcp_skin.vcp");
+    let content = specimen("acorn_mill_vcp_skin.vcp");
+    fs::write(&skin_path, &content).expect("Failed to write skin");
+
+    let path = skin_path.to_str().unwrap();
+    let loaded = load_file(path).expect("Failed to load skin");
+    save_file(path, &loaded.document, &SerializeOptions::default()).expect("Failed to save skin");
+
+    assert_eq!(fs::read(&skin_path).unwrap(), content.as_bytes());
+}
+
+#[test]
+fn test_editing_one_button_only_changes_that_button() {
+    let content = specimen("hand_edited_skin.vcp");
+    let mut doc = parse_vcp(&content).expect("Failed to parse specimen");
+    doc.buttons[1].row = 3;
+
+    let expected = content.replace(
+        "<button\n\t\trow=\"1\"\n\t\tcolumn=\"2\"\n\t\t>spin_ccw</button>",
+        "<button row=\"3\" column=\"2\">spin_ccw</button>",
+    );
+    assert_ne!(expected, content);
+    assert_eq!(serialize_vcp(&doc), expected);
+}
+
+#[test]
+fn test_edited_border_keeps_unknown_children() {
+    let content = specimen("hand_edited_skin.vcp");
+    let mut doc = parse_vcp(&content).expect("Failed to parse specimen");
+    doc.borders[0].fill = "#FF0000".to_string();
+
+    let output = serialize_vcp(&doc);
+
+    assert!(output.contains("<fill>#FF0000</fill>"));
+    assert!(output.contains("<corner_radius>6</corner_radius>"));
+    assert!(output.contains("<font_scale>1.25</font_scale>"));
+    assert!(output.contains("<!-- Spindle -->"));
+    let reparsed = parse_vcp(&output).expect("Failed to reparse output");
+    assert_eq!(reparsed.borders[0].fill, "#FF0000");
+}
+
+#[test]
+fn test_added_and_removed_buttons_keep_surrounding_layout() {
+    let content = specimen("hand_edited_skin.vcp");
+    let mut doc = parse_vcp(&content).expect("Failed to parse specimen");
+    doc.buttons.remove(0);
+    doc.buttons.push(new_button(5, 4, "feed_hold"));
+
+    let output = serialize_vcp(&doc);
+
+    assert!(!output.contains("spin_cw</button>"));
+    assert!(output.contains("<!-- Spindle -->\n\t<button\n"));
+    assert!(output.ends_with(
+        "\t<button row=\"6\" column=\"1\" column_span=\"4\">cycle_start</button>\n\t<button row=\"5\" column=\"4\">feed_hold</button>\n</vcp_skin>\n"
+    ));
+}
+
+#[test]
+fn test_new_element_kind_is_placed_in_canonical_position() {
+    let content = specimen("hand_edited_skin.vcp");
+    let mut doc = parse_vcp(&content).expect("Failed to parse specimen");
+    doc.borders.clear();
+    let mut doc_without_borders = parse_vcp(&serialize_vcp(&doc)).expect("Failed to reparse");
+    doc_without_borders.borders.push(Border {
+        row_start: 4,
+        column_start: 1,
+        row_span: 1,
+        column_span: 4,
+        fill: "Transparent".to_string(),
+        outline_color: "#000000".to_string(),
+        outline_thickness: 1,
+        plc_word: None,
+        source: None,
+    });
+
+    let output = serialize_vcp(&doc_without_borders);
+
+    let border = output.find("<border>").expect("Border was not written");
+    let row_count = output.find("<row_count>").unwrap();
+    let image = output.find("<image>").unwrap();
+    assert!(row_count < border && border < image);
+    assert!(!output.contains("<corner_radius>"));
+}
@@ -0,0 +1,169 @@
+// Integration tests for document validation
+use std::fs;
+use std::path::PathBuf;
+use vcp_editor_lib::backend::models::{
+    Border, Button, ElementKind, ElementRef, Image, Severity, VcpDocument,
+};
+use vcp_editor_lib::backend::parser::parse_vcp;
+use vcp_editor_lib::backend::validate::{check_document, validate_document};
+
+fn button(row: i32, column: i32, name: &str) -> Button {
+    Button {
+        row,
+        column,
+        row_span: None,
+        column_span: None,
+        name: name.to_string(),
+        file: None,
+        default_image: None,
+        definition: None,
+        source: None,
+    }
+}
+
+fn image(row_start: i32, column_start: i32, row_span: i32, column_span: i32) -> Image {
+    Image {
+        row_start,
+        column_start,
+        row_span,
+        column_span,
+        path: "images/logo.svg".to_string(),
+        source: None,
+    }
+}
+
+fn rules(doc: &VcpDocument) -> Vec<String> {
+    validate_document(doc)
+        .into_iter()
+        .map(|issue| issue.rule)
+        .collect()
+}
+
+#[test]
+fn test_specimen_skins_are_valid() {
+    for name in ["acorn_mill_vcp_skin.vcp", "hand_edited_skin.vcp"] {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../specimens/skins")
+            .join(name);
+        let content = fs::read_to_string(&path).expect("Failed to read specimen skin");
+        let doc = parse_vcp(&content).expect("Failed to parse specimen");
+        assert_eq!(validate_document(&doc), vec![], "{} has issues", name);
+    }
+}
+
+#[test]
+fn test_span_past_grid_edge_is_out_of_grid() {
+    let mut doc = VcpDocument::default();
+    let mut wide = button(14, 5, "wide");
+    wide.column_span = Some(3);
+    doc.buttons = vec![button(1, 1, "ok"), wide];
+
+    let issues = validate_document(&doc);
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].rule, "out-of-grid");
+    assert_eq!(issues[0].severity, Severity::Error);
+    assert_eq!(
+        issues[0].element,
+        Some(ElementRef {
+            kind: ElementKind::Button,
+            index: 1
+        })
+    );
+}
+
+#[test]
+fn test_huge_positions_and_spans_are_reported_not_overflowed() {
+    let mut doc = VcpDocument::default();
+    let mut wide = button(1, i32::MAX, "wide");
+    wide.column_span = Some(i32::MAX);
+    doc.buttons = vec![wide];
+    doc.images.push(image(i32::MAX, 1, i32::MAX, 1));
+    doc.images.push(image(i32::MAX, 1, i32::MAX, 1));
+
+    assert_eq!(
+        rules(&doc),
+        vec!["out-of-grid", "out-of-grid", "out-of-grid", "image-overlap"]
+    );
+}
+
+#[test]
+fn test_invalid_spans_and_grid_size() {
+    let mut doc = VcpDocument {
+        row_count: 0,
+        ..Default::default()
+    };
+    doc.borders.push(Border {
+        row_start: 1,
+        column_start: 1,
+        row_span: 0,
+        column_span: 2,
+        fill: "Transparent".to_string(),
+        outline_color: "#000000".to_string(),
+        outline_thickness: 1,
+        plc_word: None,
+        source: None,
+    });
+    doc.buttons.push(button(1, 1, ""));
+
+    assert_eq!(
+        rules(&doc),
+        vec!["grid-size", "invalid-span", "missing-name"]
+    );
+}
+
+#[test]
+fn test_buttons_sharing_a_cell_are_errors() {
+    let mut doc = VcpDocument::default();
+    let mut tall = button(1, 2, "tall");
+    tall.row_span = Some(3);
+    doc.buttons = vec![tall, button(3, 2, "low"), button(4, 2, "below")];
+
+    let issues = validate_document(&doc);
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].rule, "button-overlap");
+    assert_eq!(issues[0].element.unwrap().index, 1);
+    assert!(issues[0].message.contains("row 3, column 2"));
+}
+
+#[test]
+fn test_overlapping_images_are_warnings_and_borders_may_overlap() {
+    let mut doc = VcpDocument {
+        images: vec![image(1, 1, 2, 2)],
+        buttons: vec![button(2, 2, "under_logo")],
+        ..Default::default()
+    };
+    doc.borders.push(Border {
+        row_start: 1,
+        column_start: 1,
+        row_span: 4,
+        column_span: 6,
+        fill: "Transparent".to_string(),
+        outline_color: "#000000".to_string(),
+        outline_thickness: 1,
+        plc_word: None,
+        source: None,
+    });
+
+    let issues = validate_document(&doc);
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].rule, "image-overlap");
+    assert_eq!(issues[0].severity, Severity::Warning);
+    assert!(check_document(&doc).is_ok());
+}
+
+#[test]
+fn test_check_document_lists_errors() {
+    let doc = VcpDocument {
+        buttons: vec![button(1, 1, "a"), button(1, 1, "b"), button(0, 1, "c")],
+        ..Default::default()
+    };
+
+    let error = check_document(&doc).unwrap_err();
+
+    assert!(error.starts_with("Skin has 2 validation error(s)"));
+    assert!(error.contains("Button 'b' overlaps Button 'a'"));
+    assert!(error.contains("Button 'c'"));
+}
@@ -1,14 +1,14 @@
 import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { open, save } from "@tauri-apps/plugin-dialog";
+import { ask, open, save } from "@tauri-apps/plugin-dialog";
 import { Store } from "@tauri-apps/plugin-store";
 import { getCurrentWindow, LogicalPosition, LogicalSize } from "@tauri-apps/api/window";
 import { dirname, homeDir, join } from "@tauri-apps/api/path";
-import { listen } from "@tauri-apps/api/event";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { copyFile, mkdir } from "@tauri-apps/plugin-fs";
 import "./App.css";
 import "./components/AboutDialog.css";
-import { VcpDocument, Selection } from "./types";
+import { VcpDocument, Selection, LoadedDocument, HistoryList, OpenedDocument, DocumentInfo, ImportReport, DeployReport, ExportReport } from "./types";
 import VcpGrid from "./components/VcpGrid";
 import Inspector from "./components/Inspector";
 import Toolbar from "./components/Toolbar";
@@ -18,9 +18,9 @@ import SettingsDialog from "./components/SettingsDialog";
 import ButtonEditorModal from "./components/ButtonEditorModal";
 import AboutDialog from "./components/AboutDialog";
 import ErrorBoundary from "./components/ErrorBoundary";
-import { UndoRedoManager } from "./undoRedo";
 import { AppSettings, defaultSettings } from "./settingsTypes";
 import { useMenu, Menu } from "./utils/MenuService";
+import { documentEdits, editLabel } from "./utils/documentEdits";
 
 interface WindowState {
   x: number;
@@ -84,14 +84,42 @@ function App() {
 
   const versionInfo = getVersionInfo(appVersion);
 
-  const undoRedoManager = useRef(new UndoRedoManager());
   const store = useRef<Store | null>(null);
   const windowSaveTimeout = useRef<number | null>(null);
+  // Id of the backend document this window edits
+  const documentId = useRef<number | null>(null);
 
   const showNotification = (message: string, type: NotificationType = 'info') => {
     setNotification({ message, type });
   };
 
+  // Log parser diagnostics and return the number of warnings/errors
+  const reportDiagnostics = (loaded: LoadedDocument): number => {
+    const problems = loaded.diagnostics.filter(d => d.severity !== 'info');
+    problems.forEach(d => console.warn(`Line ${d.line}, column ${d.column}: ${d.message}`));
+    loaded.button_errors.forEach(e => console.warn(`Button ${e.button}: ${e.message}`));
+    return problems.length + loaded.button_errors.length;
+  };
+
+  // Remember the id for the rest of the session so a reload finds the same document
+  const bindDocument = (id: number) => {
+    documentId.current = id;
+    sessionStorage.setItem('documentId', String(id));
+  };
+
+  // Start a blank skin in this window's document (or a new one on first use)
+  const newDocument = async (): Promise<VcpDocument> => {
+    const opened = await invoke<OpenedDocument>("new_document", { documentId: documentId.current });
+    bindDocument(opened.id);
+    return opened.document;
+  };
+
+  const openDocument = async (path: string): Promise<LoadedDocument> => {
+    const opened = await invoke<OpenedDocument>("open_file", { path, documentId: documentId.current });
+    bindDocument(opened.id);
+    return opened;
+  };
+
   const saveLastFilePath = async (path: string | null) => {
     if (store.current) {
       try {
@@ -160,8 +188,10 @@ function App() {
           setSettings(savedSettings);
         }
 
-        // Restore window position and size
-        const windowState = await store.current.get<WindowState>('windowState');
+        // Restore window position and size; only the main window keeps them
+        const windowState = appWindow.label === 'main'
+          ? await store.current.get<WindowState>('windowState')
+          : null;
         if (windowState) {
           await appWindow.setPosition(new LogicalPosition(windowState.x, windowState.y));
           await appWindow.setSize(new LogicalSize(windowState.width, windowState.height));
@@ -173,6 +203,24 @@ function App() {
         // Show the window after state is restored
         await appWindow.show();
 
+        // Windows opened for a document carry its id in the URL, and a reloaded
+        // webview finds it in the session; the backend still holds the history
+        const boundId = new URLSearchParams(window.location.search).get('document')
+          ?? sessionStorage.getItem('documentId');
+        if (boundId) {
+          try {
+            const info = await invoke<DocumentInfo>('get_document_info', { documentId: Number(boundId) });
+            bindDocument(info.id);
+            setDocument(await invoke<VcpDocument>('get_document', { documentId: info.id }));
+            setCurrentFilePath(info.path);
+            setSelection(null);
+            await refreshHistory();
+            return;
+          } catch (error) {
+            console.warn('Document of this window is no longer open:', error);
+          }
+        }
+
         // Try to open last file
         const lastFilePath = await store.current.get<string>('lastFilePath');
         if (lastFilePath && typeof lastFilePath === 'string') {
@@ -214,14 +262,13 @@ function App() {
               }
             }
 
-            const doc = await invoke<VcpDocument>("open_file", { path: finalFilePath });
-            setDocument(doc);
+            const loaded = await openDocument(finalFilePath);
+            reportDiagnostics(loaded);
+            setDocument(loaded.document);
             setCurrentFilePath(finalFilePath);
             setIsDirty(false);
             setSelection(null);
-            undoRedoManager.current.clear();
-            undoRedoManager.current.markAsSaved();
-            updateUndoRedoState();
+            await refreshHistory();
             await saveLastFilePath(finalFilePath);
             showNotification('Last file reopened', 'success');
             console.log('Last file opened successfully');
@@ -231,37 +278,32 @@ function App() {
             await store.current.set('lastFilePath', null);
             await store.current.save();
             // Start with new document instead
-            const newDoc = await invoke<VcpDocument>("new_document");
+            const newDoc = await newDocument();
             setDocument(newDoc);
             setCurrentFilePath(null);
             setIsDirty(false);
             setSelection(null);
-            undoRedoManager.current.clear();
-            undoRedoManager.current.markAsSaved();
-            updateUndoRedoState();
+            await refreshHistory();
           }
         } else {
           // No last file, create new document
-          const newDoc = await invoke<VcpDocument>("new_document");
+          const newDoc = await newDocument();
           setDocument(newDoc);
           setCurrentFilePath(null);
           setIsDirty(false);
           setSelection(null);
-          undoRedoManager.current.clear();
-          undoRedoManager.current.markAsSaved();
-          updateUndoRedoState();
+          await refreshHistory();
         }
       } catch (error) {
         console.error('Failed to load settings:', error);
         // Still create a new document on error
         try {
-          const newDoc = await invoke<VcpDocument>("new_document");
+          const newDoc = await newDocument();
           setDocument(newDoc);
           setCurrentFilePath(null);
           setIsDirty(false);
           setSelection(null);
-          undoRedoManager.current.clear();
-          updateUndoRedoState();
+          await refreshHistory();
         } catch (e) {
           console.error('Failed to create new document:', e);
         }
@@ -293,7 +335,7 @@ function App() {
     const appWindow = getCurrentWindow();
 
     const saveWindowState = async () => {
-      if (!store.current) return;
+      if (!store.current || appWindow.label !== 'main') return;
 
       try {
         const position = await appWindow.outerPosition();
@@ -399,22 +441,27 @@ function App() {
     };
   }, [isDirty, showSettingsDialog, showButtonEditor]);
 
-  // Listen for menu events
+  // Listen for menu events; the backend sends them to the focused window
   useEffect(() => {
     const setupMenuListeners = async () => {
-      const unlistenNew = await listen('menu-new', () => handleNew());
-      const unlistenOpen = await listen('menu-open', () => handleOpen());
-      const unlistenSave = await listen('menu-save', () => handleSave());
-      const unlistenSaveAs = await listen('menu-save-as', () => handleSaveAs());
-      const unlistenExport = await listen('menu-export-cnc', () => handleExportToCNC());
-      const unlistenPrint = await listen('menu-print', () => handlePrint());
-      const unlistenAbout = await listen('menu-about', () => {
+      const appWindow = getCurrentWebviewWindow();
+      const unlistenNew = await appWindow.listen('menu-new', () => handleNew());
+      const unlistenNewWindow = await appWindow.listen('menu-new-window', () => handleNewWindow());
+      const unlistenOpen = await appWindow.listen('menu-open', () => handleOpen());
+      const unlistenSave = await appWindow.listen('menu-save', () => handleSave());
+      const unlistenSaveAs = await appWindow.listen('menu-save-as', () => handleSaveAs());
+      const unlistenExport = await appWindow.listen('menu-export-cnc', () => handleExportToCNC());
+      const unlistenImport = await appWindow.listen('menu-import-cnc', () => handleImportFromCNC());
+      const unlistenDeploy = await appWindow.listen('menu-deploy-cnc', () => handleDeployToCNC());
+      const unlistenRollback = await appWindow.listen('menu-rollback-deploy', () => handleRollbackDeploy());
+      const unlistenPrint = await appWindow.listen('menu-print', () => handlePrint());
+      const unlistenAbout = await appWindow.listen('menu-about', () => {
         console.log('menu-about event received');
         setShowAboutDialog(true);
       });
-      const unlistenSettings = await listen('menu-settings', () => setShowSettingsDialog(true));
-      const unlistenRefreshImages = await listen('menu-refresh-images', () => setImageCacheBuster(Date.now()));
-      const unlistenZoomIn = await listen('menu-zoom-in', () => {
+      const unlistenSettings = await appWindow.listen('menu-settings', () => setShowSettingsDialog(true));
+      const unlistenRefreshImages = await appWindow.listen('menu-refresh-images', () => setImageCacheBuster(Date.now()));
+      const unlistenZoomIn = await appWindow.listen('menu-zoom-in', () => {
         setSettings(prev => {
           const newZoom = Math.min(prev.grid.cellZoom + 10, 200);
           return {
@@ -426,7 +473,7 @@ function App() {
           };
         });
       });
-      const unlistenZoomOut = await listen('menu-zoom-out', () => {
+      const unlistenZoomOut = await appWindow.listen('menu-zoom-out', () => {
         setSettings(prev => {
           const newZoom = Math.max(prev.grid.cellZoom - 10, 50);
           return {
@@ -438,7 +485,7 @@ function App() {
           };
         });
       });
-      const unlistenToggleGrid = await listen('menu-toggle-grid', () => {
+      const unlistenToggleGrid = await appWindow.listen('menu-toggle-grid', () => {
         setSettings(prev => ({
           ...prev,
           grid: {
@@ -447,14 +494,18 @@ function App() {
           }
         }));
       });
-      const unlistenQuit = await listen('menu-quit', () => handleQuit());
+      const unlistenQuit = await appWindow.listen('menu-quit', () => handleQuit());
 
       return () => {
         unlistenNew();
+        unlistenNewWindow();
         unlistenOpen();
         unlistenSave();
         unlistenSaveAs();
         unlistenExport();
+        unlistenImport();
+        unlistenDeploy();
+        unlistenRollback();
         unlistenPrint();
         unlistenAbout();
         unlistenSettings();
@@ -505,19 +556,27 @@ function App() {
     if (!await checkUnsavedChanges('new')) return;
 
     try {
-      const newDoc = await invoke<VcpDocument>("new_document");
+      const newDoc = await newDocument();
       setDocument(newDoc);
       setCurrentFilePath(null);
       setIsDirty(false);
       setSelection(null);
-      undoRedoManager.current.clear();
-      updateUndoRedoState();
+      await refreshHistory();
       await saveLastFilePath(null);
     } catch (error) {
       console.error("Failed to create new document:", error);
     }
   };
 
+  const handleNewWindow = async () => {
+    try {
+      await invoke<number>('open_document_window', { path: null });
+    } catch (error) {
+      console.error('Failed to open new window:', error);
+      showNotification(`Failed to open new window: ${error}`, 'error');
+    }
+  };
+
   const handleOpen = async () => {
     if (showSettingsDialog || showButtonEditor) return;
     if (!await checkUnsavedChanges('open')) return;
@@ -588,16 +647,19 @@ function App() {
           }
         }
 
-        const doc = await invoke<VcpDocument>("open_file", { path: finalFilePath });
-        setDocument(doc);
+        const loaded = await openDocument(finalFilePath);
+        const problemCount = reportDiagnostics(loaded);
+        setDocument(loaded.document);
         setCurrentFilePath(finalFilePath);
         setIsDirty(false);
         setSelection(null);
-        undoRedoManager.current.clear();
-        undoRedoManager.current.markAsSaved();
-        updateUndoRedoState();
+        await refreshHistory();
         await saveLastFilePath(finalFilePath);
-        showNotification('File opened successfully', 'success');
+        if (problemCount > 0) {
+          showNotification(`File opened with ${problemCount} warning(s), see console for details`, 'warning');
+        } else {
+          showNotification('File opened successfully', 'success');
+        }
       }
     } catch (error) {
       console.error("Failed to open file:", error);
@@ -637,10 +699,10 @@ function App() {
         path = savePath;
       }
 
-      await invoke("save_file_command", { path, doc: document });
+      await invoke("save_file_command", { documentId: documentId.current, path });
       setCurrentFilePath(path);
       setIsDirty(false);
-      undoRedoManager.current.markAsSaved();
+      await refreshHistory();
       await saveLastFilePath(path);
       showNotification('File saved successfully', 'success');
       return true;
@@ -680,10 +742,10 @@ function App() {
 
       if (!savePath) return;
 
-      await invoke("save_file_command", { path: savePath, doc: document });
+      await invoke("save_file_command", { documentId: documentId.current, path: savePath });
       setCurrentFilePath(savePath);
       setIsDirty(false);
-      undoRedoManager.current.markAsSaved();
+      await refreshHistory();
       await saveLastFilePath(savePath);
       showNotification('File saved successfully', 'success');
     } catch (error) {
@@ -720,23 +782,149 @@ function App() {
         return;
       }
 
-      // Generate VCP XML content
-      const vcpXml = await invoke<string>('serialize_vcp_document', { doc: document });
-
-      // Call Rust export command with path remapping
-      const result = await invoke<string>('export_to_cnc', {
+      // Call Rust export command with path remapping; formatting follows the project settings
+      const report = await invoke<ExportReport>('export_to_cnc', {
         vcpResourcesFolder: settings.files.vcpResourcesFolder,
         cncBasePath: settings.files.cncBasePath,
-        vcpContent: vcpXml
+        doc: document,
+        sourcePath: currentFilePath,
       });
 
-      showNotification(`VCP package exported successfully to: ${result}`, 'success');
+      if (report.asset_issues.length > 0) {
+        console.warn('Asset issues:', report.asset_issues);
+        showNotification(`VCP package exported to ${report.zip} with ${report.asset_issues.length} missing or unusable asset(s), see console for details`, 'warning');
+      } else {
+        showNotification(`VCP package exported successfully to: ${report.zip}`, 'success');
+      }
     } catch (error) {
       console.error('Export failed:', error);
       showNotification(`Export failed: ${error}`, 'error');
     }
   };
 
+  const handleDeployToCNC = async () => {
+    if (showSettingsDialog || showButtonEditor || !document) return;
+
+    try {
+      if (!settings.files.cncBasePath || settings.files.cncBasePath.trim() === '') {
+        showNotification('Please configure CNC base path in Settings > Files before deploying', 'error');
+        setShowSettingsDialog(true);
+        return;
+      }
+      if (!settings.files.vcpResourcesFolder || settings.files.vcpResourcesFolder.trim() === '') {
+        showNotification('Please configure VCP resources folder in Settings > Files before deploying', 'error');
+        setShowSettingsDialog(true);
+        return;
+      }
+
+      // The CNC's vcp folder as reached from here, e.g. on a mounted share or USB stick
+      const deployFolder = await open({
+        directory: true,
+        defaultPath: settings.files.cncBasePath,
+        title: 'Choose the CNC vcp folder to deploy to',
+      });
+      if (!deployFolder) return;
+
+      const report = await invoke<DeployReport>('deploy_to_cnc', {
+        vcpResourcesFolder: settings.files.vcpResourcesFolder,
+        cncBasePath: settings.files.cncBasePath,
+        deployFolder: deployFolder as string,
+        doc: document,
+        sourcePath: currentFilePath,
+      });
+
+      const backup = report.backup ? `, previous folder kept as ${report.backup}` : '';
+      if (report.asset_issues.length > 0) {
+        console.warn('Asset issues:', report.asset_issues);
+        showNotification(`Deployed to ${report.target}${backup}, with ${report.asset_issues.length} missing or unusable asset(s), see console for details`, 'warning');
+      } else {
+        showNotification(`Deployed to ${report.target}${backup}`, 'success');
+      }
+    } catch (error) {
+      console.error('Deploy failed:', error);
+      showNotification(`Deploy failed: ${error}`, 'error');
+    }
+  };
+
+  const handleRollbackDeploy = async () => {
+    if (showSettingsDialog || showButtonEditor) return;
+
+    try {
+      const deployFolder = await open({
+        directory: true,
+        defaultPath: settings.files.cncBasePath || undefined,
+        title: 'Choose the CNC vcp folder to roll back',
+      });
+      if (!deployFolder) return;
+
+      const backups = await invoke<string[]>('list_deploy_backups', { deployFolder });
+      if (backups.length === 0) {
+        showNotification(`No backups of ${deployFolder} to roll back to`, 'info');
+        return;
+      }
+      // Older backups can be restored with vcp-cli rollback
+      const confirmed = await ask(
+        `Replace ${deployFolder} with the backup ${backups[0]}? The current folder is kept as a backup.`,
+        { title: 'Roll Back Deploy', kind: 'warning' },
+      );
+      if (!confirmed) return;
+
+      const report = await invoke<DeployReport>('rollback_deploy_command', {
+        deployFolder,
+        backup: backups[0],
+      });
+      showNotification(`Restored ${report.restored} to ${report.target}`, 'success');
+    } catch (error) {
+      console.error('Rollback failed:', error);
+      showNotification(`Rollback failed: ${error}`, 'error');
+    }
+  };
+
+  const handleImportFromCNC = async () => {
+    if (showSettingsDialog || showButtonEditor) return;
+    if (!await checkUnsavedChanges('open')) return;
+
+    try {
+      // Imports go to the work in progress folder, like skins opened from the resources folder
+      if (!settings.files.defaultSaveLocation || settings.files.defaultSaveLocation.trim() === '') {
+        showNotification('Please configure the work in progress folder in Settings > Files before importing', 'error');
+        setShowSettingsDialog(true);
+        return;
+      }
+
+      const source = await open({
+        filters: [{ name: "VCP Export", extensions: ["zip"] }],
+        defaultPath: settings.files.cncBasePath || undefined,
+      });
+      if (!source) return;
+
+      const report = await invoke<ImportReport>('import_from_cnc', {
+        source: source as string,
+        destination: settings.files.defaultSaveLocation,
+        overwrite: false,
+      });
+      console.log('Imported from CNC:', report);
+
+      // Open the first imported skin
+      const skin = report.files.find(file => file.startsWith('skins/') && file.toLowerCase().endsWith('.vcp'));
+      if (skin) {
+        const skinPath = await join(report.root, skin);
+        const loaded = await openDocument(skinPath);
+        reportDiagnostics(loaded);
+        setDocument(loaded.document);
+        setCurrentFilePath(skinPath);
+        setIsDirty(false);
+        setSelection(null);
+        await refreshHistory();
+        await saveLastFilePath(skinPath);
+      }
+      showNotification(`Imported ${report.files.length} file(s), ${report.rewritten.length} image path(s) made relative`, 'success');
+    } catch (error) {
+      console.error('Import failed:', error);
+      showNotification(`Import failed: ${error}`, 'error');
+    }
+  };
+
   const handleQuit = async () => {
     if (showSettingsDialog || showButtonEditor) return;
     if (isDirty) {
@@ -781,11 +969,6 @@ function App() {
         showNotification('Failed to save settings', 'error');
       }
     }
-
-    // Update undo manager max size if changed
-    if (newSettings.editor.undoHistoryDepth !== settings.editor.undoHistoryDepth) {
-      undoRedoManager.current = new UndoRedoManager(newSettings.editor.undoHistoryDepth);
-    }
   };
 
   const handleSettingsChange = async (newSettings: AppSettings) => {
@@ -802,44 +985,71 @@ function App() {
     }
   };
 
-  const updateDocument = (updatedDoc: VcpDocument, skipHistory = false) => {
-    if (!skipHistory && document) {
-      undoRedoManager.current.pushState(document);
-      updateUndoRedoState();
+  // The backend keeps the undo history; show what it allows
+  const applyHistory = (history: HistoryList) => {
+    setCanUndo(history.position > 0);
+    setCanRedo(history.position < history.entries.length);
+    setIsDirty(history.saved_position !== history.position);
+  };
+
+  const refreshHistory = async () => {
+    if (documentId.current === null) return;
+    try {
+      applyHistory(await invoke<HistoryList>('get_edit_history', { documentId: documentId.current }));
+    } catch (error) {
+      console.error('Failed to read edit history:', error);
     }
+  };
+
+  const updateDocument = (updatedDoc: VcpDocument, skipHistory = false) => {
+    const previousDoc = document;
     setDocument(updatedDoc);
     setIsDirty(true);
     setImageCacheBuster(Date.now()); // Force image refresh
-  };
+    if (!previousDoc) return;
 
-  const handleUndo = () => {
-    if (!document) return;
-    const previousDoc = undoRedoManager.current.undo(document);
-    if (previousDoc) {
-      setDocument(previousDoc);
-      setSelection({ type: 'empty' }); // Clear selection since elements may have changed
-      // Check if we've undone back to the saved state
-      const isAtSaved = undoRedoManager.current.isAtSavedState();
-      setIsDirty(!isAtSaved);
-      updateUndoRedoState();
+    let record: Promise<void>;
+    if (skipHistory) {
+      record = invoke('update_document', { documentId: documentId.current, doc: updatedDoc }).then(refreshHistory);
+    } else {
+      const edits = documentEdits(previousDoc, updatedDoc);
+      if (edits.length === 0) return;
+      record = invoke<HistoryList>('apply_edits', { documentId: documentId.current, edits, label: editLabel(edits) }).then(applyHistory);
     }
+    record.catch(async (error) => {
+      // Keep the backend in step even if the history could not follow
+      console.error('Failed to record edit:', error);
+      await invoke('update_document', { documentId: documentId.current, doc: updatedDoc });
+      await refreshHistory();
+    });
   };
 
-  const handleRedo = () => {
+  const handleUndo = async () => {
     if (!document) return;
-    const nextDoc = undoRedoManager.current.redo(document);
-    if (nextDoc) {
-      setDocument(nextDoc);
-      setSelection({ type: 'empty' }); // Clear selection since elements may have changed
-      // Redo always makes the document dirty (we're moving away from saved state)
-      setIsDirty(true);
-      updateUndoRedoState();
+    try {
+      const previousDoc = await invoke<VcpDocument | null>('undo_edit', { documentId: documentId.current });
+      if (previousDoc) {
+        setDocument(previousDoc);
+        setSelection({ type: 'empty' }); // Clear selection since elements may have changed
+      }
+    } catch (error) {
+      console.error('Failed to undo:', error);
     }
+    await refreshHistory();
   };
 
-  const updateUndoRedoState = () => {
-    setCanUndo(undoRedoManager.current.canUndo());
-    setCanRedo(undoRedoManager.current.canRedo());
+  const handleRedo = async () => {
+    if (!document) return;
+    try {
+      const nextDoc = await invoke<VcpDocument | null>('redo_edit', { documentId: documentId.current });
+      if (nextDoc) {
+        setDocument(nextDoc);
+        setSelection({ type: 'empty' }); // Clear selection since elements may have changed
+      }
+    } catch (error) {
+      console.error('Failed to redo:', error);
+    }
+    await refreshHistory();
   };
 
   // Check if a cell is occupied by a button or image
@@ -1255,12 +1465,13 @@ function App() {
                 };
 
                 // Save the document first
-                await invoke("save_file_command", { path: currentFilePath, doc: updatedDoc });
+                await invoke("save_file_command", { documentId: documentId.current, path: currentFilePath, doc: updatedDoc });
                 setIsDirty(false);
 
                 // Now reload to get updated default_image field
-                const reloadedDoc = await invoke<VcpDocument>("open_file", { path: currentFilePath });
-                setDocument(reloadedDoc);
+                const reloaded = await openDocument(currentFilePath);
+                setDocument(reloaded.document);
+                await refreshHistory();
                 setImageCacheBuster(Date.now()); // Force image refresh
                 showNotification(`Button "${buttonName}" saved`, 'success');
               }
@@ -31,55 +31,79 @@ export interface VcpButtonDefinition {
     value: string;
   };
   app?: string;        // Application to launch
+
+  extra?: ButtonExtra[];  // Comments and unrecognised elements, kept verbatim by the backend
+}
+
+// A comment or unrecognised element, written after the known element `after`
+export interface ButtonExtra {
+  after?: string;  // absent: before all known elements
+  markup: string;
 }
 
-// Generate VCP button XML from definition
+// Generate VCP button XML from definition (mirrors serialize_button_xml in the backend)
 export function generateButtonXML(button: VcpButtonDefinition): string {
   let xml = '<vcp_button>\n';
+  const extras = (after?: string) => (button.extra ?? [])
+    .filter(extra => extra.after === after)
+    .map(extra => `  ${extra.markup}\n`)
+    .join('');
+  xml += extras(undefined);
   
   if (button.skinEventNum !== undefined) {
     xml += `  <skin_event_num>${button.skinEventNum}</skin_event_num>\n`;
   }
+  xml += extras('skin_event_num');
+  
+  if (button.defaultImage) {
+    xml += `  <default_image>${escapeXml(button.defaultImage)}</default_image>\n`;
+  }
+  xml += extras('default_image');
   
   if (button.onClickSwap) {
-    xml += `  <on_click_swap>${button.onClickSwap}</on_click_swap>\n`;
+    xml += `  <on_click_swap>${escapeXml(button.onClickSwap)}</on_click_swap>\n`;
   }
+  xml += extras('on_click_swap');
   
   if (button.run) {
     xml += `  <run>\n`;
     xml += `    <${button.run.type}>${escapeXml(button.run.value)}</${button.run.type}>\n`;
     xml += `  </run>\n`;
   }
+  xml += extras('run');
   
   if (button.app) {
     xml += `  <app>${escapeXml(button.app)}</app>\n`;
   }
+  xml += extras('app');
   
   if (button.plcOutput) {
     xml += '  <plc_output>\n';
     xml += `    <number>${button.plcOutput.number}</number>\n`;
-    xml += `    <color_on>${button.plcOutput.colorOn}</color_on>\n`;
-    xml += `    <color_off>${button.plcOutput.colorOff}</color_off>\n`;
+    xml += `    <color_on>${escapeXml(button.plcOutput.colorOn)}</color_on>\n`;
+    xml += `    <color_off>${escapeXml(button.plcOutput.colorOff)}</color_off>\n`;
     if (button.plcOutput.imageOn) {
-      xml += `    <image_on>${button.plcOutput.imageOn}</image_on>\n`;
+      xml += `    <image_on>${escapeXml(button.plcOutput.imageOn)}</image_on>\n`;
     }
     if (button.plcOutput.imageOff) {
-      xml += `    <image_off>${button.plcOutput.imageOff}</image_off>\n`;
+      xml += `    <image_off>${escapeXml(button.plcOutput.imageOff)}</image_off>\n`;
     }
     xml += '  </plc_output>\n';
   }
+  xml += extras('plc_output');
   
   if (button.plcInput) {
     xml += '  <plc_input>\n';
     xml += `    <number>${button.plcInput.number}</number>\n`;
     if (button.plcInput.imageActive) {
-      xml += `    <image_active>${button.plcInput.imageActive}</image_active>\n`;
+      xml += `    <image_active>${escapeXml(button.plcInput.imageActive)}</image_active>\n`;
     }
     if (button.plcInput.imageInactive) {
-      xml += `    <image_inactive>${button.plcInput.imageInactive}</image_inactive>\n`;
+      xml += `    <image_inactive>${escapeXml(button.plcInput.imageInactive)}</image_inactive>\n`;
     }
     xml += '  </plc_input>\n';
   }
+  xml += extras('plc_input');
   
   xml += '</vcp_button>';
   return xml;
@@ -95,86 +119,6 @@ function escapeXml(str: string): string {
     .replace(/'/g, '&apos;');
 }
 
-// Parse VCP button XML to definition
-export function parseButtonXML(xml: string): VcpButtonDefinition {
-  const parser = new DOMParser();
-  const doc = parser.parseFromString(xml, 'text/xml');
-  
-  const button: Partial<VcpButtonDefinition> = {
-    name: '',
-    defaultImage: '',
-  };
-  
-  const skinEventNum = doc.querySelector('skin_event_num')?.textContent;
-  if (skinEventNum) {
-    button.skinEventNum = parseInt(skinEventNum);
-  }
-  
-  const onClickSwap = doc.querySelector('on_click_swap')?.textContent;
-  if (onClickSwap) {
-    button.onClickSwap = onClickSwap;
-  }
-  
-  const runEl = doc.querySelector('run');
-  if (runEl) {
-    const lineEl = runEl.querySelector('line');
-    const macroEl = runEl.querySelector('macro');
-    
-    if (lineEl && lineEl.textContent) {
-      button.run = {
-        type: 'line',
-        value: lineEl.textContent
-      };
-    } else if (macroEl && macroEl.textContent) {
-      button.run = {
-        type: 'macro',
-        value: macroEl.textContent
-      };
-    }
-  }
-  
-  const app = doc.querySelector('app')?.textContent;
-  if (app) {
-    button.app = app;
-  }
-  
-  const plcOutputEl = doc.querySelector('plc_output');
-  if (plcOutputEl) {
-    const number = plcOutputEl.querySelector('number')?.textContent;
-    const colorOn = plcOutputEl.querySelector('color_on')?.textContent || '#EC1C24';
-    const colorOff = plcOutputEl.querySelector('color_off')?.textContent || '#81151C';
-    const imageOn = plcOutputEl.querySelector('image_on')?.textContent;
-    const imageOff = plcOutputEl.querySelector('image_off')?.textContent;
-    
-    if (number) {
-      button.plcOutput = {
-        number: parseInt(number),
-        colorOn: colorOn,
-        colorOff: colorOff,
-        imageOn: imageOn || undefined,
-        imageOff: imageOff || undefined,
-      };
-    }
-  }
-  
-  const plcInputEl = doc.querySelector('plc_input');
-  if (plcInputEl) {
-    const number = plcInputEl.querySelector('number')?.textContent;
-    const imageActive = plcInputEl.querySelector('image_active')?.textContent;
-    const imageInactive = plcInputEl.querySelector('image_inactive')?.textContent;
-    
-    if (number) {
-      button.plcInput = {
-        number: parseInt(number),
-        imageActive: imageActive || undefined,
-        imageInactive: imageInactive || undefined,
-      };
-    }
-  }
-  
-  return button as VcpButtonDefinition;
-}
-
 // Determine PLC behavior type from button definition
 export function getPlcBehaviorType(button: VcpButtonDefinition): 'none' | 'plc_output' | 'plc_input' {
   if (button.plcOutput) return 'plc_output';
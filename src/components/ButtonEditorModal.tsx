@@ -3,8 +3,9 @@ import { invoke } from '@tauri-apps/api/core';
 import { open } from '@tauri-apps/plugin-dialog';
 import { join } from '@tauri-apps/api/path';
 import { toAssetUrl, getButtonAssetUrl } from '../utils/assetPaths';
-import { VcpButtonDefinition, createDefaultButton, generateButtonXML, validateButton, parseButtonXML } from '../buttonDefinition';
-import { SKIN_EVENTS, PLC_INPUTS } from '../vcpEventData';
+import { VcpButtonDefinition, createDefaultButton, generateButtonXML, validateButton } from '../buttonDefinition';
+import { SKIN_EVENTS, PLC_INPUTS, SkinEvent, PlcInput } from '../vcpEventData';
+import { CatalogEntry, SymbolCatalog } from '../types';
 import './ButtonEditorModal.css';
 
 interface ButtonEditorModalProps {
@@ -62,6 +63,21 @@ export default function ButtonEditorModal({ onClose, onSave, vcpResourcesFolder,
   const [previewState, setPreviewState] = useState<'default' | 'pressed' | 'output-on' | 'output-off' | 'input-active' | 'input-inactive'>('default');
   const [splitRatio, setSplitRatio] = useState(50); // Percentage for left panel width
   const [isResizing, setIsResizing] = useState(false);
+  const [skinEvents, setSkinEvents] = useState<SkinEvent[]>(SKIN_EVENTS);
+  const [plcInputs, setPlcInputs] = useState<PlcInput[]>(PLC_INPUTS);
+  const [plcOutputs, setPlcOutputs] = useState<CatalogEntry[]>([]);
+
+  // Dropdowns come from the project's symbol catalog; the built-in lists are the fallback
+  useEffect(() => {
+    if (!vcpResourcesFolder) return;
+    invoke<SymbolCatalog>('get_symbol_catalog', { vcpResourcesFolder })
+      .then(catalog => {
+        setSkinEvents(catalog.skin_events.map(e => ({ id: e.number, name: e.name, displayName: e.label })));
+        setPlcInputs(catalog.inputs.map(i => ({ number: i.number, name: i.name, displayName: i.label })));
+        setPlcOutputs(catalog.outputs);
+      })
+      .catch(error => console.warn('Failed to load symbol catalog, using built-in lists:', error));
+  }, [vcpResourcesFolder]);
 
   // Handle ESC key to close modal (acts as Cancel)
   useEffect(() => {
@@ -143,23 +159,18 @@ export default function ButtonEditorModal({ onClose, onSave, vcpResourcesFolder,
       // If button has XML file, load it
       if (existingButton.file) {
         console.log('Loading XML for button:', existingButton.name);
-        const xml = await invoke<string>('load_button_xml', {
-          vcpResourcesFolder,
+        const def = await invoke<VcpButtonDefinition>('load_button_definition', {
+          basePath: vcpResourcesFolder,
           buttonName: existingButton.name,
         });
-
-        console.log('Loaded XML:', xml);
-
-        // Parse XML to button definition
-        const def = parseButtonXML(xml);
-        console.log('Parsed definition:', def);
+        console.log('Loaded definition:', def);
 
         // Set name and default image from existing button
         def.name = existingButton.name;
         def.defaultImage = `${existingButton.name}.svg`;
 
         setButtonDef(def);
-        setXmlPreview(xml);
+        setXmlPreview(generateButtonXML(def));
         setWarnings(validateButton(def));
 
         // Load preview image
@@ -208,30 +219,21 @@ export default function ButtonEditorModal({ onClose, onSave, vcpResourcesFolder,
 
       // Check if XML file already exists for this button
       let def: VcpButtonDefinition;
-      let xmlContent: string;
 
       try {
-        const xml = await invoke<string>('load_button_xml', {
+        // Load existing definition for button
+        def = await invoke<VcpButtonDefinition>('load_button_definition', {
           basePath,
           buttonName: sanitizedName,
         });
-
-        // found existing XML for button
-
-        // Parse existing XML
-        def = parseButtonXML(xml);
-        def.name = sanitizedName;
         def.defaultImage = `${sanitizedName}.svg`;
-        xmlContent = xml;
       } catch (error) {
         // No existing XML, create default
-        // creating default XML for button
         def = createDefaultButton(sanitizedName);
-        xmlContent = generateButtonXML(def);
       }
 
       setButtonDef(def);
-      setXmlPreview(xmlContent);
+      setXmlPreview(generateButtonXML(def));
       setWarnings(validateButton(def));
 
       // Check if SVG already exists and set preview
@@ -291,11 +293,9 @@ export default function ButtonEditorModal({ onClose, onSave, vcpResourcesFolder,
         setWarnings(validateButton(updated));
 
         // Save XML file to button folder immediately
-        const xml = generateButtonXML(updated);
-        await invoke('save_button_xml', {
+        await invoke('save_button_definition', {
           buttonFolder,
-          buttonName: sanitizedName,
-          xmlContent: xml,
+          button: { ...updated, name: sanitizedName },
         });
 
         // Update preview with cache-busting timestamp to force reload
@@ -345,11 +345,9 @@ export default function ButtonEditorModal({ onClose, onSave, vcpResourcesFolder,
 
     try {
       // Save button XML
-      const xml = generateButtonXML(buttonDef);
-      await invoke('save_button_xml', {
+      await invoke('save_button_definition', {
         buttonFolder,
-        buttonName: sanitizedName,
-        xmlContent: xml,
+        button: { ...buttonDef, name: sanitizedName },
       });
 
       onSave(sanitizedName);
@@ -645,7 +643,7 @@ export default function ButtonEditorModal({ onClose, onSave, vcpResourcesFolder,
                     }}
                   >
                     <option value="">None</option>
-                    {[...SKIN_EVENTS].sort((a, b) => a.displayName.localeCompare(b.displayName)).map(event => (
+                    {[...skinEvents].sort((a, b) => a.displayName.localeCompare(b.displayName)).map(event => (
                       <option key={event.id} value={event.id}>
                         {event.displayName}
                       </option>
@@ -857,6 +855,7 @@ export default function ButtonEditorModal({ onClose, onSave, vcpResourcesFolder,
                       <label>Output Number:</label>
                       <input
                         type="number"
+                        list="plc-output-numbers"
                         value={buttonDef.plcOutput.number}
                         onChange={(e) => {
                           const updated = {
@@ -869,6 +868,13 @@ export default function ButtonEditorModal({ onClose, onSave, vcpResourcesFolder,
                         }}
                         min="1"
                       />
+                      <datalist id="plc-output-numbers">
+                        {plcOutputs.map(output => (
+                          <option key={output.number} value={output.number}>
+                            {output.label}
+                          </option>
+                        ))}
+                      </datalist>
                     </div>
                     <div className="editor-section">
                       <label>LED Color ON:</label>
@@ -1071,7 +1077,7 @@ export default function ButtonEditorModal({ onClose, onSave, vcpResourcesFolder,
                           setWarnings(validateButton(updated));
                         }}
                       >
-                        {[...PLC_INPUTS].sort((a, b) => a.displayName.localeCompare(b.displayName)).map(input => (
+                        {[...plcInputs].sort((a, b) => a.displayName.localeCompare(b.displayName)).map(input => (
                           <option key={input.number} value={input.number}>
                             {input.displayName} ({input.number})
                           </option>
@@ -164,12 +164,6 @@ export default function SettingsDialog({ settings, onSave, onCancel, onChange }:
               <div className="settings-section">
                 <h3>Editor Settings</h3>
 
-                <div className="setting-row">
-                  <label className="noninteractive">Undo history depth</label>
-                  <input type="number" min={5} max={50} value={localSettings.editor.undoHistoryDepth} onChange={(e) => updateEditorSetting('undoHistoryDepth', parseInt(e.target.value) || 5)} />
-                  <span className="help-text">Number of undo steps to remember (5-50)</span>
-                </div>
-
                 <div className="setting-row">
                   <label className="noninteractive">Auto-save interval</label>
                   <select value={String(localSettings.editor.autoSaveInterval)} onChange={(e) => updateEditorSetting('autoSaveInterval', parseInt(e.target.value))}>
@@ -16,7 +16,6 @@ export interface DisplaySettings {
 }
 
 export interface EditorSettings {
-  undoHistoryDepth: number; // 5-50
   autoSaveInterval: number; // 0 = off, or minutes
   confirmBeforeDelete: boolean;
   externalSvgEditor: string; // Path to external SVG editor
@@ -51,7 +50,6 @@ export const defaultSettings: AppSettings = {
     theme: 'system',
   },
   editor: {
-    undoHistoryDepth: 5,
     autoSaveInterval: 0,
     confirmBeforeDelete: false,
     externalSvgEditor: '',
@@ -1,3 +1,5 @@
+import { VcpButtonDefinition } from './buttonDefinition';
+
 export interface PlcWord {
   number: number;
   color: string;
@@ -19,6 +21,7 @@ export interface Border {
   outline_color: string;
   outline_thickness: number;
   plc_word?: PlcWord;
+  source?: string;  // Original markup, kept so unchanged elements save byte-for-byte
 }
 
 export interface Image {
@@ -27,6 +30,7 @@ export interface Image {
   row_span: number;
   column_span: number;
   path: string;
+  source?: string;
 }
 
 export interface Button {
@@ -37,6 +41,8 @@ export interface Button {
   name: string;
   file?: string;  // XML file reference (empty if not configured yet)
   default_image?: string;  // Actual image filename from button XML
+  definition?: VcpButtonDefinition;  // Full button XML, resolved when the skin is opened
+  source?: string;
 }
 
 export interface OnClick {
@@ -58,13 +64,255 @@ export interface VcpDocument {
   borders: Border[];
   images: Image[];
   buttons: Button[];
+  layout?: unknown;  // Formatting and comments of the loaded file, owned by the backend
+  format?: FileFormat;  // Encoding and line endings of the loaded file
+}
+
+export interface SerializeOptions {
+  line_ending: 'lf' | 'crlf' | null;  // null leaves line breaks as they are
+  indent: string;
+  declaration: boolean;
+  attribute_quote: 'double' | 'single';
+  element_order: 'original' | 'sorted';  // sorted: top to bottom, then left to right
+}
+
+export interface ProjectSettings {
+  output: SerializeOptions;
+  validation: ValidationSettings;
+  machine: MachineProfile;  // skins are exported for this machine unless told otherwise
+  cnc_base_path?: string | null;  // for exports that do not name one; absent: CNC12's own
+}
+
+// Controller and machine type; exported as e.g. oak_lathe_vcp_skin.vcp
+export interface MachineProfile {
+  controller: 'acorn' | 'acornsix' | 'oak' | 'hickory';
+  machine: 'mill' | 'lathe' | 'router' | 'plasma';
+}
+
+// Name of an exported skin in skins/ on the CNC
+export type SkinTarget = { machine: MachineProfile } | { file: string };
+
+// Contents of vcp/.vcp-manifest.json in every export
+export interface ExportManifest {
+  version: number;
+  editor_version: string;
+  created: string;             // RFC 3339, UTC
+  source: string;              // VCP root exported from
+  cnc_base_path: string;
+  skins: { file: string; source: string | null }[];
+  options: SerializeOptions;
+  files: { path: string; size: number; sha256: string }[];
+}
+
+// Result of verify_export_command
+export interface VerifyReport {
+  manifest: ExportManifest;
+  issues: { kind: 'missing' | 'modified' | 'unexpected'; path: string }[];
+}
+
+// Result of export_to_cnc
+export interface ExportReport {
+  zip: string;
+  asset_issues: AssetIssue[];  // not empty only when they do not block exports
+}
+
+// Result of deploy_to_cnc and rollback_deploy_command
+export interface DeployReport {
+  target: string;
+  backup: string | null;    // where the replaced folder was moved
+  restored: string | null;  // backup put back, when rolling back
+  asset_issues: AssetIssue[];  // found by the export, when deploying
+}
+
+// Result of import_from_cnc; paths are relative to root
+export interface ImportReport {
+  root: string;
+  files: string[];
+  rewritten: { skin: string; from: string; to: string }[];
+}
+
+// Another skin of the project to include in export_to_cnc
+export interface ExportSkin {
+  source: string;            // absolute or relative to the VCP root
+  target?: SkinTarget | null;  // null: the project's machine
+}
+
+// Whether save and export refuse skins with validation errors
+export interface ValidationSettings {
+  block_save: boolean;
+  block_export: boolean;
+}
+
+// Result of validate_document
+export interface Issue {
+  rule: string;  // e.g. 'out-of-grid', 'button-overlap'
+  severity: DiagnosticSeverity;
+  element?: ElementRef;
+  message: string;
+}
+
+// Result of check_project_assets
+export interface AssetIssue {
+  kind: 'missing' | 'unreadable' | 'empty';
+  path: string;           // relative to the VCP root
+  referenced_by: string;  // skin, button definition or button folder
+  message: string;
+}
+
+// Result of analyze_skin_events
+export interface EventIssue {
+  rule: string;  // e.g. 'duplicate-event', 'reserved-event'
+  severity: DiagnosticSeverity;
+  event?: number;
+  buttons: string[];
+  message: string;
+}
+
+// Whole project as one JSON/YAML file (export_project_file, import_project_file),
+// see docs/PROJECT_FORMAT.md
+export interface VcpProject {
+  version: number;
+  skins: { file: string; document: VcpDocument }[];
+  buttons: VcpButtonDefinition[];
+}
+
+export interface FileFormat {
+  encoding: 'utf8' | 'utf16le' | 'utf16be' | 'windows1252';
+  bom: boolean;
+  line_ending: 'lf' | 'crlf';
 }
 
 export type ElementType = 'border' | 'image' | 'button' | 'empty';
 
+export type ElementKind = Exclude<ElementType, 'empty'>;
+
+export interface ElementRef {
+  kind: ElementKind;
+  index: number;
+}
+
+export type Element =
+  | ({ kind: 'border' } & Border)
+  | ({ kind: 'image' } & Image)
+  | ({ kind: 'button' } & Button);
+
+// One undoable change, applied by the backend (apply_edits)
+export type EditCommand =
+  | { type: 'add_element'; element: Element; index?: number }
+  | { type: 'delete_element'; kind: ElementKind; index: number }
+  | { type: 'move_element'; kind: ElementKind; index: number; row: number; column: number }
+  | { type: 'resize_element'; kind: ElementKind; index: number; row_span: number | null; column_span: number | null }
+  | { type: 'set_property'; element?: ElementRef; name: string; value: unknown }  // no element: skin property
+  | { type: 'resize_grid'; row_count: number; column_count: number };
+
+// Undo history kept by the backend (get_edit_history)
+export interface HistoryList {
+  entries: string[];            // step labels, oldest first
+  position: number;             // steps applied; later entries can be redone
+  saved_position: number | null;
+}
+
 export interface Selection {
   type: ElementType;
   index?: number;
   row?: number;
   column?: number;
 }
+
+export type DiagnosticSeverity = 'error' | 'warning' | 'info';
+
+export interface ParseDiagnostic {
+  line: number;
+  column: number;
+  severity: DiagnosticSeverity;
+  message: string;
+}
+
+export interface ButtonLoadError {
+  button: string;
+  message: string;
+}
+
+export interface LoadedDocument {
+  document: VcpDocument;
+  diagnostics: ParseDiagnostic[];
+  button_errors: ButtonLoadError[];
+}
+
+// A document the backend just created or opened, with the id later commands take
+export interface OpenedDocument extends LoadedDocument {
+  id: number;
+}
+
+export interface DocumentInfo {
+  id: number;
+  path: string | null;          // null until saved
+  dirty: boolean;
+  window: string | null;        // label of the window editing it
+}
+
+// Result of load_plc_source: definitions read from a PLC program or SkinEventsList.txt
+export interface SkinEventDefinition {
+  name: string;
+  number: number;
+  row?: number;     // from a '; Row N Column M' comment
+  column?: number;
+  commented: boolean;
+  line: number;
+}
+
+export interface PlcSymbol {
+  name: string;
+  kind: 'out' | 'inp' | 'mem';
+  number: number;
+  comment?: string;
+  commented: boolean;
+  line: number;
+}
+
+export interface PlcDefinitions {
+  skin_events: SkinEventDefinition[];
+  symbols: PlcSymbol[];
+  diagnostics: ParseDiagnostic[];
+}
+
+// Result of cross_check_plc: skin buttons compared with a machine's PLC program
+export interface ButtonBinding {
+  button: string;
+  number: number;
+}
+
+export interface PlcCrossCheck {
+  undefined_events: ButtonBinding[];    // no 'IS SV_SKIN_EVENT_n' in the PLC
+  undefined_outputs: ButtonBinding[];   // no 'IS OUTn'
+  undeclared_inputs: ButtonBinding[];   // no 'IS INPn'
+  untriggered_events: SkinEventDefinition[];
+  unresolved_buttons: string[];         // no button definition to check
+}
+
+// Skin events, LED outputs and inputs offered by the button editor (get_symbol_catalog)
+export interface CatalogEntry {
+  number: number;
+  name: string;   // PLC symbol, e.g. 'SkinCoolFlood_M'
+  label: string;
+}
+
+export interface SymbolCatalog {
+  replaces_defaults: boolean;
+  skin_events: CatalogEntry[];
+  outputs: CatalogEntry[];
+  inputs: CatalogEntry[];
+}
+
+export interface CatalogListDiff {
+  added: CatalogEntry[];
+  removed: CatalogEntry[];
+  renamed: [CatalogEntry, CatalogEntry][];  // [before, after]
+}
+
+// Result of refresh_symbol_catalog
+export interface CatalogDiff {
+  skin_events: CatalogListDiff;
+  outputs: CatalogListDiff;
+  inputs: CatalogListDiff;
+}
@@ -0,0 +1,124 @@
+import { Border, Button, EditCommand, Element, ElementKind, Image, VcpDocument } from "../types";
+
+type AnyElement = Border | Image | Button;
+
+// Fields each kind keeps its position and size in
+const POSITION: Record<ElementKind, [string, string]> = {
+  border: ['row_start', 'column_start'],
+  image: ['row_start', 'column_start'],
+  button: ['row', 'column'],
+};
+const SPANS = ['row_span', 'column_span'];
+
+const same = (a: unknown, b: unknown): boolean =>
+  a === b || JSON.stringify(a) === JSON.stringify(b);
+
+/**
+ * Describe the change from `before` to `after` as backend edit commands, so
+ * components can keep handing over whole documents while the undo history
+ * only stores what changed.
+ */
+export function documentEdits(before: VcpDocument, after: VcpDocument): EditCommand[] {
+  const edits: EditCommand[] = [];
+
+  if (before.row_count !== after.row_count || before.column_count !== after.column_count) {
+    edits.push({ type: 'resize_grid', row_count: after.row_count, column_count: after.column_count });
+  }
+  for (const name of ['background', 'on_click', 'on_hover'] as const) {
+    if (!same(before[name], after[name])) {
+      edits.push({ type: 'set_property', name, value: after[name] ?? null });
+    }
+  }
+
+  edits.push(...listEdits('border', before.borders, after.borders));
+  edits.push(...listEdits('image', before.images, after.images));
+  edits.push(...listEdits('button', before.buttons, after.buttons));
+  return edits;
+}
+
+function listEdits(kind: ElementKind, before: AnyElement[], after: AnyElement[]): EditCommand[] {
+  if (before === after) return [];
+
+  // Toolbar actions add or remove a single element; find it past the common prefix
+  let start = 0;
+  while (start < before.length && start < after.length && same(before[start], after[start])) {
+    start++;
+  }
+  if (after.length === before.length + 1 && after.slice(start + 1).every((el, i) => same(el, before[start + i]))) {
+    return [addElement(kind, after[start], start)];
+  }
+  if (after.length === before.length - 1 && after.slice(start).every((el, i) => same(el, before[start + i + 1]))) {
+    return [{ type: 'delete_element', kind, index: start }];
+  }
+
+  if (after.length !== before.length) {
+    // Anything else: replace the elements from the first difference on
+    const edits: EditCommand[] = [];
+    for (let index = before.length - 1; index >= start; index--) {
+      edits.push({ type: 'delete_element', kind, index });
+    }
+    after.slice(start).forEach((element, i) => edits.push(addElement(kind, element, start + i)));
+    return edits;
+  }
+
+  const edits: EditCommand[] = [];
+  for (let index = start; index < after.length; index++) {
+    if (same(before[index], after[index])) continue;
+    edits.push(...elementEdits(kind, index, before[index], after[index]));
+  }
+  return edits;
+}
+
+function elementEdits(kind: ElementKind, index: number, before: AnyElement, after: AnyElement): EditCommand[] {
+  const edits: EditCommand[] = [];
+  const old = before as unknown as Record<string, unknown>;
+  const now = after as unknown as Record<string, unknown>;
+  const [rowField, columnField] = POSITION[kind];
+
+  if (old[rowField] !== now[rowField] || old[columnField] !== now[columnField]) {
+    edits.push({ type: 'move_element', kind, index, row: now[rowField] as number, column: now[columnField] as number });
+  }
+  if (old.row_span !== now.row_span || old.column_span !== now.column_span) {
+    edits.push({
+      type: 'resize_element',
+      kind,
+      index,
+      row_span: (now.row_span as number | undefined) ?? null,
+      column_span: (now.column_span as number | undefined) ?? null,
+    });
+  }
+
+  const names = new Set([...Object.keys(old), ...Object.keys(now)]);
+  for (const name of names) {
+    if (name === rowField || name === columnField || SPANS.includes(name) || name === 'source') continue;
+    if (!same(old[name], now[name])) {
+      edits.push({ type: 'set_property', element: { kind, index }, name, value: now[name] ?? null });
+    }
+  }
+  return edits;
+}
+
+function addElement(kind: ElementKind, element: AnyElement, index: number): EditCommand {
+  return { type: 'add_element', element: { ...element, kind } as Element, index };
+}
+
+/** Short description of a step for the Edit menu. */
+export function editLabel(edits: EditCommand[]): string {
+  const kinds = new Set(edits.map(edit => edit.type));
+  const edit = edits[0];
+  if (kinds.size > 1 || !edit) return 'Edit';
+  switch (edit.type) {
+    case 'add_element':
+      return `Add ${edit.element.kind}`;
+    case 'delete_element':
+      return `Delete ${edit.kind}`;
+    case 'move_element':
+      return `Move ${edit.kind}`;
+    case 'resize_element':
+      return `Resize ${edit.kind}`;
+    case 'set_property':
+      return `Change ${edit.name.replace(/_/g, ' ')}`;
+    case 'resize_grid':
+      return 'Resize grid';
+  }
+}